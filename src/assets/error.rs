@@ -0,0 +1,23 @@
+use std::fmt;
+
+// Why an asset couldn't be loaded
+#[derive(Debug, Clone, PartialEq)]
+pub enum AssetError {
+    NotFound(String),
+    Io { path: String, message: String },
+    Decode { path: String, message: String }, // Image couldn't be decoded or turned into a texture
+    Parse { path: String, message: String },  // RON/CSV data is malformed
+}
+
+impl fmt::Display for AssetError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AssetError::NotFound(path) => write!(f, "Asset not found: {}", path),
+            AssetError::Io { path, message } => write!(f, "Failed to read {}: {}", path, message),
+            AssetError::Decode { path, message } => write!(f, "Failed to decode {}: {}", path, message),
+            AssetError::Parse { path, message } => write!(f, "Failed to parse {}: {}", path, message),
+        }
+    }
+}
+
+impl std::error::Error for AssetError {}
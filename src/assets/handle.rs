@@ -0,0 +1,168 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::marker::PhantomData;
+use std::rc::Rc;
+
+// Typed reference to an asset owned by the AssetServer. Cloning a handle bumps the
+// asset's reference count; once every handle is dropped the asset can be unloaded.
+pub struct Handle<T> {
+    id: u64,
+    refs: Rc<()>,
+    marker: PhantomData<fn() -> T>,
+}
+
+// Implemented by hand so handles are cloneable/comparable whatever T is
+impl<T> Clone for Handle<T> {
+    fn clone(&self) -> Self {
+        Handle {
+            id: self.id,
+            refs: Rc::clone(&self.refs),
+            marker: PhantomData,
+        }
+    }
+}
+
+impl<T> PartialEq for Handle<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.id == other.id
+    }
+}
+
+impl<T> Eq for Handle<T> {}
+
+impl<T> Hash for Handle<T> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.id.hash(state);
+    }
+}
+
+impl<T> fmt::Debug for Handle<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Handle({})", self.id)
+    }
+}
+
+struct AssetEntry<T> {
+    path: String,
+    asset: T,
+    refs: Rc<()>, // Kept here to mint new handles; live handles = strong count - 1
+}
+
+// Storage for one asset type, keyed by handle id and deduplicated by path
+pub struct Assets<T> {
+    entries: HashMap<u64, AssetEntry<T>>,
+    ids: HashMap<String, u64>,
+    next_id: u64,
+}
+
+impl<T> Assets<T> {
+    pub fn new() -> Self {
+        Assets {
+            entries: HashMap::new(),
+            ids: HashMap::new(),
+            next_id: 0,
+        }
+    }
+
+    // Handle to an already loaded asset
+    pub fn find(&self, path: &str) -> Option<Handle<T>> {
+        let id = *self.ids.get(path)?;
+        let entry = self.entries.get(&id)?;
+        Some(Handle {
+            id,
+            refs: Rc::clone(&entry.refs),
+            marker: PhantomData,
+        })
+    }
+
+    pub fn insert(&mut self, path: &str, asset: T) -> Handle<T> {
+        let id = self.next_id;
+        self.next_id += 1;
+
+        let refs = Rc::new(());
+        let handle = Handle {
+            id,
+            refs: Rc::clone(&refs),
+            marker: PhantomData,
+        };
+        self.entries.insert(id, AssetEntry { path: path.to_string(), asset, refs });
+        self.ids.insert(path.to_string(), id);
+        handle
+    }
+
+    pub fn get(&self, handle: &Handle<T>) -> Option<&T> {
+        self.entries.get(&handle.id).map(|entry| &entry.asset)
+    }
+
+    // Swap in a freshly loaded version; every existing handle sees the new asset
+    pub fn replace(&mut self, path: &str, asset: T) -> bool {
+        match self.ids.get(path).and_then(|id| self.entries.get_mut(id)) {
+            Some(entry) => {
+                entry.asset = asset;
+                true
+            },
+            None => false,
+        }
+    }
+
+    pub fn contains(&self, path: &str) -> bool {
+        self.ids.contains_key(path)
+    }
+
+    pub fn paths(&self) -> Vec<String> {
+        self.ids.keys().cloned().collect()
+    }
+
+    pub fn values_mut(&mut self) -> impl Iterator<Item = &mut T> {
+        self.entries.values_mut().map(|entry| &mut entry.asset)
+    }
+
+    // Drop every asset nothing holds a handle to; returns the paths that were unloaded
+    pub fn remove_unused(&mut self) -> Vec<String> {
+        let unused: Vec<u64> = self.entries.iter()
+            .filter(|(_, entry)| Rc::strong_count(&entry.refs) == 1)
+            .map(|(id, _)| *id)
+            .collect();
+
+        let mut removed = Vec::new();
+        for id in unused {
+            if let Some(entry) = self.entries.remove(&id) {
+                self.ids.remove(&entry.path);
+                removed.push(entry.path);
+            }
+        }
+        removed
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn dropping_the_last_handle_lets_the_asset_be_removed() {
+        let mut assets = Assets::new();
+        let handle = assets.insert("a.png", 1);
+        let clone = handle.clone();
+        assert!(assets.remove_unused().is_empty());
+
+        drop(handle);
+        assert!(assets.remove_unused().is_empty());
+        assert_eq!(assets.get(&clone), Some(&1));
+
+        drop(clone);
+        assert_eq!(assets.remove_unused(), vec!["a.png".to_string()]);
+        assert!(!assets.contains("a.png"));
+        assert!(assets.find("a.png").is_none());
+    }
+
+    #[test]
+    fn handles_found_by_path_keep_the_asset_alive() {
+        let mut assets = Assets::new();
+        drop(assets.insert("a.png", 1));
+        let found = assets.find("a.png").unwrap();
+        assert!(assets.remove_unused().is_empty());
+        assert_eq!(assets.get(&found), Some(&1));
+    }
+}
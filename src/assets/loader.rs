@@ -0,0 +1,141 @@
+use super::{read_entity_definitions, read_level_definition, read_tilemap_data, AssetError, TilemapData};
+use crate::components::texture::{decode_image, DecodedImage};
+use crate::entity_definitions::EntityDefinitions;
+use crate::level_definitions::LevelDefinition;
+use crate::vfs;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::mpsc::{channel, Receiver, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+
+const MAX_WORKERS: usize = 4;
+
+// Work a loader thread can do without touching the renderer
+#[derive(Clone, Debug)]
+pub enum LoadJob {
+    Image(String),
+    Tilemap(String),
+    EntityDefinitions(String),
+    Level(String),
+}
+
+impl LoadJob {
+    pub fn path(&self) -> &str {
+        match self {
+            LoadJob::Image(path) => path,
+            LoadJob::Tilemap(path) => path,
+            LoadJob::EntityDefinitions(path) => path,
+            LoadJob::Level(path) => path,
+        }
+    }
+}
+
+// CPU-side result of a job, finished into a real asset on the main thread
+pub enum LoadedData {
+    Image(DecodedImage),
+    Tilemap(TilemapData),
+    EntityDefinitions(EntityDefinitions),
+    Level(LevelDefinition),
+}
+
+pub struct LoadResult {
+    pub job: LoadJob,
+    pub data: Result<LoadedData, AssetError>,
+}
+
+fn run_job(job: &LoadJob) -> Result<LoadedData, AssetError> {
+    match job {
+        LoadJob::Image(path) => {
+            if !vfs::exists(path) {
+                return Err(AssetError::NotFound(path.clone()));
+            }
+            decode_image(path).map(LoadedData::Image)
+        },
+        LoadJob::Tilemap(path) => read_tilemap_data(path).map(LoadedData::Tilemap),
+        LoadJob::EntityDefinitions(path) => read_entity_definitions(path).map(LoadedData::EntityDefinitions),
+        LoadJob::Level(path) => read_level_definition(path).map(LoadedData::Level),
+    }
+}
+
+// A panicking decoder still reports back, or its path would stay pending forever
+fn run_job_caught(job: &LoadJob) -> Result<LoadedData, AssetError> {
+    panic::catch_unwind(AssertUnwindSafe(|| run_job(job))).unwrap_or_else(|payload| {
+        let message = payload.downcast_ref::<&str>().map(|message| message.to_string())
+            .or_else(|| payload.downcast_ref::<String>().cloned())
+            .unwrap_or_else(|| "unknown panic".to_string());
+        Err(AssetError::Decode { path: job.path().to_string(), message: format!("loader panicked: {}", message) })
+    })
+}
+
+// Small pool of threads that read and decode asset files. Results are collected
+// with try_recv so the main thread never blocks on disk.
+pub struct AssetLoader {
+    jobs: Option<Sender<LoadJob>>,
+    results: Receiver<LoadResult>,
+    workers: Vec<JoinHandle<()>>,
+}
+
+impl AssetLoader {
+    pub fn new() -> Self {
+        let worker_count = thread::available_parallelism()
+            .map(|count| count.get())
+            .unwrap_or(1)
+            .clamp(1, MAX_WORKERS);
+
+        let (job_sender, job_receiver) = channel::<LoadJob>();
+        let (result_sender, results) = channel();
+        let job_receiver = Arc::new(Mutex::new(job_receiver));
+
+        let workers = (0..worker_count)
+            .map(|_| {
+                let job_receiver = Arc::clone(&job_receiver);
+                let result_sender = result_sender.clone();
+                thread::spawn(move || loop {
+                    // Hold the lock only while taking a job, not while running it
+                    let job = match job_receiver.lock() {
+                        Ok(receiver) => receiver.recv(),
+                        Err(_) => return,
+                    };
+                    let job = match job {
+                        Ok(job) => job,
+                        Err(_) => return, // Loader dropped
+                    };
+                    let data = run_job_caught(&job);
+                    if result_sender.send(LoadResult { job, data }).is_err() {
+                        return;
+                    }
+                })
+            })
+            .collect();
+
+        AssetLoader {
+            jobs: Some(job_sender),
+            results,
+            workers,
+        }
+    }
+
+    // Returns false if the job couldn't be handed to a worker (every worker has
+    // exited), so the caller doesn't wait on a result that will never arrive
+    pub fn submit(&self, job: LoadJob) -> bool {
+        match &self.jobs {
+            Some(jobs) => jobs.send(job).is_ok(),
+            None => false,
+        }
+    }
+
+    // Every result that has arrived since the last call
+    pub fn finished(&self) -> Vec<LoadResult> {
+        self.results.try_iter().collect()
+    }
+}
+
+impl Drop for AssetLoader {
+    fn drop(&mut self) {
+        // Closing the job channel lets the workers fall out of their loops
+        self.jobs = None;
+        for worker in self.workers.drain(..) {
+            let _ = worker.join();
+        }
+    }
+}
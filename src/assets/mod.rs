@@ -0,0 +1,570 @@
+// Single owner for every loaded asset. Callers get typed Handles back; the server
+// deduplicates loads by path, counts handles and unloads whatever nothing uses.
+pub mod handle;
+pub mod error;
+pub mod watcher;
+pub mod loader;
+pub mod placeholder;
+
+pub use self::error::AssetError;
+pub use self::handle::{Assets, Handle};
+use self::loader::{AssetLoader, LoadJob, LoadedData};
+use self::placeholder::{placeholder_image, PlaceholderLayout};
+use self::watcher::{FileWatcher, DEFAULT_POLL_INTERVAL};
+
+use crate::components::chunked_tilemap::ChunkedTilemap;
+use crate::components::texture::Texture;
+use crate::components::tilemap::{parse_tile_csv, TileId, Tilemap, Tileset};
+use crate::entity_definitions::{Animations, EntityDefinitions};
+use crate::level_definitions::LevelDefinition;
+use sdl2::render::TextureCreator;
+use sdl2::video::WindowContext;
+use crate::vfs;
+use std::collections::{BTreeMap, HashSet};
+
+// Raw tile grid of a level CSV. Levels edit their tiles at runtime, so each
+// Tilemap gets its own copy of this data.
+pub struct TilemapData {
+    pub tiles: Vec<Vec<TileId>>,
+}
+
+// Sprite sheets and frame counts for one entity definition. Textures are in the
+// order the render system indexes them: idle, walk, attack.
+pub struct AnimationSet<'a> {
+    pub textures: Vec<Handle<Texture<'a>>>,
+    pub idle_frames: usize,
+    pub walk_frames: usize,
+    pub attack_frames: usize,
+}
+
+pub const ANIMATION_TYPES: [&str; 3] = ["idle", "walk", "attack"];
+
+const ANIMATION_KEY_PREFIX: &str = "animations/";
+
+// Frame size RenderSystem slices sprite sheets with
+pub const SPRITE_FRAME_SIZE: u32 = 100;
+
+// A watched file that changed on disk and has been reloaded in place
+#[derive(Clone, Debug, PartialEq)]
+pub enum AssetChange {
+    Texture(String),
+    Tilemap(String),
+    EntityDefinitions(String),
+    File(String), // Registered with watch_file but not a loaded asset (e.g. a level .ron)
+}
+
+pub struct AssetServer<'a> {
+    texture_creator: &'a TextureCreator<WindowContext>,
+    textures: Assets<Texture<'a>>,
+    tilesets: Assets<Tileset<'a>>,
+    tilemaps: Assets<TilemapData>,
+    animations: Assets<AnimationSet<'a>>,
+    entity_definitions: Assets<EntityDefinitions>,
+    levels: Assets<LevelDefinition>,
+    watcher: FileWatcher,
+    watched_files: HashSet<String>, // Watched on request rather than because they're loaded
+    loader: AssetLoader,
+    pending: HashSet<String>, // Paths queued on the loader that haven't come back yet
+    queued_count: usize,      // Jobs queued since the loader was last idle
+    finished_count: usize,
+    missing: BTreeMap<String, AssetError>, // Replaced by placeholders since the last report
+    // What background loads brought in, held until release_warmed so unload_unused
+    // doesn't throw it away before the level that asked for it is spawned
+    warmed_textures: Vec<Handle<Texture<'a>>>,
+    warmed_tilemaps: Vec<Handle<TilemapData>>,
+}
+
+fn read_file(path: &str) -> Result<String, AssetError> {
+    vfs::read_to_string(path)
+}
+
+fn read_tilemap_data(path: &str) -> Result<TilemapData, AssetError> {
+    let bytes = vfs::read(path)?;
+    let tiles = parse_tile_csv(bytes.as_slice())
+        .map_err(|message| AssetError::Parse { path: path.to_string(), message })?;
+    Ok(TilemapData { tiles })
+}
+
+fn read_entity_definitions(path: &str) -> Result<EntityDefinitions, AssetError> {
+    let ron_str = read_file(path)?;
+    EntityDefinitions::parse(&ron_str)
+        .map_err(|message| AssetError::Parse { path: path.to_string(), message })
+}
+
+fn read_level_definition(path: &str) -> Result<LevelDefinition, AssetError> {
+    let ron_str = read_file(path)?;
+    ron::from_str(&ron_str)
+        .map_err(|e| AssetError::Parse { path: path.to_string(), message: e.to_string() })
+}
+
+impl<'a> AssetServer<'a> {
+    pub fn new(texture_creator: &'a TextureCreator<WindowContext>) -> Self {
+        AssetServer {
+            texture_creator,
+            textures: Assets::new(),
+            tilesets: Assets::new(),
+            tilemaps: Assets::new(),
+            animations: Assets::new(),
+            entity_definitions: Assets::new(),
+            levels: Assets::new(),
+            watcher: FileWatcher::new(DEFAULT_POLL_INTERVAL),
+            watched_files: HashSet::new(),
+            loader: AssetLoader::new(),
+            pending: HashSet::new(),
+            queued_count: 0,
+            finished_count: 0,
+            missing: BTreeMap::new(),
+            warmed_textures: Vec::new(),
+            warmed_tilemaps: Vec::new(),
+        }
+    }
+
+    pub fn load_texture(&mut self, path: &str) -> Result<Handle<Texture<'a>>, AssetError> {
+        let path = vfs::normalize(path);
+        if let Some(handle) = self.textures.find(&path) {
+            return Ok(handle);
+        }
+
+        let texture = self.read_texture(&path)?;
+        self.watcher.watch(&path);
+        Ok(self.textures.insert(&path, texture))
+    }
+
+    fn read_texture(&self, path: &str) -> Result<Texture<'a>, AssetError> {
+        if !vfs::exists(path) {
+            return Err(AssetError::NotFound(path.to_string()));
+        }
+        Texture::new(self.texture_creator, path)
+    }
+
+    // The same image sliced with different tile sizes is a different tileset
+    pub fn load_tileset(&mut self, path: &str, tile_width: u32, tile_height: u32) -> Result<Handle<Tileset<'a>>, AssetError> {
+        let key = format!("{}#{}x{}", vfs::normalize(path), tile_width, tile_height);
+        if let Some(handle) = self.tilesets.find(&key) {
+            return Ok(handle);
+        }
+
+        let texture = self.load_texture_or_placeholder(path, PlaceholderLayout::tileset(tile_width, tile_height))?;
+        let query = self.textures.get(&texture)
+            .map(|texture| texture.handle.query())
+            .ok_or_else(|| AssetError::NotFound(path.to_string()))?;
+        let tileset = Tileset::new(texture, query.width, query.height, tile_width, tile_height);
+        Ok(self.tilesets.insert(&key, tileset))
+    }
+
+    pub fn load_tilemap(&mut self, path: &str) -> Result<Handle<TilemapData>, AssetError> {
+        let path = vfs::normalize(path);
+        if let Some(handle) = self.tilemaps.find(&path) {
+            return Ok(handle);
+        }
+
+        let data = read_tilemap_data(&path)?;
+        self.watcher.watch(&path);
+        Ok(self.tilemaps.insert(&path, data))
+    }
+
+    pub fn load_entity_definitions(&mut self, path: &str) -> Result<Handle<EntityDefinitions>, AssetError> {
+        let path = vfs::normalize(path);
+        if let Some(handle) = self.entity_definitions.find(&path) {
+            return Ok(handle);
+        }
+
+        let definitions = read_entity_definitions(&path)?;
+        self.watcher.watch(&path);
+        Ok(self.entity_definitions.insert(&path, definitions))
+    }
+
+    pub fn load_level_definition(&mut self, path: &str) -> Result<Handle<LevelDefinition>, AssetError> {
+        let path = vfs::normalize(path);
+        if let Some(handle) = self.levels.find(&path) {
+            return Ok(handle);
+        }
+
+        let level = read_level_definition(&path)?;
+        Ok(self.levels.insert(&path, level))
+    }
+
+    // Animation sets are keyed by entity name. A sheet that fails to load gets a
+    // placeholder so the entity still spawns with every animation in its slot.
+    pub fn load_animation_set(&mut self, entity_name: &str, animations: &Animations) -> Result<Handle<AnimationSet<'a>>, AssetError> {
+        let key = format!("{}{}", ANIMATION_KEY_PREFIX, entity_name);
+        if let Some(handle) = self.animations.find(&key) {
+            return Ok(handle);
+        }
+
+        let animation_set = self.build_animation_set(animations)?;
+        Ok(self.animations.insert(&key, animation_set))
+    }
+
+    fn build_animation_set(&mut self, animations: &Animations) -> Result<AnimationSet<'a>, AssetError> {
+        // Exactly one texture per animation type, since RenderSystem indexes them by position
+        let mut textures = Vec::new();
+        for animation in animations.in_order() {
+            let layout = PlaceholderLayout::sprite_sheet(SPRITE_FRAME_SIZE, SPRITE_FRAME_SIZE, animation.frames);
+            textures.push(self.load_texture_or_placeholder(&animation.texture, layout)?);
+        }
+
+        Ok(AnimationSet {
+            textures,
+            idle_frames: animations.idle.frames,
+            walk_frames: animations.walk.frames,
+            attack_frames: animations.attack.frames,
+        })
+    }
+
+    // Like load_texture, but a missing or undecodable image is swapped for a generated
+    // placeholder and noted for report_missing. The path stays watched, so the real
+    // file replaces the placeholder as soon as it shows up.
+    pub fn load_texture_or_placeholder(&mut self, path: &str, layout: PlaceholderLayout) -> Result<Handle<Texture<'a>>, AssetError> {
+        match self.load_texture(path) {
+            Ok(handle) => Ok(handle),
+            Err(error) => self.placeholder(&vfs::normalize(path), error, layout),
+        }
+    }
+
+    fn placeholder(&mut self, key: &str, error: AssetError, layout: PlaceholderLayout) -> Result<Handle<Texture<'a>>, AssetError> {
+        if let Some(handle) = self.textures.find(key) {
+            return Ok(handle);
+        }
+
+        let mut image = placeholder_image(key, layout);
+        let texture = Texture::from_image(self.texture_creator, key, &mut image)
+            .map_err(|message| AssetError::Decode { path: key.to_string(), message })?;
+        self.missing.insert(key.to_string(), error);
+        self.watcher.watch(key);
+        Ok(self.textures.insert(key, texture))
+    }
+
+    // Print every asset replaced by a placeholder since the last report as one list,
+    // rather than one error per use
+    pub fn report_missing(&mut self) {
+        if self.missing.is_empty() {
+            return;
+        }
+        eprintln!("{} missing asset(s), using placeholders:", self.missing.len());
+        for error in self.missing.values() {
+            eprintln!("  {}", error);
+        }
+        self.missing.clear();
+    }
+
+    // Build an editable Tilemap from a level CSV and its tileset
+    pub fn create_tilemap(&mut self, map_path: &str, tileset_path: &str, tile_width: u32, tile_height: u32) -> Result<Tilemap<'a>, AssetError> {
+        let tileset = self.load_tileset(tileset_path, tile_width, tile_height)?;
+        let data = self.load_tilemap(map_path)?;
+        let tiles = self.tilemaps.get(&data)
+            .map(|data| data.tiles.clone())
+            .ok_or_else(|| AssetError::NotFound(map_path.to_string()))?;
+        Ok(Tilemap::from_tiles(tiles, tile_width, Some(tileset)))
+    }
+
+    // Chunked worlds stream their own tiles, so only the tileset comes from here
+    pub fn create_chunked_tilemap(&mut self, world_dir: &str, tileset_path: &str, tile_width: u32, tile_height: u32) -> Result<ChunkedTilemap<'a>, AssetError> {
+        let tileset = self.load_tileset(tileset_path, tile_width, tile_height)?;
+        Ok(ChunkedTilemap::new(world_dir, tile_width, Some(tileset)))
+    }
+
+    pub fn texture(&self, handle: &Handle<Texture<'a>>) -> Option<&Texture<'a>> {
+        self.textures.get(handle)
+    }
+
+    pub fn tileset(&self, handle: &Handle<Tileset<'a>>) -> Option<&Tileset<'a>> {
+        self.tilesets.get(handle)
+    }
+
+    pub fn tilemap(&self, handle: &Handle<TilemapData>) -> Option<&TilemapData> {
+        self.tilemaps.get(handle)
+    }
+
+    pub fn animation_set(&self, handle: &Handle<AnimationSet<'a>>) -> Option<&AnimationSet<'a>> {
+        self.animations.get(handle)
+    }
+
+    pub fn entity_definitions(&self, handle: &Handle<EntityDefinitions>) -> Option<&EntityDefinitions> {
+        self.entity_definitions.get(handle)
+    }
+
+    pub fn level(&self, handle: &Handle<LevelDefinition>) -> Option<&LevelDefinition> {
+        self.levels.get(handle)
+    }
+
+    // Tileset and its texture in one lookup, for drawing tiles
+    pub fn tileset_texture(&self, handle: &Handle<Tileset<'a>>) -> Option<(&Tileset<'a>, &Texture<'a>)> {
+        let tileset = self.tilesets.get(handle)?;
+        let texture = self.textures.get(&tileset.texture)?;
+        Some((tileset, texture))
+    }
+
+    // Unload every asset without a live handle. Assets that hold handles to others
+    // (animation sets, tilesets) go first so the textures they release are freed too.
+    pub fn unload_unused(&mut self) -> usize {
+        let mut removed = Vec::new();
+        removed.extend(self.levels.remove_unused());
+        removed.extend(self.entity_definitions.remove_unused());
+        removed.extend(self.animations.remove_unused());
+        removed.extend(self.tilemaps.remove_unused());
+        removed.extend(self.tilesets.remove_unused());
+        removed.extend(self.textures.remove_unused());
+
+        for path in &removed {
+            if !self.watched_files.contains(path) {
+                self.watcher.unwatch(path);
+            }
+            println!("Unloaded asset {}", path);
+        }
+        removed.len()
+    }
+
+    // Watch a file that isn't loaded through the server, such as a level definition.
+    // Changes to it are reported as AssetChange::File.
+    pub fn watch_file(&mut self, path: &str) {
+        let path = vfs::normalize(path);
+        self.watcher.watch(&path);
+        self.watched_files.insert(path);
+    }
+
+    pub fn unwatch_file(&mut self, path: &str) {
+        let path = vfs::normalize(path);
+        self.watched_files.remove(&path);
+        let still_loaded = self.textures.contains(&path)
+            || self.tilemaps.contains(&path)
+            || self.entity_definitions.contains(&path);
+        if !still_loaded {
+            self.watcher.unwatch(&path);
+        }
+    }
+
+    // Reload every watched file that changed on disk. A file that fails to reload
+    // keeps its previous version so a half-saved edit doesn't break the game.
+    pub fn poll_changes(&mut self, delta_time: f32) -> Vec<AssetChange> {
+        let mut changes = Vec::new();
+        for path in self.watcher.poll(delta_time) {
+            match self.reload(&path) {
+                Ok(change) => {
+                    println!("Reloaded {}", path);
+                    changes.push(change);
+                },
+                Err(e) => eprintln!("Failed to reload {}: {}", path, e),
+            }
+        }
+        changes
+    }
+
+    fn reload(&mut self, path: &str) -> Result<AssetChange, AssetError> {
+        if self.textures.contains(path) {
+            let texture = self.read_texture(path)?;
+            self.textures.replace(path, texture);
+            self.missing.remove(path);
+            self.refresh_tilesets(path);
+            return Ok(AssetChange::Texture(path.to_string()));
+        }
+
+        if self.tilemaps.contains(path) {
+            self.tilemaps.replace(path, read_tilemap_data(path)?);
+            return Ok(AssetChange::Tilemap(path.to_string()));
+        }
+
+        if self.levels.contains(path) {
+            self.levels.replace(path, read_level_definition(path)?);
+            return Ok(AssetChange::File(path.to_string()));
+        }
+
+        if self.entity_definitions.contains(path) {
+            let definitions = read_entity_definitions(path)?;
+            self.refresh_animation_sets(&definitions);
+            self.entity_definitions.replace(path, definitions);
+            return Ok(AssetChange::EntityDefinitions(path.to_string()));
+        }
+
+        Ok(AssetChange::File(path.to_string()))
+    }
+
+    // A re-exported tileset image may have a different size, so recount its tiles
+    fn refresh_tilesets(&mut self, texture_path: &str) {
+        let texture = match self.textures.find(texture_path) {
+            Some(texture) => texture,
+            None => return,
+        };
+        let query = match self.textures.get(&texture) {
+            Some(loaded) => loaded.handle.query(),
+            None => return,
+        };
+
+        for tileset in self.tilesets.values_mut() {
+            if tileset.texture == texture {
+                tileset.columns = query.width / tileset.tile_width;
+                tileset.rows = query.height / tileset.tile_height;
+            }
+        }
+    }
+
+    // Rebuild cached animation sets from re-parsed definitions (new sheets, frame counts)
+    fn refresh_animation_sets(&mut self, definitions: &EntityDefinitions) {
+        for key in self.animations.paths() {
+            let entity_name = key.trim_start_matches(ANIMATION_KEY_PREFIX);
+            let animations = definitions.entities.get(entity_name)
+                .and_then(|definition| definition.animations.as_ref());
+            if let Some(animations) = animations {
+                match self.build_animation_set(animations) {
+                    Ok(animation_set) => {
+                        self.animations.replace(&key, animation_set);
+                    },
+                    Err(e) => eprintln!("Failed to rebuild animations for {}: {}", entity_name, e),
+                }
+            }
+        }
+    }
+
+    // Background loading: queue files to be read and decoded on the loader threads.
+    // Once finished they sit in the cache, so the regular load_* calls return instantly.
+    pub fn queue_texture(&mut self, path: &str) {
+        let path = vfs::normalize(path);
+        if !self.textures.contains(&path) {
+            self.queue(LoadJob::Image(path));
+        }
+    }
+
+    pub fn queue_tilemap(&mut self, path: &str) {
+        let path = vfs::normalize(path);
+        if !self.tilemaps.contains(&path) {
+            self.queue(LoadJob::Tilemap(path));
+        }
+    }
+
+    pub fn queue_entity_definitions(&mut self, path: &str) {
+        let path = vfs::normalize(path);
+        match self.entity_definitions.find(&path) {
+            // Still make sure the sprite sheets are warm
+            Some(handle) => self.queue_definition_textures(&handle),
+            None => self.queue(LoadJob::EntityDefinitions(path)),
+        }
+    }
+
+    // Queues the level file, then (once it's parsed) its tilemap and tileset
+    pub fn queue_level(&mut self, path: &str) {
+        let path = vfs::normalize(path);
+        match self.levels.find(&path) {
+            Some(handle) => self.queue_level_contents(&handle),
+            None => self.queue(LoadJob::Level(path)),
+        }
+    }
+
+    fn queue(&mut self, job: LoadJob) {
+        if self.pending.contains(job.path()) {
+            return;
+        }
+        // Progress restarts from zero for each new batch
+        if self.pending.is_empty() {
+            self.queued_count = 0;
+            self.finished_count = 0;
+        }
+        let path = job.path().to_string();
+        if !self.loader.submit(job) {
+            eprintln!("Failed to queue {}: no loader threads left", path);
+            return;
+        }
+        self.pending.insert(path);
+        self.queued_count += 1;
+    }
+
+    fn queue_definition_textures(&mut self, handle: &Handle<EntityDefinitions>) {
+        let paths: Vec<String> = match self.entity_definitions.get(handle) {
+            Some(definitions) => definitions.entities.values()
+                .filter_map(|definition| definition.animations.as_ref())
+                .flat_map(|animations| animations.in_order().map(|animation| animation.texture.clone()))
+                .collect(),
+            None => return,
+        };
+        for path in paths {
+            self.queue_texture(&path);
+        }
+    }
+
+    fn queue_level_contents(&mut self, handle: &Handle<LevelDefinition>) {
+        let (tilemap, tileset) = match self.levels.get(handle) {
+            // A streamed world's chunks are read as the camera reaches them
+            Some(level) => (level.world.is_none().then(|| level.tilemap.clone()), level.tileset.clone()),
+            None => return,
+        };
+        if let Some(tilemap) = tilemap {
+            self.queue_tilemap(&tilemap);
+        }
+        self.queue_texture(&tileset);
+    }
+
+    pub fn is_loading(&self) -> bool {
+        !self.pending.is_empty()
+    }
+
+    // Let go of what background loads brought in; from here on it stays loaded only
+    // while something holds a handle to it
+    pub fn release_warmed(&mut self) {
+        self.warmed_textures.clear();
+        self.warmed_tilemaps.clear();
+    }
+
+    // Fraction of the current batch that has finished, 0.0 to 1.0
+    pub fn progress(&self) -> f32 {
+        if self.queued_count == 0 {
+            return 1.0;
+        }
+        self.finished_count as f32 / self.queued_count as f32
+    }
+
+    // Turn finished loader jobs into assets. Textures are created here because the
+    // renderer may only be touched from the main thread.
+    pub fn process_loaded(&mut self) {
+        for result in self.loader.finished() {
+            let path = result.job.path().to_string();
+            self.pending.remove(&path);
+            self.finished_count += 1;
+
+            let data = match result.data {
+                Ok(data) => data,
+                Err(_) => {
+                    // The synchronous load retries when the asset is used, and reports
+                    // it (or swaps in a placeholder) then
+                    continue;
+                },
+            };
+
+            match data {
+                LoadedData::Image(mut image) => {
+                    if self.textures.contains(&path) {
+                        continue;
+                    }
+                    match Texture::from_image(self.texture_creator, &path, &mut image) {
+                        Ok(texture) => {
+                            self.warmed_textures.push(self.textures.insert(&path, texture));
+                            self.watcher.watch(&path);
+                        },
+                        Err(message) => eprintln!("Background load failed: {}", AssetError::Decode { path, message }),
+                    }
+                },
+                LoadedData::Tilemap(data) => {
+                    if !self.tilemaps.contains(&path) {
+                        self.warmed_tilemaps.push(self.tilemaps.insert(&path, data));
+                        self.watcher.watch(&path);
+                    }
+                },
+                LoadedData::EntityDefinitions(definitions) => {
+                    let handle = match self.entity_definitions.find(&path) {
+                        Some(handle) => handle,
+                        None => {
+                            self.watcher.watch(&path);
+                            self.entity_definitions.insert(&path, definitions)
+                        },
+                    };
+                    self.queue_definition_textures(&handle);
+                },
+                LoadedData::Level(level) => {
+                    let handle = match self.levels.find(&path) {
+                        Some(handle) => handle,
+                        None => self.levels.insert(&path, level),
+                    };
+                    self.queue_level_contents(&handle);
+                },
+            }
+        }
+    }
+}
@@ -0,0 +1,178 @@
+use crate::components::texture::DecodedImage;
+
+// Stand-in images for assets that couldn't be loaded: a magenta/black checkerboard
+// with the file name written on it, so a bad path is obvious on screen instead of
+// an entity quietly drawing the wrong sheet.
+
+const CHECKER_SIZE: u32 = 8;
+const CHECKER_LIGHT: [u8; 4] = [255, 0, 255, 255];
+const CHECKER_DARK: [u8; 4] = [40, 0, 40, 255];
+const TEXT_COLOR: [u8; 4] = [255, 255, 255, 255];
+
+const GLYPH_WIDTH: u32 = 3;
+const GLYPH_HEIGHT: u32 = 5;
+
+// Grid of cells the placeholder is drawn as, matching how the missing image would
+// have been sliced (animation frames in a row, or a tileset's tiles)
+#[derive(Clone, Copy, Debug)]
+pub struct PlaceholderLayout {
+    pub cell_width: u32,
+    pub cell_height: u32,
+    pub columns: u32,
+    pub rows: u32,
+}
+
+impl PlaceholderLayout {
+    pub fn sprite_sheet(frame_width: u32, frame_height: u32, frames: usize) -> Self {
+        PlaceholderLayout {
+            cell_width: frame_width,
+            cell_height: frame_height,
+            columns: frames.max(1) as u32,
+            rows: 1,
+        }
+    }
+
+    pub fn tileset(tile_width: u32, tile_height: u32) -> Self {
+        PlaceholderLayout {
+            cell_width: tile_width,
+            cell_height: tile_height,
+            columns: 8,
+            rows: 8,
+        }
+    }
+}
+
+// 3x5 pixel glyphs, one row per entry, most significant of the 3 bits on the left
+fn glyph(c: char) -> [u8; 5] {
+    match c.to_ascii_uppercase() {
+        'A' => [0b010, 0b101, 0b111, 0b101, 0b101],
+        'B' => [0b110, 0b101, 0b110, 0b101, 0b110],
+        'C' => [0b011, 0b100, 0b100, 0b100, 0b011],
+        'D' => [0b110, 0b101, 0b101, 0b101, 0b110],
+        'E' => [0b111, 0b100, 0b110, 0b100, 0b111],
+        'F' => [0b111, 0b100, 0b110, 0b100, 0b100],
+        'G' => [0b011, 0b100, 0b101, 0b101, 0b011],
+        'H' => [0b101, 0b101, 0b111, 0b101, 0b101],
+        'I' => [0b111, 0b010, 0b010, 0b010, 0b111],
+        'J' => [0b001, 0b001, 0b001, 0b101, 0b010],
+        'K' => [0b101, 0b101, 0b110, 0b101, 0b101],
+        'L' => [0b100, 0b100, 0b100, 0b100, 0b111],
+        'M' => [0b101, 0b111, 0b111, 0b101, 0b101],
+        'N' => [0b110, 0b101, 0b101, 0b101, 0b101],
+        'O' => [0b010, 0b101, 0b101, 0b101, 0b010],
+        'P' => [0b110, 0b101, 0b110, 0b100, 0b100],
+        'Q' => [0b010, 0b101, 0b101, 0b110, 0b011],
+        'R' => [0b110, 0b101, 0b110, 0b101, 0b101],
+        'S' => [0b011, 0b100, 0b010, 0b001, 0b110],
+        'T' => [0b111, 0b010, 0b010, 0b010, 0b010],
+        'U' => [0b101, 0b101, 0b101, 0b101, 0b111],
+        'V' => [0b101, 0b101, 0b101, 0b101, 0b010],
+        'W' => [0b101, 0b101, 0b111, 0b111, 0b101],
+        'X' => [0b101, 0b101, 0b010, 0b101, 0b101],
+        'Y' => [0b101, 0b101, 0b010, 0b010, 0b010],
+        'Z' => [0b111, 0b001, 0b010, 0b100, 0b111],
+        '0' => [0b111, 0b101, 0b101, 0b101, 0b111],
+        '1' => [0b010, 0b110, 0b010, 0b010, 0b111],
+        '2' => [0b110, 0b001, 0b010, 0b100, 0b111],
+        '3' => [0b110, 0b001, 0b010, 0b001, 0b110],
+        '4' => [0b101, 0b101, 0b111, 0b001, 0b001],
+        '5' => [0b111, 0b100, 0b110, 0b001, 0b110],
+        '6' => [0b011, 0b100, 0b111, 0b101, 0b111],
+        '7' => [0b111, 0b001, 0b010, 0b010, 0b010],
+        '8' => [0b111, 0b101, 0b111, 0b101, 0b111],
+        '9' => [0b111, 0b101, 0b111, 0b001, 0b110],
+        '-' => [0b000, 0b000, 0b111, 0b000, 0b000],
+        '.' => [0b000, 0b000, 0b000, 0b000, 0b010],
+        '_' => [0b000, 0b000, 0b000, 0b000, 0b111],
+        '(' => [0b001, 0b010, 0b010, 0b010, 0b001],
+        ')' => [0b100, 0b010, 0b010, 0b010, 0b100],
+        ' ' => [0b000; 5],
+        _ => [0b110, 0b001, 0b010, 0b000, 0b010], // '?'
+    }
+}
+
+struct Canvas {
+    width: u32,
+    height: u32,
+    pixels: Vec<u8>,
+}
+
+impl Canvas {
+    fn set(&mut self, x: u32, y: u32, color: [u8; 4]) {
+        if x < self.width && y < self.height {
+            let index = ((y * self.width + x) * 4) as usize;
+            self.pixels[index..index + 4].copy_from_slice(&color);
+        }
+    }
+
+    // Text clipped to `max_width`; returns false if not even one glyph fits
+    fn draw_text(&mut self, text: &str, x: u32, y: u32, scale: u32, max_width: u32) -> bool {
+        let advance = (GLYPH_WIDTH + 1) * scale;
+        let max_chars = (max_width / advance) as usize;
+        if max_chars == 0 {
+            return false;
+        }
+
+        for (i, c) in text.chars().take(max_chars).enumerate() {
+            let rows = glyph(c);
+            let glyph_x = x + i as u32 * advance;
+            for (row, bits) in rows.iter().enumerate() {
+                for column in 0..GLYPH_WIDTH {
+                    if bits & (1 << (GLYPH_WIDTH - 1 - column)) == 0 {
+                        continue;
+                    }
+                    for dy in 0..scale {
+                        for dx in 0..scale {
+                            self.set(glyph_x + column * scale + dx, y + row as u32 * scale + dy, TEXT_COLOR);
+                        }
+                    }
+                }
+            }
+        }
+        true
+    }
+}
+
+// Checkerboard placeholder labelled with the missing file's name. The checkerboard
+// shifts by one square per cell, so a placeholder sprite sheet visibly animates.
+pub fn placeholder_image(label: &str, layout: PlaceholderLayout) -> DecodedImage {
+    let width = layout.cell_width.max(1) * layout.columns.max(1);
+    let height = layout.cell_height.max(1) * layout.rows.max(1);
+    let mut canvas = Canvas {
+        width,
+        height,
+        pixels: vec![0; (width * height * 4) as usize],
+    };
+
+    for y in 0..height {
+        for x in 0..width {
+            let cell = x / layout.cell_width.max(1) + y / layout.cell_height.max(1);
+            let light = (x / CHECKER_SIZE + y / CHECKER_SIZE + cell).is_multiple_of(2);
+            canvas.set(x, y, if light { CHECKER_LIGHT } else { CHECKER_DARK });
+        }
+    }
+
+    // File name without directories or extension, e.g. "Orc-Idle"
+    let name = label.rsplit('/').next().unwrap_or(label);
+    let name = name.rsplit_once('.').map_or(name, |(stem, _)| stem);
+    let scale = if layout.cell_width >= 64 { 2 } else { 1 };
+    let line_height = (GLYPH_HEIGHT + 2) * scale;
+    let max_width = layout.cell_width.saturating_sub(4);
+
+    for row in 0..layout.rows.max(1) {
+        for column in 0..layout.columns.max(1) {
+            let x = column * layout.cell_width + 2;
+            let y = row * layout.cell_height + 2;
+            if canvas.draw_text("MISSING", x, y, scale, max_width) && 2 * line_height + 2 <= layout.cell_height {
+                canvas.draw_text(name, x, y + line_height, scale, max_width);
+            }
+        }
+    }
+
+    DecodedImage {
+        width,
+        height,
+        pitch: width * 4,
+        pixels: canvas.pixels,
+    }
+}
@@ -0,0 +1,60 @@
+use std::collections::HashMap;
+use std::time::SystemTime;
+
+// How often modification times are checked, in seconds
+pub const DEFAULT_POLL_INTERVAL: f32 = 0.5;
+
+// Goes through the vfs so files served from a packed archive are never reported as changed
+fn modified(path: &str) -> Option<SystemTime> {
+    crate::vfs::modified(path)
+}
+
+// Polls the modification time of a set of files. Plain polling keeps this portable
+// and dependency free; with a few dozen asset files it costs next to nothing.
+pub struct FileWatcher {
+    files: HashMap<String, Option<SystemTime>>,
+    interval: f32,
+    elapsed: f32,
+}
+
+impl FileWatcher {
+    pub fn new(interval: f32) -> Self {
+        FileWatcher {
+            files: HashMap::new(),
+            interval,
+            elapsed: 0.0,
+        }
+    }
+
+    pub fn watch(&mut self, path: &str) {
+        if !self.files.contains_key(path) {
+            self.files.insert(path.to_string(), modified(path));
+        }
+    }
+
+    pub fn unwatch(&mut self, path: &str) {
+        self.files.remove(path);
+    }
+
+    // Files whose modification time changed since the last check. Only touches the
+    // disk once per interval.
+    pub fn poll(&mut self, delta_time: f32) -> Vec<String> {
+        self.elapsed += delta_time;
+        if self.elapsed < self.interval {
+            return Vec::new();
+        }
+        self.elapsed = 0.0;
+
+        let mut changed = Vec::new();
+        for (path, last_modified) in self.files.iter_mut() {
+            let current = modified(path);
+            // A file that's briefly missing mid-save shows up as None; wait for it to come back
+            if current.is_some() && current != *last_modified {
+                *last_modified = current;
+                changed.push(path.clone());
+            }
+        }
+        changed.sort();
+        changed
+    }
+}
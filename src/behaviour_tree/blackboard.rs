@@ -0,0 +1,65 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+// Keys filled in for every behaviour-driven entity by the factory, the level loader
+// or BehaviourSystem
+pub const TARGET: &str = "target";       // Nearest hostile entity, by faction
+pub const HOME: &str = "home";           // Where the entity spawned
+pub const WAYPOINTS: &str = "waypoints"; // Patrol route from the level's spawn
+pub const LAST_SEEN: &str = "last_seen"; // Where the target was last seen or heard, for entities with a Perception
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum BlackboardValue {
+    Entity(usize),
+    Point(f32, f32),
+    Points(Vec<(f32, f32)>),
+}
+
+// Per-entity memory shared by every node of its tree. Ordered so saves come out
+// the same every time.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct Blackboard {
+    values: BTreeMap<String, BlackboardValue>,
+}
+
+impl Blackboard {
+    pub fn get(&self, key: &str) -> Option<&BlackboardValue> {
+        self.values.get(key)
+    }
+
+    pub fn set(&mut self, key: &str, value: BlackboardValue) {
+        self.values.insert(key.to_string(), value);
+    }
+
+    pub fn remove(&mut self, key: &str) {
+        self.values.remove(key);
+    }
+
+    pub fn entity(&self, key: &str) -> Option<usize> {
+        match self.values.get(key) {
+            Some(BlackboardValue::Entity(entity)) => Some(*entity),
+            _ => None,
+        }
+    }
+
+    // Entities are stored by index; see Ai::remap_entities
+    pub fn remap_entities(&mut self, map: impl Fn(usize) -> Option<usize>) {
+        self.values.retain(|_, value| match value {
+            BlackboardValue::Entity(entity) => match map(*entity) {
+                Some(index) => {
+                    *entity = index;
+                    true
+                },
+                None => false,
+            },
+            _ => true,
+        });
+    }
+
+    pub fn points(&self, key: &str) -> &[(f32, f32)] {
+        match self.values.get(key) {
+            Some(BlackboardValue::Points(points)) => points,
+            _ => &[],
+        }
+    }
+}
@@ -0,0 +1,11 @@
+// Behaviour trees for AI. Trees are written in entities.ron under `behaviours` and
+// picked by an entity with `ai: Behaviour("name")`; BehaviourSystem ticks them. A
+// tree is shared by every entity using it, while each entity keeps its own
+// BehaviourState (blackboard, timers and which child is running).
+pub mod blackboard;
+pub mod node;
+pub mod tree;
+
+pub use self::blackboard::{Blackboard, BlackboardValue};
+pub use self::node::BehaviourNode;
+pub use self::tree::{BehaviourState, BehaviourTree, NodeKind, Status};
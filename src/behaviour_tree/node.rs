@@ -0,0 +1,48 @@
+use serde::{Deserialize, Serialize};
+
+// A tree node as written in entities.ron, e.g.
+//
+//   Selector([
+//     Sequence([CanSee(200.0), InRange(50.0), Cooldown(seconds: 1.0, child: Attack)]),
+//     Sequence([CanSee(200.0), MoveTo("target")]),
+//     Sequence([Patrol, Wait(2.0)]),
+//   ])
+//
+// Ranges are in pixels and times in seconds. Keys name blackboard entries; see
+// blackboard.rs for the ones every entity starts with.
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+pub enum BehaviourNode {
+    // Runs children in order until one fails. Resumes at the running child, but
+    // conditions before it are checked again every tick.
+    Sequence(Vec<BehaviourNode>),
+    // Tries children in order until one doesn't fail. Starts from the first child
+    // every tick, so a higher-priority branch interrupts a running one.
+    Selector(Vec<BehaviourNode>),
+    // Ticks every child each tick. Fails as soon as one fails, succeeds once all do.
+    Parallel(Vec<BehaviourNode>),
+
+    // Swaps success and failure
+    Inverter(Box<BehaviourNode>),
+    // Fails without running the child until `seconds` after it last succeeded
+    Cooldown { seconds: f32, child: Box<BehaviourNode> },
+    // Runs the child `times` times in a row (0 = forever), failing if it does
+    Repeat { times: u32, child: Box<BehaviourNode> },
+
+    // Walks to an entity or point stored under a key; succeeds on arrival
+    MoveTo(String),
+    // Starts an attack
+    Attack,
+    // Whether the target is within this many pixels
+    InRange(f32),
+    // Whether the target is within this many pixels with no wall in between (and,
+    // for entities with a Perception, inside its vision cone)
+    CanSee(f32),
+    // Stands still for this many seconds
+    Wait(f32),
+    // Walks to the next patrol waypoint; succeeds each time one is reached
+    Patrol,
+    // Runs from an entity or point stored under a key until `distance` pixels away
+    Flee { from: String, distance: f32 },
+    // Strolls around aimlessly; never finishes
+    Wander,
+}
@@ -0,0 +1,183 @@
+use super::blackboard::Blackboard;
+use super::node::BehaviourNode;
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Status {
+    Success,
+    Failure,
+    Running,
+}
+
+// A BehaviourNode without its children, which live in Node::children
+#[derive(Clone, Debug, PartialEq)]
+pub enum NodeKind {
+    Sequence,
+    Selector,
+    Parallel,
+    Inverter,
+    Cooldown(f32),
+    Repeat(u32),
+    MoveTo(String),
+    Attack,
+    InRange(f32),
+    CanSee(f32),
+    Wait(f32),
+    Patrol,
+    Flee(String, f32),
+    Wander,
+}
+
+#[derive(Clone, Debug)]
+pub struct Node {
+    pub kind: NodeKind,
+    pub children: Vec<usize>,
+}
+
+// A tree flattened into a list, root first, so per-entity state can be a plain
+// Vec indexed the same way
+#[derive(Clone, Debug)]
+pub struct BehaviourTree {
+    pub nodes: Vec<Node>,
+}
+
+impl BehaviourTree {
+    pub fn compile(root: &BehaviourNode) -> Self {
+        let mut tree = BehaviourTree { nodes: Vec::new() };
+        tree.add(root);
+        tree
+    }
+
+    fn add(&mut self, definition: &BehaviourNode) -> usize {
+        let (kind, children): (NodeKind, Vec<&BehaviourNode>) = match definition {
+            BehaviourNode::Sequence(children) => (NodeKind::Sequence, children.iter().collect()),
+            BehaviourNode::Selector(children) => (NodeKind::Selector, children.iter().collect()),
+            BehaviourNode::Parallel(children) => (NodeKind::Parallel, children.iter().collect()),
+            BehaviourNode::Inverter(child) => (NodeKind::Inverter, vec![child]),
+            BehaviourNode::Cooldown { seconds, child } => (NodeKind::Cooldown(*seconds), vec![child]),
+            BehaviourNode::Repeat { times, child } => (NodeKind::Repeat(*times), vec![child]),
+            BehaviourNode::MoveTo(key) => (NodeKind::MoveTo(key.clone()), vec![]),
+            BehaviourNode::Attack => (NodeKind::Attack, vec![]),
+            BehaviourNode::InRange(range) => (NodeKind::InRange(*range), vec![]),
+            BehaviourNode::CanSee(range) => (NodeKind::CanSee(*range), vec![]),
+            BehaviourNode::Wait(seconds) => (NodeKind::Wait(*seconds), vec![]),
+            BehaviourNode::Patrol => (NodeKind::Patrol, vec![]),
+            BehaviourNode::Flee { from, distance } => (NodeKind::Flee(from.clone(), *distance), vec![]),
+            BehaviourNode::Wander => (NodeKind::Wander, vec![]),
+        };
+
+        let id = self.nodes.len();
+        self.nodes.push(Node { kind, children: Vec::new() });
+        let children = children.into_iter().map(|child| self.add(child)).collect();
+        self.nodes[id].children = children;
+        id
+    }
+
+    // Conditions only look at the world, so a Sequence can re-check them every tick
+    // without repeating any actions
+    pub fn is_condition(&self, id: usize) -> bool {
+        let node = &self.nodes[id];
+        match node.kind {
+            NodeKind::InRange(_) | NodeKind::CanSee(_) => true,
+            NodeKind::Inverter => self.is_condition(node.children[0]),
+            _ => false,
+        }
+    }
+}
+
+// What a node remembers between ticks. `index` is the running child of a Sequence,
+// the next waypoint of a Patrol or the finished runs of a Repeat; `since` is when a
+// Wait started or a Cooldown's child last succeeded.
+#[derive(Clone, Copy, Debug, Default, Serialize, Deserialize)]
+pub struct NodeMemory {
+    pub index: usize,
+    pub since: Option<f32>,
+}
+
+// One entity's progress through a tree. Saved with the entity, so it refers to its
+// tree by name.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct BehaviourState {
+    pub tree: String,
+    pub blackboard: Blackboard,
+    pub memory: Vec<NodeMemory>, // Indexed like BehaviourTree::nodes
+    pub time: f32,               // Seconds this entity has been thinking, for timers
+}
+
+impl BehaviourState {
+    pub fn new(tree: &str, blackboard: Blackboard) -> Self {
+        BehaviourState {
+            tree: tree.to_string(),
+            blackboard,
+            memory: Vec::new(),
+            time: 0.0,
+        }
+    }
+
+    // Make sure there's memory for every node. A tree edited by hot reload (or a save
+    // from before an edit) may have a different shape, so it starts over.
+    pub fn fit(&mut self, tree: &BehaviourTree) {
+        if self.memory.len() != tree.nodes.len() {
+            self.memory = vec![NodeMemory::default(); tree.nodes.len()];
+        }
+    }
+
+    // Forget what a node was in the middle of after a tick that didn't reach it.
+    // Cooldowns and patrol progress are kept; they aren't tied to running.
+    pub fn halt(&mut self, tree: &BehaviourTree, id: usize) {
+        let memory = &mut self.memory[id];
+        match tree.nodes[id].kind {
+            NodeKind::Sequence | NodeKind::Repeat(_) => memory.index = 0,
+            NodeKind::Wait(_) => memory.since = None,
+            _ => {},
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn compiles_depth_first_with_the_root_first() {
+        let root: BehaviourNode = ron::from_str(
+            "Selector([Sequence([Inverter(CanSee(200.0)), Wait(1.0)]), Cooldown(seconds: 2.0, child: Attack)])",
+        ).unwrap();
+        let tree = BehaviourTree::compile(&root);
+
+        let kinds: Vec<&NodeKind> = tree.nodes.iter().map(|node| &node.kind).collect();
+        assert_eq!(kinds, [
+            &NodeKind::Selector,
+            &NodeKind::Sequence,
+            &NodeKind::Inverter,
+            &NodeKind::CanSee(200.0),
+            &NodeKind::Wait(1.0),
+            &NodeKind::Cooldown(2.0),
+            &NodeKind::Attack,
+        ]);
+        assert_eq!(tree.nodes[0].children, [1, 5]);
+        assert_eq!(tree.nodes[1].children, [2, 4]);
+        assert_eq!(tree.nodes[5].children, [6]);
+
+        assert!(tree.is_condition(2));
+        assert!(tree.is_condition(3));
+        assert!(!tree.is_condition(4));
+        assert!(!tree.is_condition(1));
+    }
+
+    #[test]
+    fn state_starts_over_when_the_tree_changes_shape() {
+        let small = BehaviourTree::compile(&ron::from_str("Sequence([Wait(1.0), Attack])").unwrap());
+        let large = BehaviourTree::compile(&ron::from_str("Sequence([Wait(1.0), Attack, Wander])").unwrap());
+        let mut state = BehaviourState::new("test", Blackboard::default());
+
+        state.fit(&small);
+        state.memory[0].index = 1;
+        state.fit(&small);
+        assert_eq!(state.memory[0].index, 1);
+
+        state.fit(&large);
+        assert_eq!(state.memory.len(), 4);
+        assert_eq!(state.memory[0].index, 0);
+    }
+}
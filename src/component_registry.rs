@@ -0,0 +1,290 @@
+use crate::assets::placeholder::PlaceholderLayout;
+use crate::assets::AssetServer;
+use crate::components::*;
+use crate::components::spawner::{ClearCondition, Wave, DEFAULT_MAX_ALIVE, DEFAULT_SPAWN_INTERVAL, DEFAULT_WAVE_DELAY};
+use crate::entity_factory::EntityComponents;
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+use std::collections::BTreeMap;
+
+// A component that can be listed by name in an entity definition's `components`
+// map. The RON value is deserialized into Self, then inserted into the entity
+// being built.
+pub trait PrefabComponent: DeserializeOwned {
+    fn insert<'a>(self, components: &mut EntityComponents<'a>, assets: &mut AssetServer<'a>) -> Result<(), String>;
+}
+
+type CheckFn = fn(&ron::Value) -> Result<(), String>;
+type ApplyFn = for<'a> fn(&ron::Value, &mut EntityComponents<'a>, &mut AssetServer<'a>) -> Result<(), String>;
+
+struct Registration {
+    check: CheckFn,
+    apply: ApplyFn,
+}
+
+fn deserialize<T: PrefabComponent>(value: &ron::Value) -> Result<T, String> {
+    value.clone().into_rust::<T>().map_err(|e| e.to_string())
+}
+
+fn check<T: PrefabComponent>(value: &ron::Value) -> Result<(), String> {
+    deserialize::<T>(value).map(|_| ())
+}
+
+fn apply<'a, T: PrefabComponent>(value: &ron::Value, components: &mut EntityComponents<'a>, assets: &mut AssetServer<'a>) -> Result<(), String> {
+    deserialize::<T>(value)?.insert(components, assets)
+}
+
+// Name -> deserializer for every component entities.ron can list
+pub struct ComponentRegistry {
+    components: BTreeMap<&'static str, Registration>,
+}
+
+impl ComponentRegistry {
+    pub fn new() -> Self {
+        ComponentRegistry { components: BTreeMap::new() }
+    }
+
+    // Every component the game itself knows about
+    pub fn with_defaults() -> Self {
+        let mut registry = ComponentRegistry::new();
+        registry.register::<SpriteDefinition>("Sprite");
+        registry.register::<HealthDefinition>("Health");
+        registry.register::<Light>("Light");
+        registry.register::<Velocity>("Velocity");
+        registry.register::<Damage>("Damage");
+        registry.register::<PerceptionDefinition>("Perception");
+        registry.register::<ProjectileDefinition>("Projectile");
+        registry.register::<RangedAttack>("RangedAttack");
+        registry.register::<Faction>("Faction");
+        registry.register::<SpawnerDefinition>("Spawner");
+        registry
+    }
+
+    pub fn register<T: PrefabComponent>(&mut self, name: &'static str) {
+        self.components.insert(name, Registration {
+            check: check::<T>,
+            apply: apply::<T>,
+        });
+    }
+
+    pub fn names(&self) -> Vec<&'static str> {
+        self.components.keys().copied().collect()
+    }
+
+    // Whether `value` is valid data for the named component, without building anything
+    pub fn check(&self, name: &str, value: &ron::Value) -> Result<(), String> {
+        let registration = self.components.get(name)
+            .ok_or_else(|| format!("Unknown component {} (registered: {:?})", name, self.names()))?;
+        (registration.check)(value)
+    }
+
+    pub fn apply<'a>(&self, name: &str, value: &ron::Value, components: &mut EntityComponents<'a>, assets: &mut AssetServer<'a>) -> Result<(), String> {
+        let registration = self.components.get(name)
+            .ok_or_else(|| format!("Unknown component {} (registered: {:?})", name, self.names()))?;
+        (registration.apply)(value, components, assets)
+    }
+}
+
+// A single looping sheet, for entities that don't have character animations.
+// Optional values use serde defaults rather than Option: RON values are kept
+// untyped until the component is built, and they don't round-trip Some(..).
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SpriteDefinition {
+    pub texture: String,
+    #[serde(default = "default_frames")]
+    pub frames: usize,
+    #[serde(default = "default_frame_duration")]
+    pub frame_duration: u32, // Milliseconds
+    #[serde(default = "default_frame_size")]
+    pub frame_width: u32,
+    #[serde(default = "default_frame_size")]
+    pub frame_height: u32,
+    #[serde(default)]
+    pub origin: (u32, u32),
+    #[serde(default = "default_scale")]
+    pub scale: f32,
+}
+
+fn default_frames() -> usize {
+    1
+}
+
+fn default_frame_duration() -> u32 {
+    100
+}
+
+fn default_frame_size() -> u32 {
+    Sprite::default().frame_width
+}
+
+fn default_scale() -> f32 {
+    Sprite::default().scale
+}
+
+impl PrefabComponent for SpriteDefinition {
+    fn insert<'a>(self, components: &mut EntityComponents<'a>, assets: &mut AssetServer<'a>) -> Result<(), String> {
+        let sprite = Sprite {
+            frame_width: self.frame_width,
+            frame_height: self.frame_height,
+            origin: self.origin,
+            scale: self.scale,
+        };
+        let layout = PlaceholderLayout::sprite_sheet(sprite.frame_width, sprite.frame_height, self.frames);
+        let texture = assets.load_texture_or_placeholder(&self.texture, layout).map_err(|e| e.to_string())?;
+        components.textures = vec![texture];
+        // Every state plays the same sheet
+        components.animation = Animation::new(AnimationState::Idle, self.frames, self.frames, self.frames);
+        components.animation.frame_duration = self.frame_duration;
+        components.sprite = Some(sprite);
+        Ok(())
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct HealthDefinition {
+    pub max: u32,
+    #[serde(default)]
+    pub regeneration: f32, // Points per second
+}
+
+impl PrefabComponent for HealthDefinition {
+    fn insert<'a>(self, components: &mut EntityComponents<'a>, _assets: &mut AssetServer<'a>) -> Result<(), String> {
+        components.health = Some(Health::with_regeneration(self.max, self.max, self.regeneration));
+        Ok(())
+    }
+}
+
+impl PrefabComponent for Light {
+    fn insert<'a>(self, components: &mut EntityComponents<'a>, _assets: &mut AssetServer<'a>) -> Result<(), String> {
+        components.light = Some(self);
+        Ok(())
+    }
+}
+
+impl PrefabComponent for Velocity {
+    fn insert<'a>(self, components: &mut EntityComponents<'a>, _assets: &mut AssetServer<'a>) -> Result<(), String> {
+        components.velocity = Some(self);
+        Ok(())
+    }
+}
+
+impl PrefabComponent for Damage {
+    fn insert<'a>(self, components: &mut EntityComponents<'a>, _assets: &mut AssetServer<'a>) -> Result<(), String> {
+        components.damage = Some(self);
+        Ok(())
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PerceptionDefinition {
+    pub view_range: f32,
+    #[serde(default = "default_view_angle")]
+    pub view_angle: f32, // Degrees
+    #[serde(default)]
+    pub hearing_radius: f32,
+    #[serde(default = "default_memory")]
+    pub memory: f32, // Seconds
+}
+
+fn default_view_angle() -> f32 {
+    120.0
+}
+
+fn default_memory() -> f32 {
+    5.0
+}
+
+impl PrefabComponent for PerceptionDefinition {
+    fn insert<'a>(self, components: &mut EntityComponents<'a>, _assets: &mut AssetServer<'a>) -> Result<(), String> {
+        components.perception = Some(Perception::new(self.view_range, self.view_angle, self.hearing_radius, self.memory));
+        Ok(())
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ProjectileDefinition {
+    #[serde(default = "default_lifetime")]
+    pub lifetime: f32, // Seconds
+    #[serde(default)]
+    pub pierce: u32,
+    #[serde(default)]
+    pub bounce: bool, // Off walls, instead of stopping; a bool since untyped RON values lose enum variant names
+}
+
+fn default_lifetime() -> f32 {
+    2.0
+}
+
+impl PrefabComponent for ProjectileDefinition {
+    fn insert<'a>(self, components: &mut EntityComponents<'a>, _assets: &mut AssetServer<'a>) -> Result<(), String> {
+        let on_tile = if self.bounce { TileHit::Bounce } else { TileHit::Stop };
+        components.projectile = Some(Projectile::new(self.lifetime, self.pierce, on_tile));
+        Ok(())
+    }
+}
+
+impl PrefabComponent for RangedAttack {
+    fn insert<'a>(self, components: &mut EntityComponents<'a>, _assets: &mut AssetServer<'a>) -> Result<(), String> {
+        components.ranged_attack = Some(self);
+        Ok(())
+    }
+}
+
+impl PrefabComponent for Faction {
+    fn insert<'a>(self, components: &mut EntityComponents<'a>, _assets: &mut AssetServer<'a>) -> Result<(), String> {
+        components.faction = Some(self);
+        Ok(())
+    }
+}
+
+// Either `entity` (spawned continuously) or `waves`; see components::Spawner
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SpawnerDefinition {
+    #[serde(default)]
+    pub entity: String,
+    #[serde(default = "default_spawn_interval")]
+    pub interval: f32, // Seconds
+    #[serde(default = "default_max_alive")]
+    pub max_alive: usize,
+    #[serde(default)]
+    pub radius: f32,
+    #[serde(default)]
+    pub activation_range: f32, // 0 = always active
+    #[serde(default)]
+    pub waves: Vec<Wave>,
+    #[serde(default = "default_wave_delay")]
+    pub wave_delay: f32, // Seconds
+    #[serde(default)]
+    pub clear: ClearCondition,
+}
+
+fn default_spawn_interval() -> f32 {
+    DEFAULT_SPAWN_INTERVAL
+}
+
+fn default_max_alive() -> usize {
+    DEFAULT_MAX_ALIVE
+}
+
+fn default_wave_delay() -> f32 {
+    DEFAULT_WAVE_DELAY
+}
+
+impl PrefabComponent for SpawnerDefinition {
+    fn insert<'a>(self, components: &mut EntityComponents<'a>, _assets: &mut AssetServer<'a>) -> Result<(), String> {
+        let mut spawner = Spawner::new(self.entity, self.waves);
+        spawner.interval = self.interval;
+        spawner.max_alive = self.max_alive;
+        spawner.radius = self.radius;
+        spawner.activation_range = self.activation_range;
+        spawner.wave_delay = self.wave_delay;
+        spawner.clear = self.clear;
+        components.spawner = Some(spawner);
+        Ok(())
+    }
+}
@@ -1,8 +1,42 @@
-#[derive(Clone, PartialEq, Debug)]
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
 pub enum ActionState {
     None,
     Moving { right: bool, left: bool, up: bool, down: bool },
     Attacking,
+    // Analog movement from AI steering: a direction scaled by how much of the
+    // entity's speed to use, so its length is at most 1
+    Steering { x: f32, y: f32 },
+    // Firing the entity's RangedAttack
+    Shooting,
+}
+
+impl ActionState {
+    // Pixels per frame this state moves an entity with the given speed
+    pub fn velocity(&self, speed: f32) -> (f32, f32) {
+        match self {
+            ActionState::Moving { right, left, up, down } => {
+                let axis = |positive: bool, negative: bool| match (positive, negative) {
+                    (true, false) => speed,
+                    (false, true) => -speed,
+                    _ => 0.0,
+                };
+                (axis(*right, *left), axis(*down, *up))
+            },
+            ActionState::Steering { x, y } => (x * speed, y * speed),
+            ActionState::None | ActionState::Attacking | ActionState::Shooting => (0.0, 0.0),
+        }
+    }
+    
+    pub fn is_moving(&self) -> bool {
+        self.velocity(1.0) != (0.0, 0.0)
+    }
+    
+    // Melee or ranged; both play the attack animation
+    pub fn is_attacking(&self) -> bool {
+        matches!(self, ActionState::Attacking | ActionState::Shooting)
+    }
 }
 
 impl Default for ActionState {
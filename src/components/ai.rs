@@ -1,11 +1,112 @@
 // In components/ai.rs or similar file
+use crate::behaviour_tree::blackboard::TARGET;
+use crate::behaviour_tree::BehaviourState;
+use crate::utility_ai::UtilityState;
+use serde::{Deserialize, Serialize};
+
+// Chase settings an entity definition can leave out
+pub const DEFAULT_LEASH_RANGE: f32 = 400.0;
+pub const DEFAULT_ATTACK_COOLDOWN: f32 = 1.0;
+pub const DEFAULT_WIND_UP: f32 = 0.25;
+pub const DEFAULT_SEARCH_TIME: f32 = 4.0;
+
+#[derive(Clone, Serialize, Deserialize)]
 pub enum AiState {
     Idle,
     Patrol { waypoints: Vec<(f32, f32)>, current_waypoint: usize },
-    Chase { target_entity: usize, detection_range: f32, attack_range: f32 },
+    Chase(Chase),
+    Behaviour(BehaviourState), // Run by BehaviourSystem
+    Utility(UtilityState),     // Run by UtilitySystem
+}
+
+// Where a chaser is in dealing with its target
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum ChasePhase {
+    Guarding,                                     // No target: patrolling or standing at home
+    Hunting,                                      // Closing in on a target it can see
+    WindingUp { remaining: f32 },                 // Standing still before an attack lands
+    Searching { at: (f32, f32), remaining: f32 }, // Lost the target; looking where it was last noticed (feet)
+    Returning,                                    // Gave up; heading home, ignoring targets until there
+}
+
+// An enemy that guards its home (or patrol route), goes after hostile entities it
+// notices and gives up once they're lost or it's been lured too far away.
+// Distances are in pixels and times in seconds.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Chase {
+    pub detection_range: f32,
+    pub attack_range: f32,
+    pub leash_range: f32, // Gives up once this far from home
+    pub attack_cooldown: f32,
+    pub wind_up: f32,
+    pub search_time: f32,
+    pub home: (f32, f32),        // Where it spawned; a position, like patrol waypoints
+    pub patrol: Vec<(f32, f32)>, // Walked while guarding, if the level gives one
+    pub current_waypoint: usize,
+    pub target_entity: Option<usize>,
+    pub phase: ChasePhase,
+    pub cooldown: f32, // Until it can attack again
 }
 
+impl Chase {
+    pub fn new(detection_range: f32, attack_range: f32, home: (f32, f32)) -> Self {
+        Chase {
+            detection_range,
+            attack_range,
+            leash_range: DEFAULT_LEASH_RANGE,
+            attack_cooldown: DEFAULT_ATTACK_COOLDOWN,
+            wind_up: DEFAULT_WIND_UP,
+            search_time: DEFAULT_SEARCH_TIME,
+            home,
+            patrol: Vec::new(),
+            current_waypoint: 0,
+            target_entity: None,
+            phase: ChasePhase::Guarding,
+            cooldown: 0.0,
+        }
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize)]
 pub struct Ai {
     // Define the fields for the Ai struct
     pub behavior: AiState,
 }
+
+impl Ai {
+    // Entity this AI is after, if any
+    pub fn target(&self) -> Option<usize> {
+        match &self.behavior {
+            AiState::Chase(chase) => chase.target_entity,
+            AiState::Behaviour(state) => state.blackboard.entity(TARGET),
+            AiState::Utility(state) => state.target,
+            AiState::Idle | AiState::Patrol { .. } => None,
+        }
+    }
+    
+    // Keep entity indices pointing at the same entities after the one at `removed`
+    // is taken out; a target that was removed is forgotten
+    pub fn entity_removed(&mut self, removed: usize) {
+        self.remap_entities(|index| shift_index(index, removed));
+    }
+    
+    // Move every entity index this AI holds to where `map` says that entity is now,
+    // forgetting the ones it maps to None (saving and loading renumber entities)
+    pub fn remap_entities(&mut self, map: impl Fn(usize) -> Option<usize>) {
+        match &mut self.behavior {
+            AiState::Chase(chase) => chase.target_entity = chase.target_entity.and_then(&map),
+            AiState::Behaviour(state) => state.blackboard.remap_entities(map),
+            AiState::Utility(state) => state.target = state.target.and_then(&map),
+            AiState::Idle | AiState::Patrol { .. } => {},
+        }
+    }
+}
+
+// Where entity `index` ends up once `removed` is taken out of the columns
+pub fn shift_index(index: usize, removed: usize) -> Option<usize> {
+    match index.cmp(&removed) {
+        std::cmp::Ordering::Less => Some(index),
+        std::cmp::Ordering::Equal => None,
+        std::cmp::Ordering::Greater => Some(index - 1),
+    }
+}
@@ -1,4 +1,6 @@
-#[derive(Debug, Clone, Copy, PartialEq)]
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
 pub enum AnimationState {
     Idle,
     Walk,
@@ -7,7 +9,7 @@ pub enum AnimationState {
     Death,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Animation {
     pub current_frame: usize,
     pub frame_duration: u32, // Duration per frame in milliseconds
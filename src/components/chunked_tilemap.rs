@@ -0,0 +1,357 @@
+use crate::assets::Handle;
+use crate::components::tilemap::{parse_tile_csv, DestructibleTile, TileId, TileType, Tileset};
+use std::collections::{HashMap, HashSet};
+use crate::vfs;
+
+// Width and height of a chunk in tiles
+pub const CHUNK_SIZE: usize = 32;
+
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
+pub struct ChunkCoord {
+    pub x: i32,
+    pub y: i32,
+}
+
+impl ChunkCoord {
+    pub fn new(x: i32, y: i32) -> Self {
+        ChunkCoord { x, y }
+    }
+
+    // Chunk containing the given world tile (works for negative coordinates too)
+    pub fn from_tile(tile_x: i32, tile_y: i32) -> Self {
+        ChunkCoord {
+            x: tile_x.div_euclid(CHUNK_SIZE as i32),
+            y: tile_y.div_euclid(CHUNK_SIZE as i32),
+        }
+    }
+
+    // World tile coordinate of this chunk's top-left corner
+    pub fn origin(&self) -> (i32, i32) {
+        (self.x * CHUNK_SIZE as i32, self.y * CHUNK_SIZE as i32)
+    }
+}
+
+// A runtime edit in world tile coordinates, which unlike TileChange can be negative
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct WorldTileChange {
+    pub x: i32,
+    pub y: i32,
+    pub old: TileId,
+    pub new: TileId,
+}
+
+pub struct Chunk {
+    pub tiles: Vec<TileId>, // CHUNK_SIZE * CHUNK_SIZE, row-major
+    pub dirty: bool,        // Modified since load, needs writing back to disk
+}
+
+impl Chunk {
+    pub fn empty() -> Self {
+        Chunk {
+            tiles: vec![TileId(0); CHUNK_SIZE * CHUNK_SIZE],
+            dirty: false,
+        }
+    }
+
+    pub fn get(&self, local_x: usize, local_y: usize) -> TileId {
+        self.tiles[local_y * CHUNK_SIZE + local_x]
+    }
+
+    // Returns the tile that was replaced, or None if nothing changed
+    pub fn set(&mut self, local_x: usize, local_y: usize, tile: TileId) -> Option<TileId> {
+        let index = local_y * CHUNK_SIZE + local_x;
+        let old = self.tiles[index];
+        if old == tile {
+            return None;
+        }
+        self.tiles[index] = tile;
+        self.dirty = true;
+        Some(old)
+    }
+
+    fn from_rows(rows: Vec<Vec<TileId>>) -> Result<Self, String> {
+        if rows.len() != CHUNK_SIZE || rows.iter().any(|row| row.len() != CHUNK_SIZE) {
+            return Err(format!("Chunk must be {}x{} tiles", CHUNK_SIZE, CHUNK_SIZE));
+        }
+
+        Ok(Chunk {
+            tiles: rows.into_iter().flatten().collect(),
+            dirty: false,
+        })
+    }
+
+    fn to_csv(&self) -> String {
+        let mut csv = String::new();
+        for row in self.tiles.chunks(CHUNK_SIZE) {
+            let line: Vec<String> = row.iter().map(|tile| tile.0.to_string()).collect();
+            csv.push_str(&line.join(","));
+            csv.push('\n');
+        }
+        csv
+    }
+}
+
+// A world split into fixed-size chunks that are streamed from disk around the camera.
+// Each chunk lives in `<world_dir>/<x>_<y>.csv` using the same CSV format as whole levels.
+// Chunks without a file are treated as empty and only written once something is placed in them.
+pub struct ChunkedTilemap<'a> {
+    pub tile_size: u32,
+    pub tileset: Option<Handle<Tileset<'a>>>,
+    pub chunks: HashMap<ChunkCoord, Chunk>,
+    pub load_margin: i32, // Extra ring of chunks kept loaded around the visible area
+    pub tile_damage: HashMap<(i32, i32), u32>, // Damage taken so far by destructible tiles
+    changes: Vec<WorldTileChange>, // Edits since the last take_changes()
+    world_dir: String, // Virtual path, see crate::vfs
+}
+
+impl<'a> ChunkedTilemap<'a> {
+    pub fn new(world_dir: &str, tile_size: u32, tileset: Option<Handle<Tileset<'a>>>) -> Self {
+        ChunkedTilemap {
+            tile_size,
+            tileset,
+            chunks: HashMap::new(),
+            load_margin: 1,
+            tile_damage: HashMap::new(),
+            changes: Vec::new(),
+            world_dir: vfs::normalize(world_dir),
+        }
+    }
+
+    fn chunk_path(&self, coord: ChunkCoord) -> String {
+        format!("{}/{}_{}.csv", self.world_dir, coord.x, coord.y)
+    }
+
+    // Split a world tile coordinate into chunk + local tile coordinates
+    fn locate(tile_x: i32, tile_y: i32) -> (ChunkCoord, usize, usize) {
+        let coord = ChunkCoord::from_tile(tile_x, tile_y);
+        let local_x = tile_x.rem_euclid(CHUNK_SIZE as i32) as usize;
+        let local_y = tile_y.rem_euclid(CHUNK_SIZE as i32) as usize;
+        (coord, local_x, local_y)
+    }
+
+    pub fn is_loaded(&self, coord: ChunkCoord) -> bool {
+        self.chunks.contains_key(&coord)
+    }
+
+    // Returns None if the chunk holding this tile isn't currently loaded
+    pub fn get_tile(&self, x: i32, y: i32) -> Option<TileId> {
+        let (coord, local_x, local_y) = Self::locate(x, y);
+        self.chunks.get(&coord).map(|chunk| chunk.get(local_x, local_y))
+    }
+
+    // Loads the owning chunk on demand so edits never get dropped
+    pub fn set_tile(&mut self, x: i32, y: i32, tile: TileId) -> Result<(), String> {
+        let (coord, local_x, local_y) = Self::locate(x, y);
+        self.load_chunk(coord)?;
+        if let Some(old) = self.chunks.get_mut(&coord).and_then(|chunk| chunk.set(local_x, local_y, tile)) {
+            self.tile_damage.remove(&(x, y));
+            self.changes.push(WorldTileChange { x, y, old, new: tile });
+        }
+        Ok(())
+    }
+
+    // Apply damage to a destructible tile. Returns true if this hit broke it.
+    pub fn damage_tile(&mut self, x: i32, y: i32, amount: u32, destructible: &HashMap<TileId, DestructibleTile>) -> Result<bool, String> {
+        let rule = match self.get_tile(x, y).and_then(|tile_id| destructible.get(&tile_id)) {
+            Some(rule) => *rule,
+            None => return Ok(false),
+        };
+
+        let damage = self.tile_damage.entry((x, y)).or_insert(0);
+        *damage += amount;
+        if *damage >= rule.hit_points {
+            self.set_tile(x, y, rule.becomes)?;
+            return Ok(true);
+        }
+        Ok(false)
+    }
+
+    // Hand over every change made since the last call
+    pub fn take_changes(&mut self) -> Vec<WorldTileChange> {
+        std::mem::take(&mut self.changes)
+    }
+
+    pub fn is_solid(&self, x: i32, y: i32, tile_types: &HashMap<TileId, TileType>) -> bool {
+        match self.get_tile(x, y) {
+            Some(tile_id) => tile_types.get(&tile_id).is_some_and(|tile_type| tile_type.is_solid()),
+            None => true, // Treat unloaded chunks as solid so nothing walks into the void
+        }
+    }
+
+    pub fn load_chunk(&mut self, coord: ChunkCoord) -> Result<(), String> {
+        if self.chunks.contains_key(&coord) {
+            return Ok(());
+        }
+
+        let path = self.chunk_path(coord);
+        let chunk = if vfs::exists(&path) {
+            let bytes = vfs::read(&path)
+                .map_err(|e| format!("Failed to open chunk file {}: {}", path, e))?;
+            let rows = parse_tile_csv(bytes.as_slice())?;
+            Chunk::from_rows(rows)
+                .map_err(|e| format!("Invalid chunk file {}: {}", path, e))?
+        } else {
+            Chunk::empty()
+        };
+
+        self.chunks.insert(coord, chunk);
+        Ok(())
+    }
+
+    pub fn save_chunk(&mut self, coord: ChunkCoord) -> Result<(), String> {
+        let path = self.chunk_path(coord);
+        let chunk = match self.chunks.get_mut(&coord) {
+            Some(chunk) => chunk,
+            None => return Ok(()),
+        };
+
+        // The vfs creates the world directory on first write
+        vfs::write(&path, chunk.to_csv().as_bytes())
+            .map_err(|e| format!("Failed to write chunk file {}: {}", path, e))?;
+
+        chunk.dirty = false;
+        Ok(())
+    }
+
+    // Write back a modified chunk, then drop it from memory
+    pub fn unload_chunk(&mut self, coord: ChunkCoord) -> Result<(), String> {
+        if self.chunks.get(&coord).is_some_and(|chunk| chunk.dirty) {
+            self.save_chunk(coord)?;
+        }
+        self.chunks.remove(&coord);
+        Ok(())
+    }
+
+    pub fn save_all(&mut self) -> Result<(), String> {
+        let dirty: Vec<ChunkCoord> = self.chunks.iter()
+            .filter(|(_, chunk)| chunk.dirty)
+            .map(|(coord, _)| *coord)
+            .collect();
+
+        for coord in dirty {
+            self.save_chunk(coord)?;
+        }
+        Ok(())
+    }
+
+    // Chunk range covering the given pixel rectangle, grown by `margin` chunks on every side
+    pub fn chunks_in_view(&self, camera_x: i32, camera_y: i32, view_width: i32, view_height: i32, margin: i32) -> Vec<ChunkCoord> {
+        let chunk_pixels = CHUNK_SIZE as i32 * self.tile_size as i32;
+        let min_x = camera_x.div_euclid(chunk_pixels) - margin;
+        let min_y = camera_y.div_euclid(chunk_pixels) - margin;
+        let max_x = (camera_x + view_width).div_euclid(chunk_pixels) + margin;
+        let max_y = (camera_y + view_height).div_euclid(chunk_pixels) + margin;
+
+        let mut coords = Vec::new();
+        for y in min_y..=max_y {
+            for x in min_x..=max_x {
+                coords.push(ChunkCoord::new(x, y));
+            }
+        }
+        coords
+    }
+
+    // Load every chunk near the camera and unload the ones that have drifted out of range.
+    // Unloading uses one extra chunk of slack so walking back and forth over a chunk
+    // border doesn't thrash the disk. Returns whether any chunk came or went.
+    pub fn update_streaming(&mut self, camera_x: i32, camera_y: i32, view_width: i32, view_height: i32) -> Result<bool, String> {
+        let wanted = self.chunks_in_view(camera_x, camera_y, view_width, view_height, self.load_margin);
+        let mut changed = false;
+        for coord in wanted {
+            if !self.is_loaded(coord) {
+                self.load_chunk(coord)?;
+                changed = true;
+            }
+        }
+
+        let keep: HashSet<ChunkCoord> = self
+            .chunks_in_view(camera_x, camera_y, view_width, view_height, self.load_margin + 1)
+            .into_iter()
+            .collect();
+        let stale: Vec<ChunkCoord> = self.chunks.keys()
+            .filter(|coord| !keep.contains(coord))
+            .copied()
+            .collect();
+
+        changed |= !stale.is_empty();
+        for coord in stale {
+            self.unload_chunk(coord)?;
+        }
+        Ok(changed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Nothing is ever written here, so every chunk loads empty without touching the disk
+    fn world() -> ChunkedTilemap<'static> {
+        ChunkedTilemap::new("assets/worlds/missing", 32, None)
+    }
+
+    #[test]
+    fn negative_tiles_fall_in_the_chunk_before_zero() {
+        assert_eq!(ChunkedTilemap::locate(0, 0), (ChunkCoord::new(0, 0), 0, 0));
+        assert_eq!(ChunkedTilemap::locate(-1, -1), (ChunkCoord::new(-1, -1), 31, 31));
+        assert_eq!(ChunkedTilemap::locate(-32, 33), (ChunkCoord::new(-1, 1), 0, 1));
+        assert_eq!(ChunkedTilemap::locate(-33, 31), (ChunkCoord::new(-2, 0), 31, 31));
+    }
+
+    #[test]
+    fn view_covers_every_chunk_it_touches_plus_the_margin() {
+        let world = world();
+        // A chunk is 32 tiles of 32 pixels
+        assert_eq!(world.chunks_in_view(0, 0, 800, 600, 0), vec![ChunkCoord::new(0, 0)]);
+        assert_eq!(world.chunks_in_view(-10, 1000, 800, 600, 0), vec![
+            ChunkCoord::new(-1, 0), ChunkCoord::new(0, 0),
+            ChunkCoord::new(-1, 1), ChunkCoord::new(0, 1),
+        ]);
+        assert_eq!(world.chunks_in_view(0, 0, 800, 600, 1).len(), 9);
+    }
+
+    #[test]
+    fn streaming_keeps_one_extra_ring_before_unloading() {
+        let mut world = world();
+        assert_eq!(world.update_streaming(0, 0, 800, 600), Ok(true));
+        assert_eq!(world.chunks.len(), 9);
+        assert_eq!(world.update_streaming(0, 0, 800, 600), Ok(false));
+
+        // One chunk right: three new columns load, the old left column is still within the slack
+        assert_eq!(world.update_streaming(1024, 0, 800, 600), Ok(true));
+        assert_eq!(world.chunks.len(), 12);
+        assert!(world.is_loaded(ChunkCoord::new(-1, 0)));
+
+        // Three chunks right: everything left of x = 1 is out of range
+        assert_eq!(world.update_streaming(3 * 1024, 0, 800, 600), Ok(true));
+        assert!(!world.is_loaded(ChunkCoord::new(-1, 0)));
+        assert!(!world.is_loaded(ChunkCoord::new(0, 0)));
+        assert!(world.is_loaded(ChunkCoord::new(1, 0)));
+        assert!(world.is_loaded(ChunkCoord::new(4, 0)));
+    }
+
+    #[test]
+    fn set_tile_loads_its_chunk_and_reports_the_change() {
+        let mut world = world();
+        assert_eq!(world.get_tile(-1, 5), None);
+
+        world.set_tile(-1, 5, TileId(2)).unwrap();
+        world.set_tile(-1, 5, TileId(2)).unwrap();
+        assert_eq!(world.get_tile(-1, 5), Some(TileId(2)));
+        assert!(world.chunks[&ChunkCoord::new(-1, 0)].dirty);
+        assert_eq!(world.take_changes(), vec![WorldTileChange { x: -1, y: 5, old: TileId(0), new: TileId(2) }]);
+    }
+
+    #[test]
+    fn destructible_tiles_break_after_enough_hits() {
+        let mut world = world();
+        let destructible = HashMap::from([(TileId(5), DestructibleTile { hit_points: 2, becomes: TileId(1) })]);
+        world.set_tile(3, -3, TileId(5)).unwrap();
+        world.take_changes();
+
+        assert_eq!(world.damage_tile(3, -3, 1, &destructible), Ok(false));
+        assert_eq!(world.damage_tile(3, -3, 1, &destructible), Ok(true));
+        assert_eq!(world.get_tile(3, -3), Some(TileId(1)));
+        assert!(world.tile_damage.is_empty());
+    }
+}
@@ -0,0 +1,233 @@
+use crate::components::chunked_tilemap::{ChunkCoord, ChunkedTilemap, CHUNK_SIZE};
+use crate::components::tilemap::{TileChange, TileId, TileType, Tilemap};
+use std::collections::HashMap;
+
+// Cached per-tile solidity (and movement cost, for pathfinding) so movement doesn't
+// look up tile types every step. Built once per level and then patched cell by cell
+// from the tilemap's change events; streamed worlds rebuild it around the loaded
+// chunks whenever those change.
+pub struct CollisionGrid {
+    pub width: usize,
+    pub height: usize,
+    pub tile_size: u32,
+    origin: (i32, i32), // World tile of cell (0, 0); only streamed worlds move it
+    solid: Vec<bool>,
+    costs: Vec<f32>,
+}
+
+// Tiles without a type are walkable floor, as in Tilemap::is_solid
+fn tile_cost(tile: &TileId, tile_types: &HashMap<TileId, TileType>) -> f32 {
+    tile_types.get(tile)
+        .and_then(|tile_type| tile_type.movement_cost())
+        .unwrap_or(1.0)
+}
+
+impl CollisionGrid {
+    pub fn from_tilemap(tilemap: &Tilemap, tile_types: &HashMap<TileId, TileType>) -> Self {
+        let mut solid = vec![false; tilemap.width * tilemap.height];
+        let mut costs = vec![1.0; tilemap.width * tilemap.height];
+        for y in 0..tilemap.height {
+            for x in 0..tilemap.width {
+                solid[y * tilemap.width + x] = tilemap.is_solid(x, y, tile_types);
+                if let Some(tile) = tilemap.get_tile(x, y) {
+                    costs[y * tilemap.width + x] = tile_cost(tile, tile_types);
+                }
+            }
+        }
+        
+        CollisionGrid {
+            width: tilemap.width,
+            height: tilemap.height,
+            tile_size: tilemap.tile_size,
+            origin: (0, 0),
+            solid,
+            costs,
+        }
+    }
+    
+    // Covers every loaded chunk; gaps between them are solid like anything unloaded.
+    // None while no chunk is loaded.
+    pub fn from_chunks(world: &ChunkedTilemap, tile_types: &HashMap<TileId, TileType>) -> Option<Self> {
+        let min_x = world.chunks.keys().map(|coord| coord.x).min()?;
+        let min_y = world.chunks.keys().map(|coord| coord.y).min()?;
+        let max_x = world.chunks.keys().map(|coord| coord.x).max()?;
+        let max_y = world.chunks.keys().map(|coord| coord.y).max()?;
+        let origin = ChunkCoord::new(min_x, min_y).origin();
+        let width = (max_x - min_x + 1) as usize * CHUNK_SIZE;
+        let height = (max_y - min_y + 1) as usize * CHUNK_SIZE;
+        
+        let mut solid = vec![true; width * height];
+        let mut costs = vec![1.0; width * height];
+        for y in 0..height {
+            for x in 0..width {
+                let (tile_x, tile_y) = (origin.0 + x as i32, origin.1 + y as i32);
+                solid[y * width + x] = world.is_solid(tile_x, tile_y, tile_types);
+                if let Some(tile) = world.get_tile(tile_x, tile_y) {
+                    costs[y * width + x] = tile_cost(&tile, tile_types);
+                }
+            }
+        }
+        
+        Some(CollisionGrid {
+            width,
+            height,
+            tile_size: world.tile_size,
+            origin,
+            solid,
+            costs,
+        })
+    }
+    
+    // A world pixel relative to the grid's top-left corner
+    fn local(&self, (pixel_x, pixel_y): (f32, f32)) -> (f32, f32) {
+        let tile_size = self.tile_size as f32;
+        (pixel_x - self.origin.0 as f32 * tile_size, pixel_y - self.origin.1 as f32 * tile_size)
+    }
+    
+    pub fn apply_changes(&mut self, changes: &[TileChange], tile_types: &HashMap<TileId, TileType>) {
+        for change in changes {
+            if change.x < self.width && change.y < self.height {
+                self.solid[change.y * self.width + change.x] = tile_types.get(&change.new)
+                    .is_some_and(|tile_type| tile_type.is_solid());
+                self.costs[change.y * self.width + change.x] = tile_cost(&change.new, tile_types);
+            }
+        }
+    }
+    
+    // Grid cell holding a world tile, if it's on the grid
+    pub fn cell(&self, tile_x: i32, tile_y: i32) -> Option<(usize, usize)> {
+        let (x, y) = (tile_x - self.origin.0, tile_y - self.origin.1);
+        if x < 0 || y < 0 || x as usize >= self.width || y as usize >= self.height {
+            return None;
+        }
+        Some((x as usize, y as usize))
+    }
+    
+    // Out-of-bounds counts as solid
+    pub fn is_solid(&self, x: usize, y: usize) -> bool {
+        if x < self.width && y < self.height {
+            self.solid[y * self.width + x]
+        } else {
+            true
+        }
+    }
+    
+    // Cost of walking through a tile, or None if it can't be entered
+    pub fn cost(&self, x: usize, y: usize) -> Option<f32> {
+        if self.is_solid(x, y) {
+            None
+        } else {
+            Some(self.costs[y * self.width + x])
+        }
+    }
+    
+    // Tile containing a pixel, if it's on the grid
+    pub fn tile_at(&self, pixel_x: f32, pixel_y: f32) -> Option<(usize, usize)> {
+        let (pixel_x, pixel_y) = self.local((pixel_x, pixel_y));
+        if pixel_x < 0.0 || pixel_y < 0.0 {
+            return None;
+        }
+        let tile_size = self.tile_size as f32;
+        let (x, y) = ((pixel_x / tile_size) as usize, (pixel_y / tile_size) as usize);
+        (x < self.width && y < self.height).then_some((x, y))
+    }
+    
+    // Pixel at the middle of a tile
+    pub fn tile_center(&self, x: usize, y: usize) -> (f32, f32) {
+        let tile_size = self.tile_size as f32;
+        let (x, y) = (self.origin.0 as f32 + x as f32, self.origin.1 as f32 + y as f32);
+        ((x + 0.5) * tile_size, (y + 0.5) * tile_size)
+    }
+    
+    // Whether a straight line between two pixels crosses no solid tile. Visits every
+    // tile the line passes through, so it can't slip between two diagonal walls.
+    pub fn has_line_of_sight(&self, from: (f32, f32), to: (f32, f32)) -> bool {
+        let (from, to) = (self.local(from), self.local(to));
+        let tile_size = self.tile_size as f32;
+        let (x0, y0) = (from.0 / tile_size, from.1 / tile_size);
+        let (x1, y1) = (to.0 / tile_size, to.1 / tile_size);
+        let (mut x, mut y) = (x0.floor() as i64, y0.floor() as i64);
+        let steps = x.abs_diff(x1.floor() as i64) + y.abs_diff(y1.floor() as i64);
+        
+        // How far along the line (0 = from, 1 = to) the next vertical and horizontal
+        // tile edges are, and how far apart successive edges are
+        let edge = |start: f32, cell: i64, delta: f32| {
+            if delta > 0.0 {
+                ((cell as f32 + 1.0 - start) / delta, 1.0 / delta)
+            } else if delta < 0.0 {
+                ((start - cell as f32) / -delta, -1.0 / delta)
+            } else {
+                (f32::INFINITY, f32::INFINITY)
+            }
+        };
+        let (mut next_x, step_x) = edge(x0, x, x1 - x0);
+        let (mut next_y, step_y) = edge(y0, y, y1 - y0);
+        
+        for _ in 0..=steps {
+            if x < 0 || y < 0 || self.is_solid(x as usize, y as usize) {
+                return false;
+            }
+            if next_x < next_y {
+                x += if x1 > x0 { 1 } else { -1 };
+                next_x += step_x;
+            } else {
+                y += if y1 > y0 { 1 } else { -1 };
+                next_y += step_y;
+            }
+        }
+        true
+    }
+    
+    pub fn is_solid_at(&self, pixel_x: f32, pixel_y: f32) -> bool {
+        let (pixel_x, pixel_y) = self.local((pixel_x, pixel_y));
+        if pixel_x < 0.0 || pixel_y < 0.0 {
+            return true;
+        }
+        let tile_size = self.tile_size as f32;
+        self.is_solid((pixel_x / tile_size) as usize, (pixel_y / tile_size) as usize)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::navigation::grid;
+
+    // Centre of a tile, in pixels
+    fn centre(x: usize, y: usize) -> (f32, f32) {
+        (x as f32 * 32.0 + 16.0, y as f32 * 32.0 + 16.0)
+    }
+
+    #[test]
+    fn walls_block_the_line() {
+        let grid = grid(&[
+            ".....",
+            "..#..",
+            ".....",
+        ]);
+        assert!(grid.has_line_of_sight(centre(0, 0), centre(4, 0)));
+        assert!(!grid.has_line_of_sight(centre(0, 1), centre(4, 1)));
+        assert!(!grid.has_line_of_sight(centre(4, 1), centre(0, 1)));
+        assert!(!grid.has_line_of_sight(centre(0, 0), centre(4, 2)));
+        assert!(grid.has_line_of_sight(centre(1, 1), centre(1, 1)));
+    }
+
+    #[test]
+    fn cannot_see_between_diagonal_walls() {
+        let grid = grid(&[
+            ".#",
+            "#.",
+        ]);
+        assert!(!grid.has_line_of_sight(centre(0, 0), centre(1, 1)));
+        assert!(!grid.has_line_of_sight(centre(1, 1), centre(0, 0)));
+    }
+
+    #[test]
+    fn water_does_not_block_and_off_the_grid_does() {
+        let grid = grid(&[
+            "..~..",
+        ]);
+        assert!(grid.has_line_of_sight(centre(0, 0), centre(4, 0)));
+        assert!(!grid.has_line_of_sight((-10.0, 16.0), centre(4, 0)));
+        assert!(!grid.has_line_of_sight(centre(0, 0), (16.0, 40.0)));
+    }
+}
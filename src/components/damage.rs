@@ -0,0 +1,8 @@
+use serde::{Deserialize, Serialize};
+
+// Damage dealt to whatever this entity hits
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Damage {
+    pub amount: u32,
+}
@@ -0,0 +1,9 @@
+use serde::{Deserialize, Serialize};
+
+// Which side an entity is on; how sides treat each other is in the definitions'
+// `factions` table (see factions::FactionTable)
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Faction {
+    pub name: String,
+}
@@ -5,4 +5,5 @@ pub enum GameAction {
     MoveLeft,
     MoveRight,
     Attack,
+    Shoot,
 }
\ No newline at end of file
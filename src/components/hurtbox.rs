@@ -0,0 +1,19 @@
+use super::Position;
+use serde::{Deserialize, Serialize};
+
+// Where an entity can be hit, relative to the top-left of its sprite (the
+// definition's `collision`)
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+pub struct Hurtbox {
+    pub width: f32,
+    pub height: f32,
+    pub offset_x: f32,
+    pub offset_y: f32,
+}
+
+impl Hurtbox {
+    pub fn contains(&self, position: &Position, (x, y): (f32, f32)) -> bool {
+        let (left, top) = (position.x + self.offset_x, position.y + self.offset_y);
+        x >= left && x < left + self.width && y >= top && y < top + self.height
+    }
+}
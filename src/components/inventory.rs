@@ -0,0 +1,31 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct Inventory {
+    pub items: HashMap<String, u32>, // Item name -> count
+}
+
+impl Inventory {
+    pub fn add(&mut self, item: &str, count: u32) {
+        *self.items.entry(item.to_string()).or_insert(0) += count;
+    }
+    
+    // Returns false (and removes nothing) if there aren't enough of the item
+    pub fn remove(&mut self, item: &str, count: u32) -> bool {
+        match self.items.get_mut(item) {
+            Some(held) if *held >= count => {
+                *held -= count;
+                if *held == 0 {
+                    self.items.remove(item);
+                }
+                true
+            },
+            _ => false,
+        }
+    }
+    
+    pub fn count(&self, item: &str) -> u32 {
+        self.items.get(item).copied().unwrap_or(0)
+    }
+}
@@ -0,0 +1,15 @@
+use serde::{Deserialize, Serialize};
+
+// Glow drawn around an entity's feet
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Light {
+    pub radius: f32,
+    pub color: (u8, u8, u8),
+    #[serde(default = "default_intensity")]
+    pub intensity: f32, // 0.0 - 1.0
+}
+
+fn default_intensity() -> f32 {
+    1.0
+}
@@ -9,6 +9,19 @@ pub mod game_action;
 pub mod ai;
 pub mod texture;
 pub mod tilemap;
+pub mod chunked_tilemap;
+pub mod inventory;
+pub mod collision_grid;
+pub mod sprite;
+pub mod light;
+pub mod velocity;
+pub mod damage;
+pub mod perception;
+pub mod projectile;
+pub mod hurtbox;
+pub mod ranged_attack;
+pub mod faction;
+pub mod spawner;
 
 pub use self::action_state::ActionState;
 pub use self::animation::Animation;
@@ -22,4 +35,22 @@ pub use self::texture::Texture;
 pub use self::tilemap::Tilemap;
 pub use self::tilemap::TileId;
 pub use self::tilemap::TileType;
+pub use self::tilemap::TileChange;
+pub use self::tilemap::DestructibleTile;
+pub use self::chunked_tilemap::ChunkedTilemap;
+pub use self::inventory::Inventory;
+pub use self::collision_grid::CollisionGrid;
 pub use self::ai::AiState;
+pub use self::sprite::Sprite;
+pub use self::light::Light;
+pub use self::velocity::Velocity;
+pub use self::damage::Damage;
+pub use self::perception::Perception;
+pub use self::perception::Noise;
+pub use self::projectile::Projectile;
+pub use self::projectile::TileHit;
+pub use self::hurtbox::Hurtbox;
+pub use self::ranged_attack::RangedAttack;
+pub use self::faction::Faction;
+pub use self::spawner::Spawner;
+pub use self::spawner::WaveEvent;
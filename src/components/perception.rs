@@ -0,0 +1,51 @@
+use crate::components::ai::shift_index;
+
+// What an AI entity has noticed about its target. Only the settings come from the
+// definition; the rest is filled in by PerceptionSystem every frame.
+#[derive(Clone, Debug)]
+pub struct Perception {
+    pub view_range: f32,     // Pixels
+    pub view_angle: f32,     // Width of the vision cone in degrees, centred on the facing direction
+    pub hearing_radius: f32, // Pixels, for a noise of volume 1.0
+    pub memory: f32,         // Seconds a last-known position is kept after losing track of the target
+    pub sees_target: bool,
+    pub last_known: Option<(f32, f32)>, // Target's feet when it was last seen or heard
+    pub since_noticed: f32,             // Seconds since then
+    pub heard: Vec<Noise>,              // What it heard while it had no target, for the AI to act on
+}
+
+impl Perception {
+    pub fn new(view_range: f32, view_angle: f32, hearing_radius: f32, memory: f32) -> Self {
+        Perception {
+            view_range,
+            view_angle,
+            hearing_radius,
+            memory,
+            sees_target: false,
+            last_known: None,
+            since_noticed: 0.0,
+            heard: Vec::new(),
+        }
+    }
+
+    // Keep heard noises pointing at the entities that made them; see Ai::entity_removed
+    pub fn entity_removed(&mut self, removed: usize) {
+        self.heard.retain_mut(|noise| match shift_index(noise.source, removed) {
+            Some(source) => {
+                noise.source = source;
+                true
+            },
+            None => false,
+        });
+    }
+}
+
+// Something made a sound at (x, y) this frame. Louder noises carry further: a
+// listener hears it within hearing_radius * volume.
+#[derive(Clone, Copy, Debug)]
+pub struct Noise {
+    pub x: f32,
+    pub y: f32,
+    pub volume: f32,
+    pub source: usize, // Entity index
+}
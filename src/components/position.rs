@@ -1,5 +1,10 @@
 // src/components/position.rs
-#[derive(Clone, Copy)]
+use serde::{Deserialize, Serialize};
+
+pub const FEET_OFFSET_X: f32 = 100.0;
+pub const FEET_OFFSET_Y: f32 = 170.0;
+
+#[derive(Clone, Copy, Serialize, Deserialize)]
 pub struct Position {
     pub x: f32,
     pub y: f32,
@@ -16,4 +21,10 @@ impl Position {
             facing_right,
         }
     }
-}
\ No newline at end of file
+    
+    // Point used for tile collision and triggers. Sprites are 100x100 frames drawn at 2x,
+    // so the feet sit near the bottom centre of the 200x200 box starting at (x, y).
+    pub fn feet(&self) -> (f32, f32) {
+        (self.x + FEET_OFFSET_X, self.y + FEET_OFFSET_Y)
+    }
+}
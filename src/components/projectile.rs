@@ -0,0 +1,32 @@
+use serde::{Deserialize, Serialize};
+
+// What a projectile does when it flies into a solid tile
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum TileHit {
+    Stop,   // Disappears
+    Bounce, // Reflects off the wall and keeps flying
+}
+
+// Something fired by an entity: moved by its Velocity, hurts what its sprite's
+// centre passes through (using its Damage) and disappears when it runs out of
+// lifetime or pierce
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Projectile {
+    pub lifetime: f32,        // Seconds left
+    pub pierce: u32,          // Further hurtboxes it can go through after a hit
+    pub on_tile: TileHit,
+    pub owner: Option<usize>, // Entity id of whoever fired it, which it never hits
+    pub hit: Vec<usize>,      // Entity ids already hit, so piercing doesn't hit twice
+}
+
+impl Projectile {
+    pub fn new(lifetime: f32, pierce: u32, on_tile: TileHit) -> Self {
+        Projectile {
+            lifetime,
+            pierce,
+            on_tile,
+            owner: None,
+            hit: Vec::new(),
+        }
+    }
+}
@@ -0,0 +1,9 @@
+use serde::{Deserialize, Serialize};
+
+// Lets an entity shoot: each time it starts a ranged attack it spawns the named
+// entity (which should have a Projectile) aimed at its target, or straight ahead
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct RangedAttack {
+    pub projectile: String,
+}
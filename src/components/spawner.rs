@@ -0,0 +1,88 @@
+use serde::{Deserialize, Serialize};
+
+// Spawner settings an entity definition can leave out
+pub const DEFAULT_SPAWN_INTERVAL: f32 = 5.0;
+pub const DEFAULT_MAX_ALIVE: usize = 3;
+pub const DEFAULT_WAVE_DELAY: f32 = 3.0;
+
+// One wave: definition names, spawned in order
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Wave {
+    pub enemies: Vec<String>,
+}
+
+// When a wave counts as beaten. The defaults wait for every enemy in it to die.
+#[derive(Clone, Copy, Debug, Default, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ClearCondition {
+    pub remaining: usize, // Cleared once no more than this many of its enemies are alive
+    pub timeout: f32,     // Or this many seconds after it started (0 = no limit), spawned or not
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum SpawnerPhase {
+    Continuous,                                         // No waves: keeps `entity` topped up
+    Waiting { wave: usize, remaining: f32 },            // Seconds until `wave` starts
+    Running { wave: usize, spawned: usize, elapsed: f32 },
+    Finished,                                           // Every wave cleared
+}
+
+// Brings enemies into the level around itself while the player is within
+// `activation_range` (see SpawnerSystem). Without waves it keeps up to `max_alive`
+// of `entity` around, one every `interval` seconds; with them it runs each wave in
+// turn, `wave_delay` seconds apart.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Spawner {
+    pub entity: String,
+    pub interval: f32,         // Seconds between spawns, within a wave too
+    pub max_alive: usize,      // Spawning pauses while this many of its enemies are alive
+    pub radius: f32,           // Pixels around the spawner's feet
+    pub activation_range: f32, // Pixels from the player; 0 = always active
+    pub waves: Vec<Wave>,
+    pub wave_delay: f32,
+    pub clear: ClearCondition,
+    pub phase: SpawnerPhase,
+    pub cooldown: f32,              // Seconds until the next spawn is allowed
+    pub spawned: u32,               // Total so far; seeds where the next one appears
+    pub alive: Vec<(usize, usize)>, // Entity id and wave of each enemy it spawned that's still alive
+}
+
+impl Spawner {
+    pub fn new(entity: String, waves: Vec<Wave>) -> Self {
+        // The first wave starts as soon as the player comes in range
+        let phase = if waves.is_empty() {
+            SpawnerPhase::Continuous
+        } else {
+            SpawnerPhase::Waiting { wave: 0, remaining: 0.0 }
+        };
+        Spawner {
+            entity,
+            interval: DEFAULT_SPAWN_INTERVAL,
+            max_alive: DEFAULT_MAX_ALIVE,
+            radius: 0.0,
+            activation_range: 0.0,
+            waves,
+            wave_delay: DEFAULT_WAVE_DELAY,
+            clear: ClearCondition::default(),
+            phase,
+            cooldown: 0.0,
+            spawned: 0,
+            alive: Vec::new(),
+        }
+    }
+
+    // Enemies from `wave` that are still alive
+    pub fn alive_in_wave(&self, wave: usize) -> usize {
+        self.alive.iter().filter(|(_, spawned_in)| *spawned_in == wave).count()
+    }
+}
+
+// Wave progress from the last update, for anything that wants to react (music,
+// doors, UI). `spawner` is the spawner's entity id.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum WaveEvent {
+    Started { spawner: usize, wave: usize },
+    Completed { spawner: usize, wave: usize },
+    Finished { spawner: usize }, // After the last wave's Completed
+}
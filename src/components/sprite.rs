@@ -0,0 +1,46 @@
+use super::Position;
+use serde::{Deserialize, Serialize};
+
+// How an entity's sheet is cut into frames. Entities without one use the
+// character layout: 100x100 frames from the top-left, drawn at 2x.
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Sprite {
+    #[serde(default = "default_frame_size")]
+    pub frame_width: u32,
+    #[serde(default = "default_frame_size")]
+    pub frame_height: u32,
+    #[serde(default)]
+    pub origin: (u32, u32), // Top-left of the first frame, for sprites cut from an atlas
+    #[serde(default = "default_scale")]
+    pub scale: f32,
+}
+
+fn default_frame_size() -> u32 {
+    100
+}
+
+fn default_scale() -> f32 {
+    2.0
+}
+
+impl Sprite {
+    // Middle of the drawn frame of an entity at `position`
+    pub fn center(&self, position: &Position) -> (f32, f32) {
+        (
+            position.x + self.frame_width as f32 * self.scale / 2.0,
+            position.y + self.frame_height as f32 * self.scale / 2.0,
+        )
+    }
+}
+
+impl Default for Sprite {
+    fn default() -> Self {
+        Sprite {
+            frame_width: default_frame_size(),
+            frame_height: default_frame_size(),
+            origin: (0, 0),
+            scale: default_scale(),
+        }
+    }
+}
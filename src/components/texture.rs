@@ -1,6 +1,40 @@
+use crate::assets::AssetError;
 use sdl2::render::TextureCreator;
 use sdl2::video::WindowContext;
 use sdl2::surface::Surface;
+use sdl2::pixels::PixelFormatEnum;
+use sdl2::image::ImageRWops;
+use sdl2::rwops::RWops;
+
+// Image decoded into plain RGBA32 bytes. Unlike a Surface this can be sent between
+// threads, so decoding can happen off the main thread.
+pub struct DecodedImage {
+    pub width: u32,
+    pub height: u32,
+    pub pitch: u32,
+    pub pixels: Vec<u8>,
+}
+
+// Read an image through the vfs and decode it (BMP or anything SDL2_image supports)
+fn load_surface(path: &str) -> Result<Surface<'static>, AssetError> {
+    let bytes = crate::vfs::read(path)?;
+    let decode_error = |message: String| AssetError::Decode { path: path.to_string(), message };
+    RWops::from_bytes(&bytes).map_err(decode_error)?.load().map_err(decode_error)
+}
+
+// Decode an image file. Safe to call from a worker thread.
+pub fn decode_image(path: &str) -> Result<DecodedImage, AssetError> {
+    let surface = load_surface(path)?;
+    let surface = surface.convert_format(PixelFormatEnum::RGBA32)
+        .map_err(|message| AssetError::Decode { path: path.to_string(), message })?;
+    
+    Ok(DecodedImage {
+        width: surface.width(),
+        height: surface.height(),
+        pitch: surface.pitch(),
+        pixels: surface.with_lock(|pixels| pixels.to_vec()),
+    })
+}
 
 pub struct Texture<'a> {
     pub path: String,
@@ -8,13 +42,20 @@ pub struct Texture<'a> {
 }
 
 impl<'a> Texture<'a> {
-    pub fn new(texture_creator: &'a TextureCreator<WindowContext>, path: &str) -> Result<Self, String> {
+    pub fn new(texture_creator: &'a TextureCreator<WindowContext>, path: &str) -> Result<Self, AssetError> {
         // Load surface first
-        let surface = Surface::load_bmp(path)
-            .or_else(|_| sdl2::image::LoadSurface::from_file(path))
-            .map_err(|e| e.to_string())?;
+        let surface = load_surface(path)?;
         
         // Create texture from surface
+        let texture = texture_creator.create_texture_from_surface(&surface)
+            .map_err(|e| AssetError::Decode { path: path.to_string(), message: e.to_string() })?;
+        
+        Ok(Texture { handle: texture, path: path.to_string() })
+    }
+
+    // Upload an image decoded elsewhere. Texture creation has to happen on the main thread.
+    pub fn from_image(texture_creator: &'a TextureCreator<WindowContext>, path: &str, image: &mut DecodedImage) -> Result<Self, String> {
+        let surface = Surface::from_data(&mut image.pixels, image.width, image.height, image.pitch, PixelFormatEnum::RGBA32)?;
         let texture = texture_creator.create_texture_from_surface(&surface)
             .map_err(|e| e.to_string())?;
         
@@ -1,7 +1,7 @@
+use crate::assets::Handle;
 use crate::components::texture::Texture;
-use std::fs::File;
-use std::io::{BufRead, BufReader};
-use std::sync::Arc; // Remove the duplicate import
+use serde::{Deserialize, Serialize};
+use std::io::BufRead;
 use std::collections::HashMap;
 
 pub struct Tilemap<'a> {
@@ -9,13 +9,31 @@ pub struct Tilemap<'a> {
     pub height: usize,
     pub tile_size: u32,
     pub tiles: Vec<Vec<TileId>>,
-    pub tileset: Option<Tileset<'a>>,
+    pub tileset: Option<Handle<Tileset<'a>>>,
     pub tile_textures: HashMap<TileId, Texture<'a>>,
+    pub tile_damage: HashMap<(usize, usize), u32>, // Damage taken so far by destructible tiles
+    changes: Vec<TileChange>,                      // Edits since the last take_changes()
 }
 
-#[derive(Copy, Clone, PartialEq, Eq, Hash)]
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug, Serialize, Deserialize)]
 pub struct TileId(pub u32);
 
+// Emitted whenever a tile changes at runtime so caches can update just that cell
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct TileChange {
+    pub x: usize,
+    pub y: usize,
+    pub old: TileId,
+    pub new: TileId,
+}
+
+// A tile that breaks (or opens) after taking enough hits
+#[derive(Copy, Clone, Debug)]
+pub struct DestructibleTile {
+    pub hit_points: u32,
+    pub becomes: TileId,
+}
+
 #[derive(Copy, Clone, PartialEq, Eq)]
 pub enum TileType {
     Empty,
@@ -25,6 +43,21 @@ pub enum TileType {
     Lava,
 }
 
+impl TileType {
+    pub fn is_solid(self) -> bool {
+        self == TileType::Wall || self == TileType::Lava
+    }
+    
+    // Relative cost of walking through one tile, for pathfinding. None = impassable.
+    pub fn movement_cost(self) -> Option<f32> {
+        match self {
+            TileType::Wall | TileType::Lava => None,
+            TileType::Water => Some(3.0), // Wadeable, but worth a detour
+            TileType::Empty | TileType::Floor => Some(1.0),
+        }
+    }
+}
+
 impl<'a> Tilemap<'a> {
     pub fn new(width: usize, height: usize, tile_size: u32) -> Self {
         // Create empty tilemap
@@ -37,6 +70,8 @@ impl<'a> Tilemap<'a> {
             tiles,
             tileset: None,
             tile_textures: HashMap::new(),
+            tile_damage: HashMap::new(),
+            changes: Vec::new(),
         }
     }
     
@@ -50,15 +85,41 @@ impl<'a> Tilemap<'a> {
     
     pub fn set_tile(&mut self, x: usize, y: usize, tile: TileId) {
         if x < self.width && y < self.height {
-            self.tiles[y][x] = tile;
+            let old = self.tiles[y][x];
+            if old != tile {
+                self.tiles[y][x] = tile;
+                self.tile_damage.remove(&(x, y));
+                self.changes.push(TileChange { x, y, old, new: tile });
+            }
         }
     }
     
+    // Apply damage to a destructible tile. Returns true if this hit broke it.
+    pub fn damage_tile(&mut self, x: usize, y: usize, amount: u32, destructible: &HashMap<TileId, DestructibleTile>) -> bool {
+        let rule = match self.get_tile(x, y).and_then(|tile_id| destructible.get(tile_id)) {
+            Some(rule) => *rule,
+            None => return false,
+        };
+        
+        let damage = self.tile_damage.entry((x, y)).or_insert(0);
+        *damage += amount;
+        if *damage >= rule.hit_points {
+            self.set_tile(x, y, rule.becomes);
+            return true;
+        }
+        false
+    }
+    
+    // Hand over every change made since the last call
+    pub fn take_changes(&mut self) -> Vec<TileChange> {
+        std::mem::take(&mut self.changes)
+    }
+    
     pub fn is_solid(&self, x: usize, y: usize, tile_types: &HashMap<TileId, TileType>) -> bool {
         match self.get_tile(x, y) {
             Some(tile_id) => {
                 match tile_types.get(tile_id) {
-                    Some(tile_type) => *tile_type == TileType::Wall || *tile_type == TileType::Lava,
+                    Some(tile_type) => tile_type.is_solid(),
                     _ => false,
                 }
             },
@@ -66,73 +127,74 @@ impl<'a> Tilemap<'a> {
         }
     }
 
-    pub fn load_from_file(
-        file_path: &str, 
-        tileset_texture: &Arc<Texture<'a>>, 
-        tile_size: usize
-    ) -> Result<Self, String> {
-        // Open and read the CSV file
-        let file = File::open(file_path)
-            .map_err(|e| format!("Failed to open tilemap file {}: {}", file_path, e))?;
-        
-        let reader = BufReader::new(file);
-        let mut tiles = Vec::new();
-        let mut width = 0;
-        
-        // Parse each line
-        for line in reader.lines() {
-            let line = line.map_err(|e| format!("Failed to read line: {}", e))?;
-            if line.trim().is_empty() {
-                continue;
-            }
-            
-            let row: Vec<TileId> = line
-                .split(',')
-                .filter_map(|s| {
-                    s.trim().parse::<u32>().ok().map(TileId)
-                })
-                .collect();
-                
-            if width == 0 {
-                width = row.len();
-            } else if row.len() != width {
-                return Err(format!("Inconsistent row length in tilemap file"));
-            }
-            
-            tiles.push(row);
+    pub fn to_csv(&self) -> String {
+        let mut csv = String::new();
+        for row in &self.tiles {
+            let line: Vec<String> = row.iter().map(|tile| tile.0.to_string()).collect();
+            csv.push_str(&line.join(","));
+            csv.push('\n');
         }
-        
-        if tiles.is_empty() {
-            return Err("Empty tilemap file".to_string());
-        }
-        
-        let height = tiles.len();
-        
-        // Create tilemap
-        let mut tilemap = Tilemap {
-            width,
-            height,
-            tile_size: tile_size as u32,
+        csv
+    }
+    
+    pub fn save_to_file(&self, file_path: &str) -> Result<(), String> {
+        crate::vfs::write(file_path, self.to_csv().as_bytes())
+            .map_err(|e| format!("Failed to write tilemap file {}: {}", file_path, e))
+    }
+
+    // Build a tilemap around already parsed tile rows (see AssetServer::create_tilemap)
+    pub fn from_tiles(tiles: Vec<Vec<TileId>>, tile_size: u32, tileset: Option<Handle<Tileset<'a>>>) -> Self {
+        Tilemap {
+            width: tiles.first().map_or(0, |row| row.len()),
+            height: tiles.len(),
+            tile_size,
             tiles,
-            tileset: None,
+            tileset,
             tile_textures: HashMap::new(),
-        };
+            tile_damage: HashMap::new(),
+            changes: Vec::new(),
+        }
+    }
+}
+
+// Parse a CSV tile grid (one row per line, comma separated tile IDs).
+// Shared by whole-level maps and streamed chunks so both accept the same format.
+pub fn parse_tile_csv<R: BufRead>(reader: R) -> Result<Vec<Vec<TileId>>, String> {
+    let mut tiles = Vec::new();
+    let mut width = 0;
+    
+    // Parse each line
+    for line in reader.lines() {
+        let line = line.map_err(|e| format!("Failed to read line: {}", e))?;
+        if line.trim().is_empty() {
+            continue;
+        }
         
-        // Create tileset from the provided texture
-        // Fix this to match the definition of Tileset::new
-        let tileset = Tileset::new(
-            tileset_texture.clone(), 
-            tile_size as u32, 
-            tile_size as u32 // Adding the missing tile_height parameter
-        );
-        tilemap.tileset = Some(tileset);
+        let row: Vec<TileId> = line
+            .split(',')
+            .filter_map(|s| {
+                s.trim().parse::<u32>().ok().map(TileId)
+            })
+            .collect();
+            
+        if width == 0 {
+            width = row.len();
+        } else if row.len() != width {
+            return Err(format!("Inconsistent row length in tilemap file"));
+        }
         
-        Ok(tilemap)
+        tiles.push(row);
     }
+    
+    if tiles.is_empty() {
+        return Err("Empty tilemap file".to_string());
+    }
+    
+    Ok(tiles)
 }
 
 pub struct Tileset<'a> {
-    pub texture: Arc<Texture<'a>>,
+    pub texture: Handle<Texture<'a>>,
     pub tile_width: u32,
     pub tile_height: u32,
     pub columns: u32,
@@ -140,10 +202,9 @@ pub struct Tileset<'a> {
 }
 
 impl<'a> Tileset<'a> {
-    pub fn new(texture: Arc<Texture<'a>>, tile_width: u32, tile_height: u32) -> Self {
-        let texture_query = texture.handle.query();
-        let columns = texture_query.width / tile_width;
-        let rows = texture_query.height / tile_height;
+    pub fn new(texture: Handle<Texture<'a>>, texture_width: u32, texture_height: u32, tile_width: u32, tile_height: u32) -> Self {
+        let columns = texture_width / tile_width;
+        let rows = texture_height / tile_height;
         
         Tileset {
             texture,
@@ -0,0 +1,17 @@
+use serde::{Deserialize, Serialize};
+
+// Constant drift in pixels per second, for things that move without walking
+// (projectiles, thrown items). Ignores tile collision.
+#[derive(Clone, Copy, Debug, Default, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Velocity {
+    pub x: f32,
+    pub y: f32,
+}
+
+impl Velocity {
+    // Direction of travel in degrees, clockwise from pointing right (as canvas.copy_ex rotates)
+    pub fn angle(&self) -> f64 {
+        (self.y as f64).atan2(self.x as f64).to_degrees()
+    }
+}
@@ -0,0 +1,44 @@
+use crate::components::TileChange;
+use crate::level_definitions::SpawnDefinition;
+
+// One undoable editor operation. A whole paint stroke, fill or rectangle is a single
+// Tiles action so one undo reverts all of it.
+#[derive(Clone, Debug)]
+pub enum EditAction {
+    Tiles(Vec<TileChange>),
+    AddSpawn(usize, SpawnDefinition),
+    RemoveSpawn(usize, SpawnDefinition),
+    MoveSpawn(usize, (f32, f32), (f32, f32)),
+}
+
+pub struct History {
+    undo_stack: Vec<EditAction>,
+    redo_stack: Vec<EditAction>,
+}
+
+impl History {
+    pub fn new() -> Self {
+        History {
+            undo_stack: Vec::new(),
+            redo_stack: Vec::new(),
+        }
+    }
+    
+    // Record a new action; anything that was undone can no longer be redone
+    pub fn push(&mut self, action: EditAction) {
+        self.undo_stack.push(action);
+        self.redo_stack.clear();
+    }
+    
+    pub fn undo(&mut self) -> Option<EditAction> {
+        let action = self.undo_stack.pop()?;
+        self.redo_stack.push(action.clone());
+        Some(action)
+    }
+    
+    pub fn redo(&mut self) -> Option<EditAction> {
+        let action = self.redo_stack.pop()?;
+        self.undo_stack.push(action.clone());
+        Some(action)
+    }
+}
@@ -0,0 +1,432 @@
+// In-game level editor. Toggled with F1; while active, gameplay is paused and the
+// mouse paints tiles onto the tile layer or places spawn points on the object layer.
+pub mod history;
+
+use crate::assets::AssetServer;
+use crate::components::position::{FEET_OFFSET_X, FEET_OFFSET_Y};
+use crate::components::{TileChange, TileId, Tilemap};
+use crate::level_definitions::{LevelDefinition, SpawnDefinition};
+use self::history::{EditAction, History};
+use sdl2::event::Event;
+use sdl2::keyboard::{Keycode, Mod, Scancode};
+use sdl2::mouse::MouseButton;
+use std::collections::VecDeque;
+
+pub const SCREEN_WIDTH: i32 = 800;
+pub const SCREEN_HEIGHT: i32 = 600;
+
+// Tile palette panel along the right edge of the screen
+pub const PALETTE_COLUMNS: i32 = 4;
+pub const PALETTE_TILE_SIZE: i32 = 32;
+pub const PALETTE_PADDING: i32 = 8;
+pub const PALETTE_WIDTH: i32 = PALETTE_COLUMNS * PALETTE_TILE_SIZE + PALETTE_PADDING * 2;
+
+// Half-size of the clickable marker drawn at a spawn point's feet
+pub const SPAWN_MARKER_RADIUS: f32 = 10.0;
+
+const CAMERA_PAN_SPEED: i32 = 8;
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum EditorTool {
+    Paint,
+    Erase,
+    Fill,
+    Rectangle,
+    Spawn,
+}
+
+// Everything the editor is allowed to touch, borrowed from GameState for one call
+pub struct EditorContext<'m, 'a> {
+    pub assets: &'m AssetServer<'a>,
+    pub tilemap: &'m mut Tilemap<'a>,
+    pub level: &'m mut LevelDefinition,
+    pub level_path: &'m str,
+    pub camera_x: i32,
+    pub camera_y: i32,
+}
+
+pub struct Editor {
+    pub active: bool,
+    pub tool: EditorTool,
+    pub selected_tile: TileId,
+    pub entity_names: Vec<String>, // Definitions from entities.ron that can be placed
+    pub selected_entity: usize,
+    pub palette_scroll: i32, // First visible palette row
+    pub mouse_x: i32,
+    pub mouse_y: i32,
+    pub rect_start: Option<(usize, usize)>, // Tile where a rectangle drag began
+    history: History,
+    stroke: Vec<TileChange>, // Paint/erase edits made since the mouse went down
+    painting: bool,
+    dragging_spawn: Option<(usize, (f32, f32))>, // Spawn index and where it started
+}
+
+impl Editor {
+    pub fn new(entity_names: Vec<String>) -> Self {
+        Editor {
+            active: false,
+            tool: EditorTool::Paint,
+            selected_tile: TileId(1),
+            entity_names,
+            selected_entity: 0,
+            palette_scroll: 0,
+            mouse_x: 0,
+            mouse_y: 0,
+            rect_start: None,
+            history: History::new(),
+            stroke: Vec::new(),
+            painting: false,
+            dragging_spawn: None,
+        }
+    }
+
+    pub fn toggle(&mut self) {
+        self.active = !self.active;
+        self.painting = false;
+        self.rect_start = None;
+        self.dragging_spawn = None;
+        println!("Editor {}", if self.active { "enabled" } else { "disabled" });
+    }
+
+    pub fn palette_x() -> i32 {
+        SCREEN_WIDTH - PALETTE_WIDTH
+    }
+
+    pub fn is_over_palette(&self) -> bool {
+        self.mouse_x >= Self::palette_x()
+    }
+
+    // Tile under the mouse cursor, if it's over the map area
+    pub fn hovered_tile(&self, tilemap: &Tilemap, camera_x: i32, camera_y: i32) -> Option<(usize, usize)> {
+        if self.is_over_palette() {
+            return None;
+        }
+        let world_x = self.mouse_x + camera_x;
+        let world_y = self.mouse_y + camera_y;
+        if world_x < 0 || world_y < 0 {
+            return None;
+        }
+        let tile_x = (world_x / tilemap.tile_size as i32) as usize;
+        let tile_y = (world_y / tilemap.tile_size as i32) as usize;
+        if tile_x < tilemap.width && tile_y < tilemap.height {
+            Some((tile_x, tile_y))
+        } else {
+            None
+        }
+    }
+
+    // Pan the camera with the movement keys
+    pub fn update_camera(&self, keyboard_state: &sdl2::keyboard::KeyboardState, camera_x: &mut i32, camera_y: &mut i32) {
+        if keyboard_state.is_scancode_pressed(Scancode::Left) || keyboard_state.is_scancode_pressed(Scancode::A) {
+            *camera_x -= CAMERA_PAN_SPEED;
+        }
+        if keyboard_state.is_scancode_pressed(Scancode::Right) || keyboard_state.is_scancode_pressed(Scancode::D) {
+            *camera_x += CAMERA_PAN_SPEED;
+        }
+        if keyboard_state.is_scancode_pressed(Scancode::Up) || keyboard_state.is_scancode_pressed(Scancode::W) {
+            *camera_y -= CAMERA_PAN_SPEED;
+        }
+        if keyboard_state.is_scancode_pressed(Scancode::Down) || keyboard_state.is_scancode_pressed(Scancode::S) {
+            *camera_y += CAMERA_PAN_SPEED;
+        }
+    }
+
+    pub fn handle_event(&mut self, event: &Event, ctx: &mut EditorContext) {
+        match event {
+            Event::KeyDown { keycode: Some(keycode), keymod, repeat: false, .. } => {
+                let ctrl = keymod.intersects(Mod::LCTRLMOD | Mod::RCTRLMOD);
+                self.handle_key(*keycode, ctrl, ctx);
+            },
+            Event::MouseMotion { x, y, .. } => {
+                self.mouse_x = *x;
+                self.mouse_y = *y;
+                self.handle_drag(ctx);
+            },
+            Event::MouseButtonDown { mouse_btn, x, y, .. } => {
+                self.mouse_x = *x;
+                self.mouse_y = *y;
+                self.handle_mouse_down(*mouse_btn, ctx);
+            },
+            Event::MouseButtonUp { mouse_btn: MouseButton::Left, x, y, .. } => {
+                self.mouse_x = *x;
+                self.mouse_y = *y;
+                self.handle_mouse_up(ctx);
+            },
+            Event::MouseWheel { y, .. } if self.is_over_palette() => {
+                self.palette_scroll = (self.palette_scroll - y).max(0);
+            },
+            _ => {}
+        }
+    }
+
+    fn handle_key(&mut self, keycode: Keycode, ctrl: bool, ctx: &mut EditorContext) {
+        match keycode {
+            Keycode::Z if ctrl => self.undo(ctx),
+            Keycode::Y if ctrl => self.redo(ctx),
+            Keycode::S if ctrl => match Self::save(ctx) {
+                Ok(()) => println!("Saved level {}", ctx.level_path),
+                Err(e) => eprintln!("Failed to save level: {}", e),
+            },
+            Keycode::Num1 => self.tool = EditorTool::Paint,
+            Keycode::Num2 => self.tool = EditorTool::Erase,
+            Keycode::Num3 => self.tool = EditorTool::Fill,
+            Keycode::Num4 => self.tool = EditorTool::Rectangle,
+            Keycode::Num5 => self.tool = EditorTool::Spawn,
+            Keycode::LeftBracket if !self.entity_names.is_empty() => {
+                self.selected_entity = (self.selected_entity + self.entity_names.len() - 1) % self.entity_names.len();
+            },
+            Keycode::RightBracket if !self.entity_names.is_empty() => {
+                self.selected_entity = (self.selected_entity + 1) % self.entity_names.len();
+            },
+            _ => {}
+        }
+    }
+
+    fn handle_mouse_down(&mut self, button: MouseButton, ctx: &mut EditorContext) {
+        if self.is_over_palette() {
+            if button == MouseButton::Left {
+                self.pick_palette_tile(ctx.assets, ctx.tilemap);
+            }
+            return;
+        }
+
+        if self.tool == EditorTool::Spawn {
+            match button {
+                MouseButton::Left => self.grab_or_place_spawn(ctx),
+                MouseButton::Right => self.remove_spawn(ctx),
+                _ => {}
+            }
+            return;
+        }
+
+        if button != MouseButton::Left {
+            return;
+        }
+        let tile = match self.hovered_tile(ctx.tilemap, ctx.camera_x, ctx.camera_y) {
+            Some(tile) => tile,
+            None => return,
+        };
+
+        match self.tool {
+            EditorTool::Paint | EditorTool::Erase => {
+                self.painting = true;
+                self.stroke.clear();
+                self.paint_at(tile, ctx.tilemap);
+            },
+            EditorTool::Fill => {
+                let changes = Self::flood_fill(ctx.tilemap, tile, self.selected_tile);
+                if !changes.is_empty() {
+                    self.history.push(EditAction::Tiles(changes));
+                }
+            },
+            EditorTool::Rectangle => self.rect_start = Some(tile),
+            EditorTool::Spawn => {},
+        }
+    }
+
+    fn handle_drag(&mut self, ctx: &mut EditorContext) {
+        if self.painting
+            && let Some(tile) = self.hovered_tile(ctx.tilemap, ctx.camera_x, ctx.camera_y) {
+            self.paint_at(tile, ctx.tilemap);
+        }
+
+        if let Some((index, _)) = self.dragging_spawn {
+            let (x, y) = self.spawn_position_under_mouse(ctx);
+            if let Some(spawn) = ctx.level.spawns.get_mut(index) {
+                spawn.x = x;
+                spawn.y = y;
+            }
+        }
+    }
+
+    fn handle_mouse_up(&mut self, ctx: &mut EditorContext) {
+        if self.painting {
+            self.painting = false;
+            if !self.stroke.is_empty() {
+                self.history.push(EditAction::Tiles(std::mem::take(&mut self.stroke)));
+            }
+        }
+
+        if let Some(start) = self.rect_start.take()
+            && let Some(end) = self.hovered_tile(ctx.tilemap, ctx.camera_x, ctx.camera_y) {
+            let changes = Self::fill_rect(ctx.tilemap, start, end, self.selected_tile);
+            if !changes.is_empty() {
+                self.history.push(EditAction::Tiles(changes));
+            }
+        }
+
+        if let Some((index, from)) = self.dragging_spawn.take()
+            && let Some(spawn) = ctx.level.spawns.get(index) {
+            let to = (spawn.x, spawn.y);
+            if to != from {
+                self.history.push(EditAction::MoveSpawn(index, from, to));
+            }
+        }
+    }
+
+    fn paint_at(&mut self, tile: (usize, usize), tilemap: &mut Tilemap) {
+        let new = if self.tool == EditorTool::Erase { TileId(0) } else { self.selected_tile };
+        if let Some(change) = Self::set_tile(tilemap, tile.0, tile.1, new) {
+            self.stroke.push(change);
+        }
+    }
+
+    fn set_tile(tilemap: &mut Tilemap, x: usize, y: usize, new: TileId) -> Option<TileChange> {
+        let old = *tilemap.get_tile(x, y)?;
+        if old == new {
+            return None;
+        }
+        tilemap.set_tile(x, y, new);
+        Some(TileChange { x, y, old, new })
+    }
+
+    // 4-way flood fill of the contiguous area sharing the clicked tile's ID
+    pub fn flood_fill(tilemap: &mut Tilemap, start: (usize, usize), new: TileId) -> Vec<TileChange> {
+        let target = match tilemap.get_tile(start.0, start.1) {
+            Some(tile) if *tile != new => *tile,
+            _ => return Vec::new(),
+        };
+
+        let mut changes = Vec::new();
+        let mut queue = VecDeque::new();
+        queue.push_back(start);
+
+        while let Some((x, y)) = queue.pop_front() {
+            if tilemap.get_tile(x, y) != Some(&target) {
+                continue;
+            }
+            if let Some(change) = Self::set_tile(tilemap, x, y, new) {
+                changes.push(change);
+            }
+            if x > 0 { queue.push_back((x - 1, y)); }
+            if y > 0 { queue.push_back((x, y - 1)); }
+            if x + 1 < tilemap.width { queue.push_back((x + 1, y)); }
+            if y + 1 < tilemap.height { queue.push_back((x, y + 1)); }
+        }
+        changes
+    }
+
+    pub fn fill_rect(tilemap: &mut Tilemap, a: (usize, usize), b: (usize, usize), new: TileId) -> Vec<TileChange> {
+        let mut changes = Vec::new();
+        for y in a.1.min(b.1)..=a.1.max(b.1) {
+            for x in a.0.min(b.0)..=a.0.max(b.0) {
+                if let Some(change) = Self::set_tile(tilemap, x, y, new) {
+                    changes.push(change);
+                }
+            }
+        }
+        changes
+    }
+
+    fn pick_palette_tile(&mut self, assets: &AssetServer, tilemap: &Tilemap) {
+        let tileset = match tilemap.tileset.as_ref().and_then(|handle| assets.tileset(handle)) {
+            Some(tileset) => tileset,
+            None => return,
+        };
+
+        let column = (self.mouse_x - Self::palette_x() - PALETTE_PADDING) / PALETTE_TILE_SIZE;
+        let row = (self.mouse_y - PALETTE_PADDING) / PALETTE_TILE_SIZE + self.palette_scroll;
+        if !(0..PALETTE_COLUMNS).contains(&column) || row < 0 {
+            return;
+        }
+
+        let index = (row * PALETTE_COLUMNS + column) as u32;
+        if index < tileset.columns * tileset.rows {
+            // Tile IDs are 1-based; 0 is the empty tile
+            self.selected_tile = TileId(index + 1);
+        }
+    }
+
+    // Entity position that puts its feet under the mouse cursor
+    fn spawn_position_under_mouse(&self, ctx: &EditorContext) -> (f32, f32) {
+        (
+            (self.mouse_x + ctx.camera_x) as f32 - FEET_OFFSET_X,
+            (self.mouse_y + ctx.camera_y) as f32 - FEET_OFFSET_Y,
+        )
+    }
+
+    fn spawn_under_mouse(&self, ctx: &EditorContext) -> Option<usize> {
+        let world_x = (self.mouse_x + ctx.camera_x) as f32;
+        let world_y = (self.mouse_y + ctx.camera_y) as f32;
+        ctx.level.spawns.iter().rposition(|spawn| {
+            (spawn.x + FEET_OFFSET_X - world_x).abs() <= SPAWN_MARKER_RADIUS
+                && (spawn.y + FEET_OFFSET_Y - world_y).abs() <= SPAWN_MARKER_RADIUS
+        })
+    }
+
+    fn grab_or_place_spawn(&mut self, ctx: &mut EditorContext) {
+        if let Some(index) = self.spawn_under_mouse(ctx) {
+            let spawn = &ctx.level.spawns[index];
+            self.dragging_spawn = Some((index, (spawn.x, spawn.y)));
+            return;
+        }
+
+        let name = match self.entity_names.get(self.selected_entity) {
+            Some(name) => name,
+            None => return,
+        };
+        let (x, y) = self.spawn_position_under_mouse(ctx);
+        let spawn = SpawnDefinition::new(name, x, y);
+        ctx.level.spawns.push(spawn.clone());
+        self.history.push(EditAction::AddSpawn(ctx.level.spawns.len() - 1, spawn));
+    }
+
+    fn remove_spawn(&mut self, ctx: &mut EditorContext) {
+        if let Some(index) = self.spawn_under_mouse(ctx) {
+            let spawn = ctx.level.spawns.remove(index);
+            self.history.push(EditAction::RemoveSpawn(index, spawn));
+        }
+    }
+
+    pub fn undo(&mut self, ctx: &mut EditorContext) {
+        match self.history.undo() {
+            Some(EditAction::Tiles(changes)) => {
+                for change in changes.iter().rev() {
+                    ctx.tilemap.set_tile(change.x, change.y, change.old);
+                }
+            },
+            Some(EditAction::AddSpawn(index, _)) if index < ctx.level.spawns.len() => {
+                ctx.level.spawns.remove(index);
+            },
+            Some(EditAction::RemoveSpawn(index, spawn)) => {
+                ctx.level.spawns.insert(index.min(ctx.level.spawns.len()), spawn);
+            },
+            Some(EditAction::MoveSpawn(index, from, _)) => {
+                if let Some(spawn) = ctx.level.spawns.get_mut(index) {
+                    spawn.x = from.0;
+                    spawn.y = from.1;
+                }
+            },
+            Some(EditAction::AddSpawn(..)) | None => {}, // Spawn already gone
+        }
+    }
+
+    pub fn redo(&mut self, ctx: &mut EditorContext) {
+        match self.history.redo() {
+            Some(EditAction::Tiles(changes)) => {
+                for change in &changes {
+                    ctx.tilemap.set_tile(change.x, change.y, change.new);
+                }
+            },
+            Some(EditAction::AddSpawn(index, spawn)) => {
+                ctx.level.spawns.insert(index.min(ctx.level.spawns.len()), spawn);
+            },
+            Some(EditAction::RemoveSpawn(index, _)) if index < ctx.level.spawns.len() => {
+                ctx.level.spawns.remove(index);
+            },
+            Some(EditAction::MoveSpawn(index, _, to)) => {
+                if let Some(spawn) = ctx.level.spawns.get_mut(index) {
+                    spawn.x = to.0;
+                    spawn.y = to.1;
+                }
+            },
+            Some(EditAction::RemoveSpawn(..)) | None => {}, // Spawn already gone
+        }
+    }
+
+    // Write the tile layer back to its CSV and the object layer to the level file
+    pub fn save(ctx: &EditorContext) -> Result<(), String> {
+        ctx.tilemap.save_to_file(&ctx.level.tilemap)?;
+        ctx.level.save(ctx.level_path)
+    }
+}
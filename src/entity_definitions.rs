@@ -1,21 +1,89 @@
-
+use crate::behaviour_tree::{BehaviourNode, BehaviourTree};
+use crate::components::ai::{DEFAULT_ATTACK_COOLDOWN, DEFAULT_LEASH_RANGE, DEFAULT_SEARCH_TIME, DEFAULT_WIND_UP};
+use crate::factions::{FactionDefinition, FactionTable};
+use crate::utility_ai::reasoner::DEFAULT_HYSTERESIS;
+use crate::utility_ai::UtilityOption;
+use ron::extensions::Extensions;
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 
 
-#[derive(Debug, Deserialize, Serialize, Clone)]
-pub struct EntityDefinition {
-    pub entity_type: String,
-    pub health: u32,
-    pub max_health: u32,
-    pub speed: f32,
-    pub textures: HashMap<String, String>,
-    pub animation_frames: HashMap<String, usize>,
-    pub ai_type: Option<String>,
-    pub collision: CollisionInfo,
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Eq)]
+pub enum EntityType {
+    Player,
+    Enemy,
+}
+
+// One sprite sheet: `frames` frames laid out left to right
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct AnimationDefinition {
+    pub texture: String,
+    pub frames: usize,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+pub struct Animations {
+    pub idle: AnimationDefinition,
+    pub walk: AnimationDefinition,
+    pub attack: AnimationDefinition,
+}
+
+impl Animations {
+    // In the order the render system indexes textures (see assets::ANIMATION_TYPES)
+    pub fn in_order(&self) -> [&AnimationDefinition; 3] {
+        [&self.idle, &self.walk, &self.attack]
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+pub enum AiDefinition {
+    Idle,
+    Patrol, // Waypoints come from the level's spawn
+    // Only the ranges are required; see components::ai::Chase for the rest
+    Chase {
+        detection_range: f32,
+        attack_range: f32,
+        #[serde(default = "default_leash_range")]
+        leash_range: f32,
+        #[serde(default = "default_attack_cooldown")]
+        attack_cooldown: f32,
+        #[serde(default = "default_wind_up")]
+        wind_up: f32,
+        #[serde(default = "default_search_time")]
+        search_time: f32,
+    },
+    Behaviour(String), // A tree from the file's `behaviours`
+    // Does whichever option scores best; see utility_ai::reasoner
+    Utility {
+        options: Vec<UtilityOption>,
+        #[serde(default = "default_hysteresis")]
+        hysteresis: f32,
+    },
+}
+
+fn default_leash_range() -> f32 {
+    DEFAULT_LEASH_RANGE
+}
+
+fn default_attack_cooldown() -> f32 {
+    DEFAULT_ATTACK_COOLDOWN
+}
+
+fn default_wind_up() -> f32 {
+    DEFAULT_WIND_UP
+}
+
+fn default_search_time() -> f32 {
+    DEFAULT_SEARCH_TIME
+}
+
+fn default_hysteresis() -> f32 {
+    DEFAULT_HYSTERESIS
 }
 
 #[derive(Debug, Deserialize, Serialize, Clone)]
+#[serde(deny_unknown_fields)]
 pub struct CollisionInfo {
     pub width: f32,
     pub height: f32,
@@ -23,7 +91,285 @@ pub struct CollisionInfo {
     pub offset_y: f32,
 }
 
-#[derive(Debug, Deserialize, Serialize)]
+// A fully resolved definition, with everything inherited from its parents filled in.
+// The factory only builds the components a definition actually has, so a prop can
+// leave out health, animations and AI entirely.
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct EntityDefinition {
+    pub entity_type: Option<EntityType>,
+    pub health: Option<u32>,
+    pub max_health: Option<u32>, // Entities without one have no Health component
+    pub speed: f32,
+    pub animations: Option<Animations>,
+    pub ai: AiDefinition,
+    pub collision: Option<CollisionInfo>,
+    pub components: BTreeMap<String, ron::Value>, // Extra components by registered name, see ComponentRegistry
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, Default)]
+#[serde(deny_unknown_fields)]
+pub struct AnimationOverrides {
+    #[serde(default)]
+    pub idle: Option<AnimationDefinition>,
+    #[serde(default)]
+    pub walk: Option<AnimationDefinition>,
+    #[serde(default)]
+    pub attack: Option<AnimationDefinition>,
+}
+
+// An entry as written in entities.ron. Every field is optional so a definition can
+// name a `parent` (a template or another entity) and only override what differs.
+#[derive(Debug, Deserialize, Serialize, Clone, Default)]
+#[serde(deny_unknown_fields)]
+pub struct EntityTemplate {
+    #[serde(default)]
+    pub parent: Option<String>,
+    #[serde(default)]
+    pub entity_type: Option<EntityType>,
+    #[serde(default)]
+    pub health: Option<u32>,
+    #[serde(default)]
+    pub max_health: Option<u32>,
+    #[serde(default)]
+    pub speed: Option<f32>,
+    #[serde(default)]
+    pub animations: AnimationOverrides,
+    #[serde(default)]
+    pub ai: Option<AiDefinition>,
+    #[serde(default)]
+    pub collision: Option<CollisionInfo>,
+    #[serde(default)]
+    pub components: BTreeMap<String, ron::Value>,
+}
+
+impl EntityTemplate {
+    // `self` on top of `parent`: anything set here wins
+    fn inherit(&self, parent: &EntityTemplate) -> EntityTemplate {
+        EntityTemplate {
+            parent: None,
+            entity_type: self.entity_type.or(parent.entity_type),
+            health: self.health.or(parent.health),
+            max_health: self.max_health.or(parent.max_health),
+            speed: self.speed.or(parent.speed),
+            animations: AnimationOverrides {
+                idle: self.animations.idle.clone().or_else(|| parent.animations.idle.clone()),
+                walk: self.animations.walk.clone().or_else(|| parent.animations.walk.clone()),
+                attack: self.animations.attack.clone().or_else(|| parent.animations.attack.clone()),
+            },
+            ai: self.ai.clone().or_else(|| parent.ai.clone()),
+            collision: self.collision.clone().or_else(|| parent.collision.clone()),
+            // Merged per component, so a child can tweak one without repeating the rest
+            components: parent.components.iter()
+                .chain(self.components.iter())
+                .map(|(name, value)| (name.clone(), value.clone()))
+                .collect(),
+        }
+    }
+
+    fn into_definition(self, name: &str) -> Result<EntityDefinition, String> {
+        // Animations come as a set: the render system expects all three sheets
+        let animations = match (self.animations.idle, self.animations.walk, self.animations.attack) {
+            (Some(idle), Some(walk), Some(attack)) => Some(Animations { idle, walk, attack }),
+            (None, None, None) => None,
+            (idle, walk, _) => {
+                let missing = if idle.is_none() { "idle" } else if walk.is_none() { "walk" } else { "attack" };
+                return Err(format!("Entity {} has no {} animation (and none of its parents set it)", name, missing));
+            },
+        };
+        if self.health.is_some() && self.max_health.is_none() {
+            return Err(format!("Entity {} sets health but has no max_health", name));
+        }
+
+        Ok(EntityDefinition {
+            entity_type: self.entity_type,
+            health: self.health.or(self.max_health),
+            max_health: self.max_health,
+            speed: self.speed.unwrap_or(0.0),
+            animations,
+            ai: self.ai.unwrap_or(AiDefinition::Idle),
+            collision: self.collision,
+            components: self.components,
+        })
+    }
+}
+
+// entities.ron as written. Templates can only be inherited from; entities can be
+// spawned (and inherited from too). Behaviours are AI trees entities refer to by name,
+// and factions say which sides are hostile to each other.
+#[derive(Debug, Deserialize, Serialize, Default)]
+#[serde(deny_unknown_fields)]
+pub struct EntityDefinitionsFile {
+    #[serde(default)]
+    pub templates: HashMap<String, EntityTemplate>,
+    pub entities: HashMap<String, EntityTemplate>,
+    #[serde(default)]
+    pub behaviours: HashMap<String, BehaviourNode>,
+    #[serde(default)]
+    pub factions: HashMap<String, FactionDefinition>,
+}
+
+impl EntityDefinitionsFile {
+    fn template(&self, name: &str) -> Option<&EntityTemplate> {
+        self.entities.get(name).or_else(|| self.templates.get(name))
+    }
+
+    // Flatten `name` and its chain of parents into one template
+    fn resolve(&self, name: &str, chain: &mut Vec<String>) -> Result<EntityTemplate, String> {
+        if chain.iter().any(|visited| visited == name) {
+            chain.push(name.to_string());
+            return Err(format!("Entity inheritance cycle: {}", chain.join(" -> ")));
+        }
+        let template = self.template(name)
+            .ok_or_else(|| match chain.last() {
+                Some(child) => format!("Entity {} inherits from unknown parent {}", child, name),
+                None => format!("Unknown entity {}", name),
+            })?;
+
+        chain.push(name.to_string());
+        match &template.parent {
+            Some(parent) => Ok(template.inherit(&self.resolve(parent, chain)?)),
+            None => Ok(template.clone()),
+        }
+    }
+}
+
+#[derive(Debug)]
 pub struct EntityDefinitions {
     pub entities: HashMap<String, EntityDefinition>,
-}
\ No newline at end of file
+    pub behaviours: HashMap<String, BehaviourTree>,
+    pub factions: FactionTable,
+}
+
+impl EntityDefinitions {
+    pub fn load(path: &str) -> Result<Self, String> {
+        let ron_str = crate::vfs::read_to_string(path)
+            .map_err(|e| format!("Failed to read entity definitions: {}", e))?;
+
+        Self::parse(&ron_str)
+            .map_err(|e| format!("Failed to parse entity definitions: {}", e))
+    }
+
+    // Parse entities.ron and resolve inheritance. Optional fields can be written
+    // without `Some(...)`.
+    pub fn parse(ron_str: &str) -> Result<Self, String> {
+        let file: EntityDefinitionsFile = ron::Options::default()
+            .with_default_extension(Extensions::IMPLICIT_SOME)
+            .from_str(ron_str)
+            .map_err(|e| e.to_string())?;
+
+        let mut entities = HashMap::new();
+        for name in file.entities.keys() {
+            let definition = file.resolve(name, &mut Vec::new())?.into_definition(name)?;
+            if let AiDefinition::Behaviour(behaviour) = &definition.ai
+                && !file.behaviours.contains_key(behaviour) {
+                return Err(format!("Entity {} uses unknown behaviour {}", name, behaviour));
+            }
+            entities.insert(name.clone(), definition);
+        }
+
+        let behaviours = file.behaviours.iter()
+            .map(|(name, root)| (name.clone(), BehaviourTree::compile(root)))
+            .collect();
+        for (name, faction) in &file.factions {
+            if let Some(other) = faction.relations.keys().find(|other| !file.factions.contains_key(*other)) {
+                return Err(format!("Faction {} has a relation with unknown faction {}", name, other));
+            }
+        }
+        let factions = FactionTable::new(file.factions);
+        Ok(EntityDefinitions { entities, behaviours, factions })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DEFINITIONS: &str = r#"(
+        templates: {
+            "base_orc": (
+                entity_type: Enemy,
+                health: 50,
+                max_health: 50,
+                speed: 1.5,
+                animations: (
+                    idle: (texture: "orc_idle.png", frames: 6),
+                    walk: (texture: "orc_walk.png", frames: 8),
+                    attack: (texture: "orc_attack.png", frames: 6),
+                ),
+                ai: Chase(detection_range: 200.0, attack_range: 40.0),
+                components: {
+                    "Light": (radius: 40.0, color: (255, 0, 0), intensity: 0.5),
+                    "Damage": (amount: 10),
+                },
+            ),
+        },
+        entities: {
+            "orc": (parent: "base_orc"),
+            "orc_chief": (
+                parent: "orc",
+                max_health: 120,
+                animations: (attack: (texture: "chief_attack.png", frames: 9)),
+                components: { "Damage": (amount: 25) },
+            ),
+            "crate": (components: { "Health": (current: 5, max: 5) }),
+        },
+    )"#;
+
+    #[test]
+    fn children_inherit_what_they_leave_out() {
+        let definitions = EntityDefinitions::parse(DEFINITIONS).unwrap();
+        let orc = &definitions.entities["orc"];
+        assert_eq!(orc.entity_type, Some(EntityType::Enemy));
+        assert_eq!(orc.max_health, Some(50));
+        assert_eq!(orc.speed, 1.5);
+        assert!(matches!(orc.ai, AiDefinition::Chase { leash_range: DEFAULT_LEASH_RANGE, .. }));
+
+        // Overrides win per field and per animation
+        let chief = &definitions.entities["orc_chief"];
+        assert_eq!(chief.health, Some(50));
+        assert_eq!(chief.max_health, Some(120));
+        let animations = chief.animations.as_ref().unwrap();
+        assert_eq!(animations.idle.texture, "orc_idle.png");
+        assert_eq!(animations.attack, AnimationDefinition { texture: "chief_attack.png".to_string(), frames: 9 });
+    }
+
+    #[test]
+    fn components_merge_by_name() {
+        let definitions = EntityDefinitions::parse(DEFINITIONS).unwrap();
+        let chief = &definitions.entities["orc_chief"];
+        assert_eq!(chief.components.len(), 2);
+        assert!(chief.components.contains_key("Light"));
+        let damage: crate::components::Damage = chief.components["Damage"].clone().into_rust().unwrap();
+        assert_eq!(damage.amount, 25);
+    }
+
+    #[test]
+    fn entities_without_a_parent_only_get_what_they_set() {
+        let definitions = EntityDefinitions::parse(DEFINITIONS).unwrap();
+        let prop = &definitions.entities["crate"];
+        assert!(prop.entity_type.is_none());
+        assert!(prop.max_health.is_none());
+        assert!(prop.animations.is_none());
+        assert_eq!(prop.ai, AiDefinition::Idle);
+        // Templates themselves can't be spawned
+        assert!(!definitions.entities.contains_key("base_orc"));
+    }
+
+    #[test]
+    fn rejects_bad_parents() {
+        let unknown = r#"(entities: { "orc": (parent: "goblin") })"#;
+        let error = EntityDefinitions::parse(unknown).unwrap_err();
+        assert_eq!(error, "Entity orc inherits from unknown parent goblin");
+
+        let cycle = r#"(entities: { "a": (parent: "b"), "b": (parent: "a") })"#;
+        let error = EntityDefinitions::parse(cycle).unwrap_err();
+        assert!(error.starts_with("Entity inheritance cycle:"), "{}", error);
+    }
+
+    #[test]
+    fn animations_come_as_a_set() {
+        let partial = r#"(entities: { "ghost": (animations: (idle: (texture: "ghost.png", frames: 4))) })"#;
+        let error = EntityDefinitions::parse(partial).unwrap_err();
+        assert_eq!(error, "Entity ghost has no walk animation (and none of its parents set it)");
+    }
+}
@@ -1,37 +1,100 @@
-use crate::components::ai::Ai;
-use crate::entity_definitions::EntityDefinitions;
+use crate::assets::{AssetError, AssetServer, Handle};
+use crate::behaviour_tree::blackboard::HOME;
+use crate::behaviour_tree::{BehaviourState, Blackboard, BlackboardValue};
+use crate::component_registry::ComponentRegistry;
+use crate::components::ai::{Ai, Chase};
+use crate::utility_ai::UtilityState;
+use crate::entity_definitions::{AiDefinition, EntityDefinitions, EntityType};
 use crate::components::*;
-use crate::resource_manager::ResourceManager;
-use sdl2::render::TextureCreator;
-use sdl2::video::WindowContext;
 use sdl2::keyboard::Scancode;
-use std::sync::Arc;
+use std::collections::HashMap;
 
+pub const ENTITY_DEFINITIONS_PATH: &str = "assets/entities.ron";
 
-pub struct EntityFactory<'a> {
-    definitions: EntityDefinitions,
-    resource_manager: ResourceManager<'a>,
-    texture_creator: &'a TextureCreator<WindowContext>,
+// Everything a freshly created entity is made of. The columns every entity has are
+// plain fields; the rest are only present if its definition asks for them.
+pub struct EntityComponents<'a> {
+    pub entity: Entity,
+    pub position: Position,
+    pub health: Option<Health>,
+    pub textures: Vec<Handle<Texture<'a>>>,
+    pub animation: Animation,
+    pub sprite: Option<Sprite>, // None = the 100x100 character layout
+    pub input_bindings: InputBindings,
+    pub ai: Ai,
+    pub action_state: ActionState,
+    pub light: Option<Light>,
+    pub velocity: Option<Velocity>,
+    pub damage: Option<Damage>,
+    pub perception: Option<Perception>,
+    pub hurtbox: Option<Hurtbox>, // From the definition's `collision`
+    pub projectile: Option<Projectile>,
+    pub ranged_attack: Option<RangedAttack>,
+    pub faction: Option<Faction>,
+    pub spawner: Option<Spawner>,
+}
+
+impl<'a> EntityComponents<'a> {
+    // A bare entity: no sprite, no health, standing still
+    pub fn new(entity: Entity, position: Position) -> Self {
+        EntityComponents {
+            entity,
+            position,
+            health: None,
+            textures: Vec::new(),
+            animation: Animation::new(AnimationState::Idle, 1, 1, 1),
+            sprite: None,
+            input_bindings: InputBindings::new(vec![]),
+            ai: Ai { behavior: AiState::Idle },
+            action_state: ActionState::None,
+            light: None,
+            velocity: None,
+            damage: None,
+            perception: None,
+            hurtbox: None,
+            projectile: None,
+            ranged_attack: None,
+            faction: None,
+            spawner: None,
+        }
+    }
+}
+
+// Builds entities from entities.ron. Textures and definitions live in the AssetServer,
+// which is borrowed for each call rather than owned here.
+pub struct EntityFactory {
+    definitions_path: String,
+    definitions: Option<Handle<EntityDefinitions>>, // Loaded on first use
+    created_from: HashMap<usize, String>, // Entity id -> definition it was created from
     next_entity_id: u32,
+    registry: ComponentRegistry,
 }
 
-impl<'a> EntityFactory<'a> {
-    pub fn new(
-        texture_creator: &'a TextureCreator<WindowContext>
-    ) -> Result<Self, String> {
-        // Load entity definitions from RON file
-        let ron_str = std::fs::read_to_string("assets/entities.ron")
-            .map_err(|e| format!("Failed to read entity definitions: {}", e))?;
-            
-        let definitions: EntityDefinitions = ron::from_str(&ron_str)
-            .map_err(|e| format!("Failed to parse entity definitions: {}", e))?;
-            
-        Ok(EntityFactory {
-            definitions,
-            resource_manager: ResourceManager::new(),
-            texture_creator,
+impl EntityFactory {
+    // Doesn't touch the disk; definitions are loaded (or picked up from a background
+    // load, see queue_definitions) the first time they're needed
+    pub fn new(definitions_path: &str) -> Self {
+        EntityFactory {
+            definitions_path: definitions_path.to_string(),
+            definitions: None,
+            created_from: HashMap::new(),
             next_entity_id: 0,
-        })
+            registry: ComponentRegistry::with_defaults(),
+        }
+    }
+    
+    // Start loading the definitions and their sprite sheets on the loader threads
+    pub fn queue_definitions(&self, assets: &mut AssetServer) {
+        assets.queue_entity_definitions(&self.definitions_path);
+    }
+    
+    fn definitions_handle(&mut self, assets: &mut AssetServer) -> Result<Handle<EntityDefinitions>, AssetError> {
+        if let Some(handle) = &self.definitions {
+            return Ok(handle.clone());
+        }
+        let handle = assets.load_entity_definitions(&self.definitions_path)?;
+        self.definitions = Some(handle.clone());
+        Ok(handle)
     }
     
     pub fn next_id(&mut self) -> u32 {
@@ -40,53 +103,85 @@ impl<'a> EntityFactory<'a> {
         id
     }
     
-    pub fn create_entity(&mut self, 
+    // Names of every entity definition, sorted so lists built from them are stable
+    pub fn definition_names(&mut self, assets: &mut AssetServer) -> Vec<String> {
+        let handle = match self.definitions_handle(assets) {
+            Ok(handle) => handle,
+            Err(e) => {
+                eprintln!("Failed to load entity definitions: {}", e);
+                return Vec::new();
+            }
+        };
+        let mut names: Vec<String> = assets.entity_definitions(&handle)
+            .map(|definitions| definitions.entities.keys().cloned().collect())
+            .unwrap_or_default();
+        names.sort();
+        names
+    }
+
+    // The whole definitions file, for the AI trees and faction table in it
+    pub fn definitions<'s>(&mut self, assets: &'s mut AssetServer<'_>) -> Option<&'s EntityDefinitions> {
+        let handle = self.definitions_handle(assets).ok()?;
+        let assets: &'s AssetServer<'_> = assets;
+        assets.entity_definitions(&handle)
+    }
+
+    // Definition a live entity was created from
+    pub fn definition_name(&self, entity: &Entity) -> Option<&str> {
+        self.created_from.get(&entity.0).map(String::as_str)
+    }
+
+    // An entity is gone for good; stop tracking where it came from
+    pub fn forget(&mut self, entity: &Entity) {
+        self.created_from.remove(&entity.0);
+    }
+
+    pub fn create_entity<'a>(&mut self, 
+        assets: &mut AssetServer<'a>,
         entity_name: &str, 
         x: f32, 
         y: f32
-    ) -> Result<(Entity, Position, Health, Vec<Arc<Texture<'a>>>, Animation, InputBindings, Ai, ActionState), String> {
+    ) -> Result<EntityComponents<'a>, String> {
         // Get entity definition
-        let definition = self.definitions.entities.get(entity_name)
+        let definitions = self.definitions_handle(assets).map_err(|e| e.to_string())?;
+        let definition = assets.entity_definitions(&definitions)
+            .and_then(|definitions| definitions.entities.get(entity_name))
             .ok_or_else(|| format!("Entity definition not found: {}", entity_name))?.clone();
         
         // Create entity with ID
         let entity_id = self.next_id();
         let entity = Entity(entity_id as usize);
+        self.created_from.insert(entity.0, entity_name.to_string());
         
         // Create position
-        let position = Position::new(x, y, true); // Assuming the entity is facing right by default
+        let mut position = Position::new(x, y, true); // Assuming the entity is facing right by default
+        position.speed = definition.speed;
+        let mut components = EntityComponents::new(entity, position);
         
         // Create health
-        let health = Health::new(definition.health, definition.max_health);
-        
-        // Load textures for all animation types
-        let texture_types = vec!["idle", "walk", "attack"];
-        let mut entity_textures = Vec::new();
+        if let Some(max_health) = definition.max_health {
+            components.health = Some(Health::new(definition.health.unwrap_or(max_health), max_health));
+        }
         
-        for &anim_type in &texture_types {
-            if let Some(texture_path) = definition.textures.get(anim_type) {
-                match self.resource_manager.get_texture(self.texture_creator, texture_path) {
-                    Ok(texture) => entity_textures.push(texture),
-                    Err(e) => eprintln!("Failed to load texture {}: {}", texture_path, e),
-                }
+        // Sprite sheets and frame counts are shared by every entity of this type
+        if let Some(animations) = &definition.animations {
+            let animation_set = assets.load_animation_set(entity_name, animations).map_err(|e| e.to_string())?;
+            if let Some(set) = assets.animation_set(&animation_set) {
+                components.textures = set.textures.clone();
+                components.animation = Animation::new(AnimationState::Idle, set.idle_frames, set.walk_frames, set.attack_frames);
             }
         }
         
-        // Create animation
-        let idle_frames = definition.animation_frames.get("idle").copied().unwrap_or(1);
-        let walk_frames = definition.animation_frames.get("walk").copied().unwrap_or(1);
-        let attack_frames = definition.animation_frames.get("attack").copied().unwrap_or(1);
-
-        let animation = Animation::new(
-            AnimationState::Idle, 
-            idle_frames,
-            walk_frames,
-            attack_frames
-        );
+        components.hurtbox = definition.collision.as_ref().map(|collision| Hurtbox {
+            width: collision.width,
+            height: collision.height,
+            offset_x: collision.offset_x,
+            offset_y: collision.offset_y,
+        });
         
         // Create input bindings
-        let input_bindings = if entity_name == "player" {
-            InputBindings::new(vec![
+        if definition.entity_type == Some(EntityType::Player) {
+            components.input_bindings = InputBindings::new(vec![
                 // Arrow keys
                 (Scancode::Right, GameAction::MoveRight),
                 (Scancode::Left, GameAction::MoveLeft),
@@ -97,23 +192,73 @@ impl<'a> EntityFactory<'a> {
                 (Scancode::A, GameAction::MoveLeft),
                 (Scancode::W, GameAction::MoveUp),
                 (Scancode::S, GameAction::MoveDown),
-                // Attack key
+                // Attack keys
                 (Scancode::Space, GameAction::Attack),
-            ])
-        } else {
-            InputBindings::default()
-        };
+                (Scancode::F, GameAction::Shoot),
+            ]);
+        }
         
         // Create AI
-        let ai = match definition.ai_type.as_deref() {
-            Some("patrol") => Ai { behavior: AiState::Patrol { current_waypoint: 0, waypoints: vec![] }},
-            Some("chase") => Ai { behavior: AiState::Chase { target_entity: 0, detection_range: 200.0, attack_range: 50.0 }},
-            _ => Ai { behavior: AiState::Idle }, // Changed 'mode' to 'behavior'
+        components.ai = match definition.ai {
+            AiDefinition::Patrol => Ai { behavior: AiState::Patrol { current_waypoint: 0, waypoints: vec![] }},
+            AiDefinition::Chase { detection_range, attack_range, leash_range, attack_cooldown, wind_up, search_time } => {
+                let mut chase = Chase::new(detection_range, attack_range, (x, y));
+                chase.leash_range = leash_range;
+                chase.attack_cooldown = attack_cooldown;
+                chase.wind_up = wind_up;
+                chase.search_time = search_time;
+                Ai { behavior: AiState::Chase(chase) }
+            },
+            AiDefinition::Idle => Ai { behavior: AiState::Idle },
+            AiDefinition::Behaviour(tree) => {
+                let mut blackboard = Blackboard::default();
+                blackboard.set(HOME, BlackboardValue::Point(x, y));
+                Ai { behavior: AiState::Behaviour(BehaviourState::new(&tree, blackboard)) }
+            },
+            AiDefinition::Utility { options, hysteresis } => Ai { behavior: AiState::Utility(UtilityState::new(options, hysteresis, (x, y))) },
         };
         
-        // Create action state
-        let action_state = ActionState::None;
+        // Everything else comes from the definition's `components` map
+        for (name, value) in &definition.components {
+            self.registry.apply(name, value, &mut components, assets)
+                .map_err(|e| format!("Entity {}: component {}: {}", entity_name, name, e))?;
+        }
         
-        Ok((entity, position, health, entity_textures, animation, input_bindings, ai, action_state))
+        Ok(components)
     }
-}
\ No newline at end of file
+    
+    // Re-apply the (re-loaded) definition to an existing entity: speed, max health and
+    // animations. Position, current health and AI state are left alone.
+    pub fn refresh_entity<'a>(&mut self,
+        assets: &mut AssetServer<'a>,
+        entity: &Entity,
+        position: &mut Position,
+        health: &mut Option<Health>,
+        textures: &mut Vec<Handle<Texture<'a>>>,
+        animation: &mut Animation
+    ) -> Result<(), String> {
+        let entity_name = self.definition_name(entity)
+            .ok_or_else(|| format!("Entity {} wasn't created by the factory", entity.0))?
+            .to_string();
+        let definitions = self.definitions_handle(assets).map_err(|e| e.to_string())?;
+        let definition = assets.entity_definitions(&definitions)
+            .and_then(|definitions| definitions.entities.get(&entity_name))
+            .ok_or_else(|| format!("Entity definition not found: {}", entity_name))?.clone();
+        
+        position.speed = definition.speed;
+        if let (Some(health), Some(max_health)) = (health.as_mut(), definition.max_health) {
+            health.max = max_health;
+            health.current = health.current.min(health.max);
+        }
+        
+        let Some(animations) = &definition.animations else { return Ok(()) };
+        let animation_set = assets.load_animation_set(&entity_name, animations).map_err(|e| e.to_string())?;
+        if let Some(set) = assets.animation_set(&animation_set) {
+            *textures = set.textures.clone();
+            animation.idle_frames = set.idle_frames;
+            animation.walk_frames = set.walk_frames;
+            animation.attack_frames = set.attack_frames;
+        }
+        Ok(())
+    }
+}
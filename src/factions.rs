@@ -0,0 +1,133 @@
+// How factions feel about each other, from the `factions` table in entities.ron:
+//
+//   factions: {
+//     "player": (relations: {"orcs": Hostile}),
+//     "orcs": (relations: {"player": Hostile}, friendly_fire: true),
+//   }
+//
+// A faction is always friendly with itself. Pairs that aren't listed are neutral;
+// a pair only needs listing from one side.
+use crate::components::Faction;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Relation {
+    Hostile,  // AI goes after them
+    Neutral,  // Left alone, but can still be hurt
+    Friendly, // Allies; only hurt by factions with friendly_fire
+}
+
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct FactionDefinition {
+    #[serde(default)]
+    pub relations: HashMap<String, Relation>,
+    #[serde(default)]
+    pub friendly_fire: bool, // Its attacks hurt friendly entities too
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct FactionTable {
+    factions: HashMap<String, FactionDefinition>,
+}
+
+impl FactionTable {
+    pub fn new(factions: HashMap<String, FactionDefinition>) -> Self {
+        FactionTable { factions }
+    }
+
+    pub fn contains(&self, name: &str) -> bool {
+        self.factions.contains_key(name)
+    }
+
+    // How `own` sees `other`. Entities without a faction are neutral to everyone.
+    pub fn relation(&self, own: Option<&Faction>, other: Option<&Faction>) -> Relation {
+        let (Some(own), Some(other)) = (own, other) else { return Relation::Neutral };
+        if own.name == other.name {
+            return Relation::Friendly;
+        }
+        let listed = |from: &Faction, to: &Faction| self.factions.get(&from.name)?.relations.get(&to.name).copied();
+        listed(own, other)
+            .or_else(|| listed(other, own))
+            .unwrap_or(Relation::Neutral)
+    }
+
+    pub fn is_hostile(&self, own: Option<&Faction>, other: Option<&Faction>) -> bool {
+        self.relation(own, other) == Relation::Hostile
+    }
+
+    pub fn is_friendly(&self, own: Option<&Faction>, other: Option<&Faction>) -> bool {
+        self.relation(own, other) == Relation::Friendly
+    }
+
+    // Whether an attack from `attacker`'s side hurts `victim`: anyone but friends,
+    // and friends too if the attacker's faction allows friendly fire
+    pub fn can_damage(&self, attacker: Option<&Faction>, victim: Option<&Faction>) -> bool {
+        if !self.is_friendly(attacker, victim) {
+            return true;
+        }
+        attacker
+            .and_then(|attacker| self.factions.get(&attacker.name))
+            .is_some_and(|definition| definition.friendly_fire)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn faction(name: &str) -> Option<Faction> {
+        Some(Faction { name: name.to_string() })
+    }
+
+    fn table() -> FactionTable {
+        ron::from_str::<HashMap<String, FactionDefinition>>(r#"{
+            "player": (relations: {"orcs": Hostile}),
+            "orcs": (relations: {"goblins": Friendly}, friendly_fire: true),
+            "goblins": (),
+            "merchants": (relations: {"player": Friendly}),
+        }"#).map(FactionTable::new).unwrap()
+    }
+
+    #[test]
+    fn relations_only_need_listing_on_one_side() {
+        let table = table();
+        let (player, orcs, goblins) = (faction("player"), faction("orcs"), faction("goblins"));
+        assert_eq!(table.relation(player.as_ref(), orcs.as_ref()), Relation::Hostile);
+        assert_eq!(table.relation(orcs.as_ref(), player.as_ref()), Relation::Hostile);
+        assert_eq!(table.relation(goblins.as_ref(), orcs.as_ref()), Relation::Friendly);
+        assert_eq!(table.relation(player.as_ref(), goblins.as_ref()), Relation::Neutral);
+    }
+
+    #[test]
+    fn factions_are_friendly_with_themselves() {
+        let table = table();
+        assert!(table.is_friendly(faction("goblins").as_ref(), faction("goblins").as_ref()));
+        // Even ones missing from the table
+        assert!(table.is_friendly(faction("bandits").as_ref(), faction("bandits").as_ref()));
+    }
+
+    #[test]
+    fn entities_without_a_faction_are_neutral() {
+        let table = table();
+        assert_eq!(table.relation(None, faction("orcs").as_ref()), Relation::Neutral);
+        assert_eq!(table.relation(faction("orcs").as_ref(), None), Relation::Neutral);
+        assert_eq!(table.relation(None, None), Relation::Neutral);
+        assert!(table.can_damage(None, None));
+    }
+
+    #[test]
+    fn friends_are_only_hurt_with_friendly_fire() {
+        let table = table();
+        let (player, orcs, goblins, merchants) = (faction("player"), faction("orcs"), faction("goblins"), faction("merchants"));
+        assert!(table.can_damage(player.as_ref(), orcs.as_ref()));
+        assert!(table.can_damage(goblins.as_ref(), player.as_ref()));
+        assert!(!table.can_damage(player.as_ref(), merchants.as_ref()));
+        assert!(!table.can_damage(player.as_ref(), player.as_ref()));
+        assert!(!table.can_damage(goblins.as_ref(), orcs.as_ref()));
+        // Orcs have friendly fire, so they hurt their own and their allies
+        assert!(table.can_damage(orcs.as_ref(), orcs.as_ref()));
+        assert!(table.can_damage(orcs.as_ref(), goblins.as_ref()));
+    }
+}
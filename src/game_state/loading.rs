@@ -0,0 +1,214 @@
+// Queuing levels, swapping them in once loaded and reloading them when their files change
+use super::GameState;
+use crate::assets::AssetChange;
+use crate::components::*;
+use crate::level_loader::LevelLoader;
+use crate::loading_screen::LoadingScreen;
+use crate::systems::trigger_system::TriggerSystem;
+use crate::vfs;
+
+impl<'a> GameState<'a> {
+    // Queue a level and everything it needs on the loader threads. The level is
+    // swapped in by update_loading once all of it has arrived.
+    pub fn begin_loading(&mut self, path: &str, entry: Option<&str>) {
+        self.entity_factory.queue_definitions(&mut self.assets);
+        self.assets.queue_level(path);
+        self.loading = Some(LoadingScreen::new(path, entry));
+    }
+
+    // Returns true while a level is still loading (gameplay stays paused)
+    pub(super) fn update_loading(&mut self) -> bool {
+        let loading = match &mut self.loading {
+            Some(loading) => loading,
+            None => return false,
+        };
+        
+        self.assets.process_loaded();
+        loading.progress = self.assets.progress();
+        if self.assets.is_loading() {
+            return true;
+        }
+        
+        // Everything is cached now, so this doesn't block on the disk
+        if let Some(loading) = self.loading.take() {
+            // A level that fails to load leaves the current one running (or, for the
+            // starting level, an empty world; see update)
+            if let Err(e) = self.load_level(&loading.level_path, loading.entry.as_deref()) {
+                eprintln!("Failed to load level {}: {}", loading.level_path, e);
+            } else if let Some(save) = self.pending_save.take() {
+                self.restore(save);
+            }
+        }
+        self.pending_save = None;
+        // The level has spawned (and survived load_level's unload_unused) with them
+        self.assets.release_warmed();
+        if self.editor.entity_names.is_empty() {
+            self.editor.entity_names = self.entity_factory.definition_names(&mut self.assets);
+        }
+        self.assets.report_missing();
+        true
+    }
+
+    // Replace the current level. The player entity (with its health and inventory)
+    // is carried over and placed at `entry`; everything else from the old level is
+    // dropped and its assets released.
+    pub fn load_level(&mut self, path: &str, entry: Option<&str>) -> Result<(), String> {
+        let level_handle = self.assets.load_level_definition(path).map_err(|e| e.to_string())?;
+        let level = self.assets.level(&level_handle)
+            .cloned()
+            .ok_or_else(|| format!("Level {} was unloaded", path))?;
+        // Let unload_unused drop the cached definition; the level keeps its own copy
+        drop(level_handle);
+        
+        // Load the new tilemap (or open the streamed world) before tearing anything down
+        // so a bad level leaves us playable
+        let (tilemap, world) = match &level.world {
+            Some(world_dir) => match self.assets.create_chunked_tilemap(world_dir, &level.tileset, level.tile_size, level.tile_size) {
+                Ok(world) => (None, Some(world)),
+                Err(e) => {
+                    eprintln!("Failed to load chunked world: {}", e);
+                    (None, None)
+                }
+            },
+            None => match self.assets.create_tilemap(&level.tilemap, &level.tileset, level.tile_size, level.tile_size) {
+                Ok(map) => (Some(map), None),
+                Err(e) => {
+                    eprintln!("Failed to load tilemap: {}", e);
+                    (None, None)
+                }
+            },
+        };
+        
+        // Likewise the new level's entities; the player is only spawned when starting fresh
+        let spawned = if self.entities.is_empty() {
+            LevelLoader::spawn_entities(&mut self.entity_factory, &mut self.assets, &level)?
+        } else {
+            LevelLoader::spawn_others(&mut self.entity_factory, &mut self.assets, &level)
+        };
+        
+        let carried_player = self.take_player();
+        self.clear_entities();
+        
+        // Replacing the tilemap drops its tileset handle; the tileset is freed by unload_unused below.
+        // The old world's edited chunks are written back first.
+        self.save_world();
+        self.tilemap = tilemap;
+        self.world = world;
+        self.rebuild_collision();
+        self.tile_events.clear();
+        self.wave_events.clear();
+        self.noises.clear();
+        self.pickups = level.pickups.clone();
+        
+        if let Some((mut components, inventory)) = carried_player {
+            let entry_position = entry
+                .and_then(|name| level.entries.get(name).copied())
+                .or_else(|| level.spawns.iter()
+                    .find(|spawn| spawn.entity == "player")
+                    .map(|spawn| (spawn.x, spawn.y)));
+            match entry_position {
+                Some((x, y)) => {
+                    components.position.x = x;
+                    components.position.y = y;
+                },
+                None => eprintln!("Level {} has no entry point {:?}", path, entry),
+            }
+            components.action_state = ActionState::None;
+            self.add_entity(components);
+            self.inventories[0] = inventory;
+        }
+        for components in spawned {
+            self.add_entity(components);
+        }
+        
+        // Release whatever only the previous level was using
+        self.assets.unload_unused();
+        
+        // Don't immediately bounce back if the entry point sits inside a trigger
+        self.active_trigger = self.positions.first()
+            .and_then(|position| TriggerSystem::find(&level.triggers, position));
+        if let Some(player_pos) = self.positions.first().cloned() {
+            self.update_camera(&player_pos);
+        }
+        // A streamed world has nothing to collide with until the chunks around the camera are in
+        self.stream_world();
+        
+        // Watch the level's own files so edits to them reload the level
+        if let Some(previous) = &self.level {
+            self.assets.unwatch_file(&self.level_path);
+            if previous.world.is_none() {
+                self.assets.unwatch_file(&previous.tilemap);
+            }
+        }
+        self.assets.watch_file(path);
+        if level.world.is_none() {
+            self.assets.watch_file(&level.tilemap);
+        }
+        
+        println!("Loaded level {} with {} entities", path, self.entities.len());
+        self.level = Some(level);
+        self.level_path = path.to_string();
+        Ok(())
+    }
+
+    pub(super) fn hot_reload(&mut self, delta_time: f32) {
+        let mut reload_level = false;
+        for change in self.assets.poll_changes(delta_time) {
+            match change {
+                // Already swapped in behind the existing handles
+                AssetChange::Texture(_) => {},
+                AssetChange::EntityDefinitions(_) => self.refresh_entities(),
+                AssetChange::Tilemap(path) | AssetChange::File(path) => {
+                    reload_level |= self.is_current_level_file(&path);
+                },
+            }
+        }
+        
+        if reload_level {
+            self.reload_level();
+        }
+    }
+
+    fn is_current_level_file(&self, path: &str) -> bool {
+        match &self.level {
+            Some(level) => path == vfs::normalize(&self.level_path)
+                || (level.world.is_none() && path == vfs::normalize(&level.tilemap)),
+            None => false,
+        }
+    }
+
+    // Apply re-parsed entity definitions to every live entity
+    fn refresh_entities(&mut self) {
+        for i in 0..self.entities.len() {
+            if let Err(e) = self.entity_factory.refresh_entity(
+                &mut self.assets,
+                &self.entities[i],
+                &mut self.positions[i],
+                &mut self.healths[i],
+                &mut self.textures[i],
+                &mut self.animations[i]
+            ) {
+                eprintln!("Failed to refresh entity {}: {}", i, e);
+            }
+        }
+    }
+
+    // Reload the current level from disk, keeping the player where they are
+    fn reload_level(&mut self) {
+        let path = self.level_path.clone();
+        let player_pos = self.positions.first().copied();
+        
+        if let Err(e) = self.load_level(&path, None) {
+            eprintln!("Failed to reload level {}: {}", path, e);
+            return;
+        }
+        if let (Some(saved), Some(position)) = (player_pos, self.positions.first_mut()) {
+            position.x = saved.x;
+            position.y = saved.y;
+            position.facing_right = saved.facing_right;
+        }
+        if let Some(player_pos) = self.positions.first().cloned() {
+            self.update_camera(&player_pos);
+        }
+    }
+}
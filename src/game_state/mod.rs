@@ -0,0 +1,686 @@
+use crate::assets::{AssetServer, Handle};
+use crate::components::*;
+use crate::components::ai::Ai;
+use crate::editor::{Editor, EditorContext};
+use crate::entity_factory::{EntityFactory, ENTITY_DEFINITIONS_PATH};
+use crate::factions::FactionTable;
+use crate::level_definitions::{LevelDefinition, PickupDefinition};
+use crate::loading_screen::LoadingScreen;
+use crate::navigation::{DiagonalRule, FlowField, PathCache, PathOptions};
+use crate::save::{self, SaveFormat, SaveGame};
+use crate::scene_transition::SceneTransition;
+use crate::systems::ai_system::AiSystem;
+use crate::systems::ai_world::AiWorld;
+use crate::systems::behaviour_system::BehaviourSystem;
+use crate::systems::utility_system::UtilitySystem;
+use crate::systems::perception_system::PerceptionSystem;
+use crate::systems::projectile_system::{ProjectileSystem, ProjectileWorld};
+use crate::systems::spawner_system::SpawnerSystem;
+use crate::systems::editor_render_system::EditorRenderSystem;
+use crate::systems::health_system::HealthSystem;
+use crate::systems::light_system::LightSystem;
+use crate::systems::render_system::{Drawables, RenderSystem};
+use crate::systems::trigger_system::TriggerSystem;
+use crate::systems::pickup_system::PickupSystem;
+use crate::systems::velocity_system::VelocitySystem;
+use crate::systems::terrain_system::{self, TerrainSystem};
+use crate::systems::*;
+use crate::vfs;
+use sdl2::event::Event;
+use sdl2::keyboard::{Keycode, Mod};
+use sdl2::render::TextureCreator;
+use sdl2::video::WindowContext;
+use std::collections::HashMap;
+
+mod loading;
+mod snapshot;
+mod spawning;
+
+// Window size until main reports the canvas's real one
+const DEFAULT_VIEW_SIZE: (i32, i32) = (800, 600);
+const START_LEVEL: &str = "assets/levels/level1.ron";
+// With this many enemies chasing the player they share one flow field instead of
+// each planning its own A* path
+const FLOW_FIELD_MIN_CHASERS: usize = 4;
+// How far the player flow field spreads, in path cost (about one per tile)
+const FLOW_FIELD_RANGE: f32 = 64.0;
+// How far footsteps and attacks carry, relative to a listener's hearing_radius
+const FOOTSTEP_VOLUME: f32 = 0.5;
+const ATTACK_VOLUME: f32 = 1.0;
+
+pub struct GameState<'a> {
+    pub entities: Vec<Entity>,
+    pub positions: Vec<Position>,
+    pub healths: Vec<Option<Health>>,
+    pub input_bindings: Vec<InputBindings>,
+    pub textures: Vec<Vec<Handle<Texture<'a>>>>,
+    pub animations: Vec<Animation>,
+    pub sprites: Vec<Option<Sprite>>,
+    pub lights: Vec<Option<Light>>,
+    pub velocities: Vec<Option<Velocity>>,
+    pub damages: Vec<Option<Damage>>,
+    pub perceptions: Vec<Option<Perception>>,
+    pub hurtboxes: Vec<Option<Hurtbox>>,
+    pub projectiles: Vec<Option<Projectile>>,
+    pub ranged_attacks: Vec<Option<RangedAttack>>,
+    pub factions: Vec<Option<Faction>>,
+    pub spawners: Vec<Option<Spawner>>,
+    pub ais: Vec<Ai>,
+    pub action_states: Vec<ActionState>,
+    pub inventories: Vec<Inventory>,
+    pub tilemap: Option<Tilemap<'a>>, // Owned so tiles can change during play
+    pub collision: Option<CollisionGrid>,
+    pub paths: PathCache, // AI paths over `collision`
+    pub player_flow: FlowField, // Leads to the player; only kept up to date while a crowd is chasing
+    pub destructible_tiles: HashMap<TileId, DestructibleTile>,
+    pub tile_events: Vec<TileChange>, // Tile changes made during the last update, in collision grid cells
+    pub wave_events: Vec<WaveEvent>, // Spawner waves started and cleared during the last update
+    pub noises: Vec<Noise>, // Made during the last update, heard at the start of the next
+    pub pickups: Vec<PickupDefinition>, // Items still lying in the current level
+    pub world: Option<ChunkedTilemap<'a>>, // Streamed chunk world of a level that has one, used instead of tilemap
+    pub tile_types: HashMap<TileId, TileType>,
+    pub camera_x: i32,
+    pub camera_y: i32,
+    pub view_size: (i32, i32), // Width and height of the screen in pixels
+    pub level: Option<LevelDefinition>,
+    pub level_path: String,
+    pub transition: Option<SceneTransition>,
+    pub loading: Option<LoadingScreen>, // Level whose assets are loading in the background
+    pending_save: Option<SaveGame>, // Applied once `loading` finishes
+    pub editor: Editor,
+    active_trigger: Option<usize>, // Trigger the player is standing in, so it only fires on entry
+    input_system: InputSystem,  // Keep the InputSystem instance
+    assets: AssetServer<'a>,
+    entity_factory: EntityFactory,
+}
+
+impl<'a> GameState<'a> {
+    pub fn new(texture_creator: &'a TextureCreator<WindowContext>) -> Self {
+        let assets = AssetServer::new(texture_creator);
+        let entity_factory = EntityFactory::new(ENTITY_DEFINITIONS_PATH);
+        
+        // Initialize tile types
+        let mut tile_types = HashMap::new();
+        tile_types.insert(TileId(0), TileType::Empty);
+        tile_types.insert(TileId(1), TileType::Floor);
+        tile_types.insert(TileId(2), TileType::Wall);
+        tile_types.insert(TileId(3), TileType::Water);
+        tile_types.insert(TileId(4), TileType::Lava);
+        tile_types.insert(TileId(5), TileType::Wall);  // Cracked wall
+        tile_types.insert(TileId(6), TileType::Wall);  // Closed door
+        tile_types.insert(TileId(7), TileType::Floor); // Open door
+        tile_types.insert(terrain_system::BRIDGE_TILE, TileType::Floor);
+        
+        // Tiles that can be broken or opened by attacking them
+        let mut destructible_tiles = HashMap::new();
+        destructible_tiles.insert(TileId(5), DestructibleTile { hit_points: 3, becomes: TileId(1) });
+        destructible_tiles.insert(TileId(6), DestructibleTile { hit_points: 1, becomes: TileId(7) });
+        
+
+        let tilesets_dir = "assets/tilesets";
+        if !vfs::exists(tilesets_dir) {
+            println!("WARNING: Tilesets directory doesn't exist at {:?}", tilesets_dir);
+        }   
+
+        // Entity names are filled in once the definitions have loaded
+        let editor = Editor::new(Vec::new());
+        
+        // Create InputSystem with enough capacity
+        let input_system = InputSystem::new(200);  // 200 should be enough for all keys
+        
+        let mut game_state = GameState {
+            ais: Vec::new(),
+            healths: Vec::new(),
+            entities: Vec::new(),
+            positions: Vec::new(),
+            input_bindings: Vec::new(),
+            textures: Vec::new(),
+            animations: Vec::new(),
+            sprites: Vec::new(),
+            lights: Vec::new(),
+            velocities: Vec::new(),
+            damages: Vec::new(),
+            perceptions: Vec::new(),
+            hurtboxes: Vec::new(),
+            projectiles: Vec::new(),
+            ranged_attacks: Vec::new(),
+            factions: Vec::new(),
+            spawners: Vec::new(),
+            action_states: Vec::new(),
+            inventories: Vec::new(),
+            tilemap: None,
+            collision: None,
+            paths: PathCache::new(PathOptions::default()),
+            player_flow: FlowField::new(FLOW_FIELD_RANGE, DiagonalRule::NoCornerCutting),
+            destructible_tiles,
+            tile_events: Vec::new(),
+            wave_events: Vec::new(),
+            noises: Vec::new(),
+            pickups: Vec::new(),
+            world: None,
+            tile_types,
+            camera_x: 0,
+            camera_y: 0,
+            view_size: DEFAULT_VIEW_SIZE,
+            level: None,
+            level_path: String::new(),
+            transition: None,
+            loading: None,
+            pending_save: None,
+            editor,
+            active_trigger: None,
+            input_system,
+            assets,
+            entity_factory,
+        };
+        
+        // The first frames show the loading screen while the start level streams in
+        game_state.begin_loading(START_LEVEL, None);
+        game_state
+    }
+
+    // Window events that aren't polled through the keyboard state (editor toggle, mouse)
+    pub fn handle_event(&mut self, event: &Event) {
+        if let Event::KeyDown { keycode: Some(Keycode::F1), repeat: false, .. } = event {
+            self.editor.toggle();
+            return;
+        }
+        // Quick save (binary, or RON with Shift) and quick load
+        if let Event::KeyDown { keycode: Some(keycode @ (Keycode::F5 | Keycode::F9)), keymod, repeat: false, .. } = event {
+            if self.loading.is_some() || self.editor.active {
+                return;
+            }
+            let result = if *keycode == Keycode::F5 {
+                let format = if keymod.intersects(Mod::LSHIFTMOD | Mod::RSHIFTMOD) { SaveFormat::Ron } else { SaveFormat::Binary };
+                self.save_game(save::QUICKSAVE_SLOT, format)
+            } else {
+                self.load_game(save::QUICKSAVE_SLOT)
+            };
+            if let Err(e) = result {
+                eprintln!("{}", e);
+            }
+            return;
+        }
+        if !self.editor.active {
+            return;
+        }
+        
+        if let (Some(tilemap), Some(level)) = (&mut self.tilemap, &mut self.level) {
+            let mut ctx = EditorContext {
+                assets: &self.assets,
+                tilemap,
+                level,
+                level_path: &self.level_path,
+                camera_x: self.camera_x,
+                camera_y: self.camera_y,
+            };
+            self.editor.handle_event(event, &mut ctx);
+        }
+        self.flush_tile_changes();
+    }
+
+    pub fn update(&mut self, keyboard_state: &sdl2::keyboard::KeyboardState, delta_time: f32) {
+        self.tile_events.clear();
+        self.wave_events.clear();
+        
+        // Nothing runs until the level's assets have streamed in
+        if self.update_loading() {
+            return;
+        }
+        // Or at all without a player, if the starting level failed to load
+        if self.entities.is_empty() {
+            return;
+        }
+        
+        // Pick up asset files edited while the game is running
+        self.hot_reload(delta_time);
+        
+        // The world is frozen while editing; the movement keys pan the camera instead
+        if self.editor.active {
+            self.editor.update_camera(keyboard_state, &mut self.camera_x, &mut self.camera_y);
+            return;
+        }
+        
+        // Gameplay is paused while fading between levels
+        if self.update_transition(delta_time) {
+            return;
+        }
+        
+        // Get player input actions
+        let player_action_states = self.input_system.run(
+            &self.entities[0..1],  // Just process player entity (index 0)
+            &self.input_bindings[0..1], 
+            keyboard_state
+        );
+        
+        // Update player action state
+        if !self.animations[0].is_attack_in_progress { // Only update if not attacking
+            self.action_states[0] = player_action_states[0].clone();
+        }
+        
+        // Debug what state was assigned to player
+        match &self.action_states[0] {
+            ActionState::Moving { right, left, up, down } => {
+                println!("Player has movement state: right={}, left={}, up={}, down={}", 
+                         right, left, up, down);
+            },
+            ActionState::Attacking => println!("Player is attacking"),
+            ActionState::Shooting | ActionState::Steering { .. } | ActionState::None => {} // Not logged
+        }
+        
+        // Update player movement
+        MovementSystem::run(
+            &self.entities[0..1],  // Just player
+            &mut self.positions[0..1], 
+            &self.action_states[0..1],
+            self.collision.as_ref()
+        );
+        
+        // What each AI can see and hear before it decides what to do
+        PerceptionSystem::run(
+            &self.positions,
+            &self.ais,
+            &mut self.perceptions,
+            &self.noises,
+            self.collision.as_ref(),
+            delta_time
+        );
+        self.noises.clear();
+        
+        // Update AI for non-player entities. Who's hostile to whom comes from the
+        // definitions' faction table; until that's loaded nobody is.
+        let crowd = self.update_player_flow().then_some((0, &self.player_flow));
+        let definitions = self.entity_factory.definitions(&mut self.assets);
+        let no_factions = FactionTable::default();
+        let relations = definitions.map_or(&no_factions, |definitions| &definitions.factions);
+        let world = AiWorld {
+            entities: &self.entities,
+            positions: &self.positions,
+            factions: &self.factions,
+            relations,
+            healths: &self.healths,
+            hurtboxes: &self.hurtboxes,
+            projectiles: &self.projectiles,
+            perceptions: &self.perceptions,
+            collision: self.collision.as_ref(),
+        };
+        AiSystem::run(&world, &mut self.ais, &mut self.action_states, &mut self.paths, crowd, delta_time);
+        
+        let trees = definitions.map(|definitions| &definitions.behaviours);
+        BehaviourSystem::run(&world, &mut self.ais, &mut self.action_states, trees, &mut self.paths, delta_time);
+        
+        let heals = UtilitySystem::run(&world, &mut self.ais, &mut self.action_states, &mut self.paths, delta_time);
+        for (ally, amount) in heals {
+            HealthSystem::heal(&mut self.healths, ally, amount);
+        }
+        
+        // AI only decides to attack; entities that carry a ranged weapon use it
+        for (action_state, ranged_attack) in self.action_states.iter_mut().zip(&self.ranged_attacks).skip(1) {
+            if *action_state == ActionState::Attacking && ranged_attack.is_some() {
+                *action_state = ActionState::Shooting;
+            }
+        }
+        
+        // Update movement for non-player entities
+        MovementSystem::run(
+            &self.entities[1..], 
+            &mut self.positions[1..], 
+            &self.action_states[1..],
+            self.collision.as_ref()
+        );
+        
+        // Projectiles and anything else that drifts on its own
+        VelocitySystem::run(&mut self.positions, &self.velocities, delta_time);
+        let world = ProjectileWorld {
+            entities: &self.entities,
+            damages: &self.damages,
+            sprites: &self.sprites,
+            hurtboxes: &self.hurtboxes,
+            factions: &self.factions,
+            relations,
+            collision: self.collision.as_ref(),
+        };
+        let spent = ProjectileSystem::run(
+            &world,
+            &mut self.positions,
+            &mut self.velocities,
+            &mut self.projectiles,
+            &mut self.healths,
+            delta_time
+        );
+        for &i in spent.iter().rev() {
+            self.remove_entity(i);
+        }
+        
+        // Update health
+        HealthSystem::update(&self.entities, &mut self.healths, delta_time);
+        
+        // Spawners top up their enemies and move their waves along
+        let requests = SpawnerSystem::run(
+            &self.entities,
+            &self.positions,
+            &self.healths,
+            &mut self.spawners,
+            self.collision.as_ref(),
+            &mut self.wave_events,
+            delta_time
+        );
+        self.spawn_enemies(&requests);
+        self.report_waves();
+        
+        // Attacks that start this frame hit the terrain in front of the attacker
+        let attackers: Vec<usize> = self.action_states.iter()
+            .zip(self.animations.iter())
+            .enumerate()
+            .filter(|(_, (state, animation))| **state == ActionState::Attacking && !animation.is_attack_in_progress)
+            .map(|(i, _)| i)
+            .collect();
+        // A streamed world is collided with instead of the level's tilemap, so it's the one attacks reach
+        if let Some(world) = &mut self.world {
+            TerrainSystem::apply_attacks(
+                &attackers,
+                &self.positions,
+                &mut self.inventories,
+                world,
+                &self.tile_types,
+                &self.destructible_tiles
+            );
+        } else if let Some(tilemap) = &mut self.tilemap {
+            TerrainSystem::apply_attacks(
+                &attackers,
+                &self.positions,
+                &mut self.inventories,
+                tilemap,
+                &self.tile_types,
+                &self.destructible_tiles
+            );
+        }
+        self.flush_tile_changes();
+        
+        // Ranged attacks that start this frame let loose a projectile
+        let shooters: Vec<usize> = self.action_states.iter()
+            .zip(self.animations.iter())
+            .enumerate()
+            .filter(|(_, (state, animation))| **state == ActionState::Shooting && !animation.is_attack_in_progress)
+            .map(|(i, _)| i)
+            .collect();
+        self.fire_projectiles(&shooters);
+        
+        // Footsteps and swings, heard by AI next frame
+        for (i, state) in self.action_states.iter().enumerate() {
+            let volume = match state {
+                state if state.is_attacking() => ATTACK_VOLUME,
+                state if state.is_moving() => FOOTSTEP_VOLUME,
+                _ => continue,
+            };
+            let (x, y) = self.positions[i].feet();
+            self.noises.push(Noise { x, y, volume, source: i });
+        }
+        
+        // Update animation states based on action states
+        self.update_animations();
+        
+        // Update animation frames
+        for animation in &mut self.animations {
+            animation.update(16);
+        }
+        
+        // Update the camera to follow the player
+        if let Some(player_pos) = self.positions.get(0).cloned() {
+            self.update_camera(&player_pos);
+        }
+        
+        // Walking into a door or exit starts a level transition
+        self.check_triggers();
+        self.collect_pickups();
+        
+        self.stream_world();
+        
+        // Debug animation progress
+        for (i, animation) in self.animations.iter().enumerate() {
+            if animation.is_attack_in_progress {
+                println!("Entity {} attack animation: frame {}/{}", i, 
+                         animation.current_frame, 
+                         match animation.state {
+                             AnimationState::Attack => animation.attack_frames,
+                             _ => 0
+                         });
+            }
+        }
+    }
+
+    // Stream world chunks in and out around the camera
+    fn stream_world(&mut self) {
+        if let Some(world) = &mut self.world {
+            let (view_width, view_height) = self.view_size;
+            match world.update_streaming(self.camera_x, self.camera_y, view_width, view_height) {
+                Ok(true) => self.rebuild_collision(),
+                Ok(false) => {},
+                Err(e) => eprintln!("Failed to stream world chunks: {}", e),
+            }
+        }
+    }
+
+    // Collision (and everything planned on it) from the streamed world if there is
+    // one, otherwise from the level's tilemap
+    fn rebuild_collision(&mut self) {
+        self.collision = match &self.world {
+            Some(world) => CollisionGrid::from_chunks(world, &self.tile_types),
+            None => self.tilemap.as_ref().map(|tilemap| CollisionGrid::from_tilemap(tilemap, &self.tile_types)),
+        };
+        self.paths.clear();
+        self.player_flow.invalidate_all();
+    }
+
+    // Write edited world chunks back to disk; main calls this before quitting, and
+    // chunks that stream out are saved as they go
+    pub fn save_world(&mut self) {
+        if let Some(world) = &mut self.world
+            && let Err(e) = world.save_all() {
+            eprintln!("Failed to save world chunks: {}", e);
+        }
+    }
+
+    // Push pending tile edits into the caches that depend on tile data and keep them
+    // in tile_events so other systems can react to this frame's changes
+    fn flush_tile_changes(&mut self) {
+        let changes = match (&mut self.world, &mut self.tilemap) {
+            (Some(world), _) => {
+                let edits = world.take_changes();
+                // An edit that had to load its chunk lies outside the grid until it's rebuilt
+                let on_grid = |collision: &Option<CollisionGrid>, x, y| collision.as_ref().and_then(|grid| grid.cell(x, y));
+                if edits.iter().any(|edit| on_grid(&self.collision, edit.x, edit.y).is_none()) {
+                    self.rebuild_collision();
+                }
+                // World edits are reported in collision grid cells, like a tilemap's own tiles
+                edits.iter()
+                    .filter_map(|edit| on_grid(&self.collision, edit.x, edit.y)
+                        .map(|(x, y)| TileChange { x, y, old: edit.old, new: edit.new }))
+                    .collect()
+            },
+            (None, Some(tilemap)) => tilemap.take_changes(),
+            (None, None) => return,
+        };
+        if changes.is_empty() {
+            return;
+        }
+        
+        if let Some(collision) = &mut self.collision {
+            collision.apply_changes(&changes, &self.tile_types);
+        }
+        self.paths.invalidate(&changes);
+        self.player_flow.invalidate(&changes);
+        self.tile_events.extend(changes);
+    }
+
+    fn update_transition(&mut self, delta_time: f32) -> bool {
+        let transition = match &mut self.transition {
+            Some(transition) => transition,
+            None => return false,
+        };
+        
+        if transition.update(delta_time) {
+            let target_level = transition.target_level.clone();
+            let entry = transition.entry.clone();
+            // The screen stays black on the loading screen until the new level is ready
+            self.begin_loading(&target_level, Some(&entry));
+        }
+        
+        if self.transition.as_ref().is_some_and(|transition| transition.is_finished()) {
+            self.transition = None;
+        }
+        true
+    }
+
+    fn check_triggers(&mut self) {
+        let (level, player_pos) = match (&self.level, self.positions.first()) {
+            (Some(level), Some(position)) => (level, position),
+            _ => return,
+        };
+        
+        let current = TriggerSystem::find(&level.triggers, player_pos);
+        if current != self.active_trigger
+            && let Some(trigger) = current.and_then(|index| level.triggers.get(index)) {
+            println!("Entering {} at {}", trigger.target_level, trigger.entry);
+            self.transition = Some(SceneTransition::new(&trigger.target_level, &trigger.entry));
+        }
+        self.active_trigger = current;
+    }
+
+    fn collect_pickups(&mut self) {
+        let (Some(player_pos), Some(inventory)) = (self.positions.first(), self.inventories.first_mut()) else { return };
+        for pickup in PickupSystem::collect(&mut self.pickups, player_pos, inventory) {
+            println!("Picked up {} x{} ({} held)", pickup.item, pickup.count, inventory.count(&pickup.item));
+        }
+    }
+
+    // Waves are counted from 1 for the log; events keep the index
+    fn report_waves(&self) {
+        for event in &self.wave_events {
+            match event {
+                WaveEvent::Started { spawner, wave } => println!("Spawner {} started wave {}", spawner, wave + 1),
+                WaveEvent::Completed { spawner, wave } => println!("Spawner {} cleared wave {}", spawner, wave + 1),
+                WaveEvent::Finished { spawner } => println!("Spawner {} has no waves left", spawner),
+            }
+        }
+    }
+
+    // Refresh the flow field to the player when enough enemies are chasing them for
+    // it to pay off. Returns whether AI should use it this frame.
+    fn update_player_flow(&mut self) -> bool {
+        let chasers = self.ais.iter().skip(1)
+            .filter(|ai| matches!(&ai.behavior, AiState::Chase(chase) if chase.target_entity == Some(0)))
+            .count();
+        let (Some(grid), Some(player)) = (&self.collision, self.positions.first()) else { return false };
+        if chasers < FLOW_FIELD_MIN_CHASERS {
+            return false;
+        }
+        
+        let (feet_x, feet_y) = player.feet();
+        let Some(player_tile) = grid.tile_at(feet_x, feet_y) else { return false };
+        // Only recomputed when the player reaches another tile or the map changes
+        self.player_flow.set_goals(grid, &[player_tile]);
+        true
+    }
+
+    fn update_animations(&mut self) {
+        for (i, action_state) in self.action_states.iter().enumerate() {
+            if let Some(animation) = self.animations.get_mut(i) {
+                // Handle attack initiation
+                if action_state.is_attacking() {
+                    if !animation.is_attack_in_progress {  // Remove parentheses
+                        println!("Starting attack animation for entity {}", i);
+                        animation.state = AnimationState::Attack;
+                        animation.current_frame = 0;
+                        animation.is_attack_in_progress = true;
+                    }
+                } 
+                // Only change to other states if not attacking
+                else if !animation.is_attack_in_progress {  // Remove parentheses
+                    let new_state = if action_state.is_moving() {
+                        AnimationState::Walk
+                    } else {
+                        AnimationState::Idle
+                    };
+                    
+                    // Only update if state changes
+                    if animation.state != new_state {  // Remove parentheses
+                        animation.state = new_state;
+                        animation.current_frame = 0;
+                    }
+                }
+            }
+        }
+    }
+
+    pub fn update_camera(&mut self, player_pos: &Position) {
+        let (screen_width, screen_height) = self.view_size;
+        
+        // Chunked worlds have no fixed size, so just center on the player
+        if self.world.is_some() {
+            self.camera_x = player_pos.x as i32 - screen_width / 2;
+            self.camera_y = player_pos.y as i32 - screen_height / 2;
+            return;
+        }
+        
+        // Center camera on player with boundaries
+        if let Some(tilemap) = &self.tilemap {
+            // Calculate max camera position (so we don't show beyond the map)
+            let max_camera_x = (tilemap.width * tilemap.tile_size as usize) as i32 - screen_width;
+            let max_camera_y = (tilemap.height * tilemap.tile_size as usize) as i32 - screen_height;
+            
+            // Center on player
+            self.camera_x = (player_pos.x as i32 - screen_width / 2).max(0).min(max_camera_x);
+            self.camera_y = (player_pos.y as i32 - screen_height / 2).max(0).min(max_camera_y);
+        }
+    }
+
+    pub fn render(&self, canvas: &mut sdl2::render::Canvas<sdl2::video::Window>) {
+        if let Some(loading) = &self.loading {
+            loading.render(canvas);
+            return;
+        }
+        
+        let drawables = Drawables {
+            entities: &self.entities,
+            textures: &self.textures,
+            animations: &self.animations,
+            sprites: &self.sprites,
+            positions: &self.positions,
+            velocities: &self.velocities,
+            projectiles: &self.projectiles,
+        };
+        RenderSystem::render(
+            canvas,
+            &self.assets,
+            &drawables,
+            self.camera_x,
+            self.camera_y,
+            self.tilemap.as_ref(),
+            self.world.as_ref()
+        );
+        PickupSystem::render(canvas, &self.pickups, self.camera_x, self.camera_y);
+        LightSystem::render(canvas, &self.positions, &self.sprites, &self.lights, self.camera_x, self.camera_y);
+        
+        if self.editor.active
+            && let Some(tilemap) = &self.tilemap {
+            EditorRenderSystem::render(
+                canvas,
+                &self.assets,
+                &self.editor,
+                tilemap,
+                self.level.as_ref(),
+                self.camera_x,
+                self.camera_y
+            );
+        }
+        
+        // Fade overlay while switching levels
+        if let Some(transition) = &self.transition {
+            canvas.set_blend_mode(sdl2::render::BlendMode::Blend);
+            canvas.set_draw_color(sdl2::pixels::Color::RGBA(0, 0, 0, transition.alpha()));
+            let _ = canvas.fill_rect(None);
+            canvas.set_blend_mode(sdl2::render::BlendMode::None);
+        }
+    }
+}
\ No newline at end of file
@@ -0,0 +1,155 @@
+// Saving the world to a SaveGame and rebuilding it from one
+use super::GameState;
+use crate::save::{self, SaveFormat, SaveGame, SavedEntity, SavedTile};
+use crate::systems::trigger_system::TriggerSystem;
+use std::collections::HashMap;
+
+impl<'a> GameState<'a> {
+    // Everything needed to rebuild the current world; None until a level is loaded
+    pub fn snapshot(&mut self) -> Option<SaveGame> {
+        let level = self.level.as_ref()?;
+        
+        // Tiles are stored as the difference from the level's own tilemap file
+        let mut tiles = Vec::new();
+        let mut tile_damage = Vec::new();
+        if let Some(tilemap) = &self.tilemap {
+            let original = self.assets.load_tilemap(&level.tilemap).ok();
+            let original = original.as_ref().and_then(|handle| self.assets.tilemap(handle));
+            for (y, row) in tilemap.tiles.iter().enumerate() {
+                for (x, tile) in row.iter().enumerate() {
+                    let unchanged = original
+                        .and_then(|original| original.tiles.get(y))
+                        .and_then(|row| row.get(x))
+                        .is_some_and(|original| original == tile);
+                    if !unchanged {
+                        tiles.push(SavedTile { x, y, tile: *tile });
+                    }
+                }
+            }
+            tile_damage = tilemap.tile_damage.iter().map(|(cell, damage)| (*cell, *damage)).collect();
+            tile_damage.sort();
+        }
+        
+        let mut entities = Vec::new();
+        let mut saved_index = HashMap::new(); // Entity id -> index in `entities`
+        let mut saved_at = HashMap::new();    // Entity index -> index in `entities`
+        for i in 0..self.entities.len() {
+            // Projectiles in flight would be gone a moment later anyway
+            if self.projectiles[i].is_some() {
+                continue;
+            }
+            let Some(definition) = self.entity_factory.definition_name(&self.entities[i]) else {
+                eprintln!("Entity {} has no definition and won't be saved", self.entities[i].0);
+                continue;
+            };
+            saved_index.insert(self.entities[i].0, entities.len());
+            saved_at.insert(i, entities.len());
+            entities.push(SavedEntity {
+                definition: definition.to_string(),
+                position: self.positions[i],
+                health: self.healths[i].clone(),
+                animation: self.animations[i].clone(),
+                sprite: self.sprites[i],
+                ai: self.ais[i].clone(),
+                action_state: self.action_states[i].clone(),
+                inventory: self.inventories[i].clone(),
+                light: self.lights[i],
+                velocity: self.velocities[i],
+                damage: self.damages[i],
+                spawner: self.spawners[i].clone(),
+            });
+        }
+        // Entity ids don't survive loading and skipped entities shift the rest, so
+        // spawners and AI targets refer to entities by where they are in the save
+        for entity in &mut entities {
+            entity.ai.remap_entities(|index| saved_at.get(&index).copied());
+            if let Some(spawner) = &mut entity.spawner {
+                spawner.alive = spawner.alive.iter()
+                    .filter_map(|&(id, wave)| saved_index.get(&id).map(|&index| (index, wave)))
+                    .collect();
+            }
+        }
+        
+        Some(SaveGame {
+            level: self.level_path.clone(),
+            camera: (self.camera_x, self.camera_y),
+            tiles,
+            tile_damage,
+            entities,
+        })
+    }
+
+    pub fn save_game(&mut self, slot: u32, format: SaveFormat) -> Result<(), String> {
+        let game = self.snapshot().ok_or_else(|| "Nothing to save: no level is loaded".to_string())?;
+        let path = save::write_slot(slot, format, &game)?;
+        println!("Saved game to {}", path.display());
+        Ok(())
+    }
+
+    // Load the save's level behind the loading screen, then put its entities and
+    // tile edits back (see restore)
+    pub fn load_game(&mut self, slot: u32) -> Result<(), String> {
+        let game = save::read_slot(slot)?;
+        self.transition = None;
+        self.begin_loading(&game.level, None);
+        self.pending_save = Some(game);
+        Ok(())
+    }
+
+    // Replace the freshly loaded level's entities and tiles with a save's
+    pub(super) fn restore(&mut self, game: SaveGame) {
+        if let Some(tilemap) = &mut self.tilemap {
+            for tile in &game.tiles {
+                tilemap.set_tile(tile.x, tile.y, tile.tile);
+            }
+            tilemap.tile_damage = game.tile_damage.iter().copied().collect();
+        }
+        self.flush_tile_changes();
+        
+        self.clear_entities();
+        let mut restored = Vec::new(); // New entity index and id for each saved entity
+        for saved in game.entities {
+            let mut components = match self.entity_factory.create_entity(&mut self.assets, &saved.definition, 0.0, 0.0) {
+                Ok(components) => components,
+                Err(e) => {
+                    eprintln!("Failed to restore {}: {}", saved.definition, e);
+                    restored.push(None);
+                    continue;
+                }
+            };
+            restored.push(Some((self.entities.len(), components.entity.0)));
+            // Textures, input and perception come from the definition (so enemies forget
+            // what they'd noticed); everything else from the save
+            components.position = saved.position;
+            components.health = saved.health;
+            components.animation = saved.animation;
+            components.sprite = saved.sprite;
+            components.ai = saved.ai;
+            components.action_state = saved.action_state;
+            components.light = saved.light;
+            components.velocity = saved.velocity;
+            components.damage = saved.damage;
+            components.spawner = saved.spawner;
+            self.add_entity(components);
+            if let Some(inventory) = self.inventories.last_mut() {
+                *inventory = saved.inventory;
+            }
+        }
+        for ai in &mut self.ais {
+            ai.remap_entities(|index| restored.get(index).copied().flatten().map(|(restored_at, _)| restored_at));
+        }
+        for spawner in self.spawners.iter_mut().flatten() {
+            spawner.alive = spawner.alive.iter()
+                .filter_map(|&(index, wave)| restored.get(index).copied().flatten().map(|(_, id)| (id, wave)))
+                .collect();
+        }
+        self.assets.unload_unused();
+        
+        (self.camera_x, self.camera_y) = game.camera;
+        self.active_trigger = match (&self.level, self.positions.first()) {
+            (Some(level), Some(position)) => TriggerSystem::find(&level.triggers, position),
+            _ => None,
+        };
+        println!("Restored {} entities from save", self.entities.len());
+    }
+}
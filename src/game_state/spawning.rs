@@ -0,0 +1,169 @@
+// Adding and removing entities: every component column grows or shrinks together
+use super::GameState;
+use crate::components::*;
+use crate::entity_factory::EntityComponents;
+use crate::navigation::steering;
+use crate::systems::spawner_system::{SpawnRequest, SpawnerSystem};
+
+impl<'a> GameState<'a> {
+    pub(super) fn take_player(&mut self) -> Option<(EntityComponents<'a>, Inventory)> {
+        if self.entities.is_empty() {
+            return None;
+        }
+        
+        let components = EntityComponents {
+            entity: self.entities.remove(0),
+            position: self.positions.remove(0),
+            health: self.healths.remove(0),
+            textures: self.textures.remove(0),
+            animation: self.animations.remove(0),
+            sprite: self.sprites.remove(0),
+            input_bindings: self.input_bindings.remove(0),
+            ai: self.ais.remove(0),
+            action_state: self.action_states.remove(0),
+            light: self.lights.remove(0),
+            velocity: self.velocities.remove(0),
+            damage: self.damages.remove(0),
+            perception: self.perceptions.remove(0),
+            hurtbox: self.hurtboxes.remove(0),
+            projectile: self.projectiles.remove(0),
+            ranged_attack: self.ranged_attacks.remove(0),
+            faction: self.factions.remove(0),
+            spawner: self.spawners.remove(0),
+        };
+        Some((components, self.inventories.remove(0)))
+    }
+
+    pub(super) fn clear_entities(&mut self) {
+        self.entities.clear();
+        self.positions.clear();
+        self.healths.clear();
+        self.textures.clear();
+        self.animations.clear();
+        self.sprites.clear();
+        self.lights.clear();
+        self.velocities.clear();
+        self.damages.clear();
+        self.perceptions.clear();
+        self.hurtboxes.clear();
+        self.projectiles.clear();
+        self.ranged_attacks.clear();
+        self.factions.clear();
+        self.spawners.clear();
+        self.input_bindings.clear();
+        self.ais.clear();
+        self.action_states.clear();
+        self.inventories.clear();
+    }
+
+    pub fn add_entity(&mut self, components: EntityComponents<'a>) {
+        self.entities.push(components.entity);
+        self.positions.push(components.position);
+        self.healths.push(components.health);
+        self.textures.push(components.textures);
+        self.animations.push(components.animation);
+        self.sprites.push(components.sprite);
+        self.input_bindings.push(components.input_bindings);
+        self.ais.push(components.ai);
+        self.action_states.push(components.action_state);
+        self.lights.push(components.light);
+        self.velocities.push(components.velocity);
+        self.damages.push(components.damage);
+        self.perceptions.push(components.perception);
+        self.hurtboxes.push(components.hurtbox);
+        self.projectiles.push(components.projectile);
+        self.ranged_attacks.push(components.ranged_attack);
+        self.factions.push(components.faction);
+        self.spawners.push(components.spawner);
+        self.inventories.push(Inventory::default());
+    }
+
+    // Take an entity out of every column. Entities after it move down one index, so
+    // AI targets are renumbered to match.
+    pub fn remove_entity(&mut self, index: usize) {
+        let entity = self.entities.remove(index);
+        self.entity_factory.forget(&entity);
+        self.paths.forget(entity.0);
+        self.positions.remove(index);
+        self.healths.remove(index);
+        self.textures.remove(index);
+        self.animations.remove(index);
+        self.sprites.remove(index);
+        self.input_bindings.remove(index);
+        self.ais.remove(index);
+        self.action_states.remove(index);
+        self.lights.remove(index);
+        self.velocities.remove(index);
+        self.damages.remove(index);
+        self.perceptions.remove(index);
+        self.hurtboxes.remove(index);
+        self.projectiles.remove(index);
+        self.ranged_attacks.remove(index);
+        self.factions.remove(index);
+        self.spawners.remove(index);
+        self.inventories.remove(index);
+        for ai in &mut self.ais {
+            ai.entity_removed(index);
+        }
+        for perception in self.perceptions.iter_mut().flatten() {
+            perception.entity_removed(index);
+        }
+    }
+
+    // Spawn a projectile for each entity starting a ranged attack, aimed at its AI's
+    // target or else straight ahead. They fly at feet height, where walls are
+    // checked for everything else.
+    pub(super) fn fire_projectiles(&mut self, shooters: &[usize]) {
+        for &i in shooters {
+            let Some(ranged_attack) = self.ranged_attacks[i].clone() else { continue };
+            let position = self.positions[i];
+            let origin = position.feet();
+            let ahead = if position.facing_right { (1.0, 0.0) } else { (-1.0, 0.0) };
+            let direction = match self.ais[i].target().and_then(|target| self.positions.get(target)) {
+                Some(target) => {
+                    let aim = target.feet();
+                    let direction = steering::normalize((aim.0 - origin.0, aim.1 - origin.1));
+                    if direction == (0.0, 0.0) { ahead } else { direction }
+                },
+                None => ahead,
+            };
+            
+            let mut components = match self.entity_factory.create_entity(&mut self.assets, &ranged_attack.projectile, 0.0, 0.0) {
+                Ok(components) => components,
+                Err(e) => {
+                    eprintln!("Entity {} can't shoot: {}", i, e);
+                    continue;
+                }
+            };
+            // The definition's velocity sets the speed; the shooter sets the direction
+            let speed = components.velocity.map_or(0.0, |velocity| steering::length((velocity.x, velocity.y)));
+            components.velocity = Some(Velocity { x: direction.0 * speed, y: direction.1 * speed });
+            // Centred on the shooter
+            let sprite = components.sprite.unwrap_or_default();
+            let (half_width, half_height) = (sprite.frame_width as f32 * sprite.scale / 2.0, sprite.frame_height as f32 * sprite.scale / 2.0);
+            components.position.x = origin.0 - half_width;
+            components.position.y = origin.1 - half_height;
+            if let Some(projectile) = &mut components.projectile {
+                projectile.owner = Some(self.entities[i].0);
+            }
+            // On the shooter's side, so it spares whoever the shooter would
+            components.faction = self.factions[i].clone();
+            self.add_entity(components);
+        }
+    }
+
+    // Create what the spawners asked for this frame
+    pub(super) fn spawn_enemies(&mut self, requests: &[SpawnRequest]) {
+        for request in requests {
+            let components = match self.entity_factory.create_entity(&mut self.assets, &request.entity, request.x, request.y) {
+                Ok(components) => components,
+                Err(e) => {
+                    eprintln!("Spawner {} can't spawn: {}", self.entities[request.spawner].0, e);
+                    continue;
+                }
+            };
+            SpawnerSystem::spawned(&mut self.spawners, request, &components.entity);
+            self.add_entity(components);
+        }
+    }
+}
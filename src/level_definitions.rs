@@ -0,0 +1,98 @@
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct LevelDefinition {
+    #[serde(default)]
+    pub tilemap: String, // CSV tile layer, unused when the level is a streamed world
+    #[serde(default)]
+    pub world: Option<String>, // Chunk directory streamed instead of the tilemap (see ChunkedTilemap)
+    pub tileset: String,
+    pub tile_size: u32,
+    #[serde(default)]
+    pub spawns: Vec<SpawnDefinition>,
+    #[serde(default)]
+    pub triggers: Vec<TriggerDefinition>,
+    #[serde(default)]
+    pub entries: HashMap<String, (f32, f32)>, // Named places other levels can send the player to
+    #[serde(default)]
+    pub pickups: Vec<PickupDefinition>,
+}
+
+// One entry of the level's object layer: which entity to create, where, and any
+// per-instance overrides of its entities.ron definition
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct SpawnDefinition {
+    pub entity: String,
+    pub x: f32,
+    pub y: f32,
+    #[serde(default = "default_facing_right")]
+    pub facing_right: bool,
+    #[serde(default)]
+    pub health: Option<u32>,
+    #[serde(default)]
+    pub max_health: Option<u32>,
+    #[serde(default)]
+    pub patrol: Option<Vec<(f32, f32)>>, // Waypoints; switches the entity to patrol AI
+}
+
+// Region that moves the player to another level when they walk into it
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct TriggerDefinition {
+    pub x: f32,
+    pub y: f32,
+    pub width: f32,
+    pub height: f32,
+    pub target_level: String,
+    pub entry: String, // Entry point name in the target level
+}
+
+impl TriggerDefinition {
+    pub fn contains(&self, x: f32, y: f32) -> bool {
+        x >= self.x && x < self.x + self.width && y >= self.y && y < self.y + self.height
+    }
+}
+
+// Item lying in the level until the player walks over it (see PickupSystem)
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct PickupDefinition {
+    pub item: String,
+    #[serde(default = "default_count")]
+    pub count: u32,
+    pub x: f32,
+    pub y: f32,
+}
+
+fn default_count() -> u32 {
+    1
+}
+
+fn default_facing_right() -> bool {
+    true
+}
+
+impl SpawnDefinition {
+    pub fn new(entity: &str, x: f32, y: f32) -> Self {
+        SpawnDefinition {
+            entity: entity.to_string(),
+            x,
+            y,
+            facing_right: default_facing_right(),
+            health: None,
+            max_health: None,
+            patrol: None,
+        }
+    }
+}
+
+impl LevelDefinition {
+    pub fn save(&self, path: &str) -> Result<(), String> {
+        let ron_str = ron::ser::to_string_pretty(self, ron::ser::PrettyConfig::default())
+            .map_err(|e| format!("Failed to serialize level {}: {}", path, e))?;
+            
+        crate::vfs::write(path, ron_str.as_bytes())
+            .map_err(|e| format!("Failed to write level {}: {}", path, e))
+    }
+}
@@ -0,0 +1,70 @@
+use crate::assets::AssetServer;
+use crate::behaviour_tree::blackboard::WAYPOINTS;
+use crate::behaviour_tree::BlackboardValue;
+use crate::components::AiState;
+use crate::entity_factory::{EntityComponents, EntityFactory};
+use crate::level_definitions::{LevelDefinition, SpawnDefinition};
+
+pub struct LevelLoader;
+
+impl LevelLoader {
+    // Create every entity the level declares. The player is always spawned first
+    // because the systems treat entity index 0 as the player, so a level without
+    // a working player spawn is an error rather than a world with an enemy at 0.
+    pub fn spawn_entities<'a>(factory: &mut EntityFactory, assets: &mut AssetServer<'a>, level: &LevelDefinition) -> Result<Vec<EntityComponents<'a>>, String> {
+        let spawn = level.spawns.iter()
+            .find(|spawn| spawn.entity == "player")
+            .ok_or("Level has no player spawn")?;
+        let player = Self::spawn_entity(factory, assets, spawn)
+            .map_err(|e| format!("Failed to spawn player at ({}, {}): {}", spawn.x, spawn.y, e))?;
+        
+        let mut spawned = vec![player];
+        spawned.extend(Self::spawn_others(factory, assets, level));
+        Ok(spawned)
+    }
+    
+    // Create everything except the player, for when the player is carried over
+    // from the previous level
+    pub fn spawn_others<'a>(factory: &mut EntityFactory, assets: &mut AssetServer<'a>, level: &LevelDefinition) -> Vec<EntityComponents<'a>> {
+        let mut spawned = Vec::new();
+        for spawn in level.spawns.iter().filter(|spawn| spawn.entity != "player") {
+            match Self::spawn_entity(factory, assets, spawn) {
+                Ok(components) => spawned.push(components),
+                Err(e) => eprintln!("Failed to spawn {} at ({}, {}): {}", spawn.entity, spawn.x, spawn.y, e),
+            }
+        }
+        spawned
+    }
+    
+    pub fn spawn_entity<'a>(factory: &mut EntityFactory, assets: &mut AssetServer<'a>, spawn: &SpawnDefinition) -> Result<EntityComponents<'a>, String> {
+        let mut components = factory.create_entity(assets, &spawn.entity, spawn.x, spawn.y)?;
+        
+        // Apply per-spawn overrides on top of the entity definition
+        components.position.facing_right = spawn.facing_right;
+        
+        // Health overrides only mean something for entities that have health
+        if let Some(health) = &mut components.health {
+            if let Some(max_health) = spawn.max_health {
+                health.max = max_health;
+                health.current = health.current.min(max_health);
+            }
+            if let Some(current) = spawn.health {
+                health.current = current.min(health.max);
+            }
+        }
+        
+        if let Some(waypoints) = &spawn.patrol
+            && !waypoints.is_empty() {
+            // Chasers walk the route while guarding, behaviour trees with their
+            // Patrol node; utility AI has no use for it; anything else just patrols
+            match &mut components.ai.behavior {
+                AiState::Chase(chase) => chase.patrol = waypoints.clone(),
+                AiState::Behaviour(state) => state.blackboard.set(WAYPOINTS, BlackboardValue::Points(waypoints.clone())),
+                AiState::Utility(_) => {},
+                behavior => *behavior = AiState::Patrol { waypoints: waypoints.clone(), current_waypoint: 0 },
+            }
+        }
+        
+        Ok(components)
+    }
+}
@@ -0,0 +1,43 @@
+use sdl2::pixels::Color;
+use sdl2::rect::Rect;
+use sdl2::render::Canvas;
+use sdl2::video::Window;
+
+const BAR_WIDTH: u32 = 400;
+const BAR_HEIGHT: u32 = 20;
+
+// Shown while a level's assets are read and decoded on the loader threads.
+// The level is swapped in once everything it needs is in the asset cache.
+pub struct LoadingScreen {
+    pub level_path: String,
+    pub entry: Option<String>,
+    pub progress: f32, // 0.0 to 1.0
+}
+
+impl LoadingScreen {
+    pub fn new(level_path: &str, entry: Option<&str>) -> Self {
+        LoadingScreen {
+            level_path: level_path.to_string(),
+            entry: entry.map(str::to_string),
+            progress: 0.0,
+        }
+    }
+    
+    pub fn render(&self, canvas: &mut Canvas<Window>) {
+        canvas.set_draw_color(Color::RGB(0, 0, 0));
+        canvas.clear();
+        
+        let viewport = canvas.viewport();
+        let x = (viewport.width() as i32 - BAR_WIDTH as i32) / 2;
+        let y = (viewport.height() as i32 - BAR_HEIGHT as i32) / 2;
+        let outline = Rect::new(x, y, BAR_WIDTH, BAR_HEIGHT);
+        let filled = (BAR_WIDTH as f32 * self.progress.clamp(0.0, 1.0)) as u32;
+        
+        if filled > 0 {
+            canvas.set_draw_color(Color::RGB(200, 200, 200));
+            let _ = canvas.fill_rect(Rect::new(x, y, filled, BAR_HEIGHT));
+        }
+        canvas.set_draw_color(Color::RGB(255, 255, 255));
+        let _ = canvas.draw_rect(outline);
+    }
+}
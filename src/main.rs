@@ -2,9 +2,22 @@ mod components;
 mod systems;
 mod game_state;
 mod entity_factory;
+mod component_registry;
 mod entity_definitions;
-mod asset_manager;
-mod resource_manager;
+mod assets;
+mod procgen;
+mod level_definitions;
+mod level_loader;
+mod scene_transition;
+mod loading_screen;
+mod editor;
+mod vfs;
+mod validate;
+mod save;
+mod navigation;
+mod behaviour_tree;
+mod utility_ai;
+mod factions;
 
 use sdl2::{event::Event, keyboard::Scancode};
 use std::time::{Instant, Duration};
@@ -12,8 +25,20 @@ use game_state::GameState;
 use sdl2::pixels::Color;
 
 fn main() -> Result<(), String> {
-    // debug to see where the program is running.
-    println!("Working directory: {:?}", std::env::current_dir().unwrap());
+    // Tool subcommands run without opening a window
+    let args: Vec<String> = std::env::args().collect();
+    vfs::init(vfs::Vfs::from_environment());
+    match args.get(1).map(String::as_str) {
+        Some("generate") => return procgen::run_cli(&args[2..]),
+        Some("pack") => return vfs::run_pack_cli(&args[2..]),
+        Some("validate") => return validate::run_cli(&args[2..]),
+        _ => {},
+    }
+    
+    // debug to see where assets are being read from
+    for mount in vfs::get().mounts() {
+        println!("Mounted {}", mount.describe());
+    }
 
     let sdl_context = sdl2::init()?;
     let video_subsystem = sdl_context.video()?;
@@ -33,13 +58,8 @@ fn main() -> Result<(), String> {
     
     // Create game state
     let mut game = GameState::new(&texture_creator);
-    
-    // Add this right after creating the game state
-    let player_count = game.entities.len();
-    println!("Loaded {} entities", player_count);
-    if player_count > 0 {
-        println!("Player position: ({}, {})", game.positions[0].x, game.positions[0].y);
-    }
+    let viewport = canvas.viewport();
+    game.view_size = (viewport.width() as i32, viewport.height() as i32);
     
     let mut last_frame_time = Instant::now();
 
@@ -53,7 +73,7 @@ fn main() -> Result<(), String> {
         for event in event_pump.poll_iter() {
             match event {
                 Event::Quit {..} => break 'running,
-                _ => {}
+                _ => game.handle_event(&event),
             }
         }
 
@@ -103,5 +123,6 @@ fn main() -> Result<(), String> {
         ::std::thread::sleep(Duration::new(0, 1_000_000_000u32 / 60));
     }
     
+    game.save_world();
     Ok(())
 }
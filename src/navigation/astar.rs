@@ -0,0 +1,298 @@
+use crate::components::CollisionGrid;
+use std::cmp::Ordering;
+use std::collections::{BinaryHeap, HashMap};
+
+pub type Tile = (usize, usize);
+
+const DIAGONAL_COST: f32 = std::f32::consts::SQRT_2;
+
+// When a diagonal step may squeeze past a wall corner
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum DiagonalRule {
+    Never,           // 4-way movement only
+    NoCornerCutting, // Only if both tiles beside the step are walkable
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct PathOptions {
+    pub diagonals: DiagonalRule,
+    pub max_expanded: usize, // Give up after visiting this many tiles
+}
+
+impl Default for PathOptions {
+    fn default() -> Self {
+        PathOptions {
+            diagonals: DiagonalRule::NoCornerCutting,
+            max_expanded: 4096,
+        }
+    }
+}
+
+// Frontier entry for A* (and the flow field's Dijkstra, where estimate == cost)
+#[derive(PartialEq)]
+pub(super) struct Open {
+    pub(super) estimate: f32,
+    pub(super) cost: f32,
+    pub(super) tile: Tile,
+}
+
+impl Eq for Open {}
+
+// Reversed so BinaryHeap pops the lowest estimate first
+impl Ord for Open {
+    fn cmp(&self, other: &Self) -> Ordering {
+        other.estimate.total_cmp(&self.estimate)
+    }
+}
+
+impl PartialOrd for Open {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+// Octile distance: the cheapest possible cost on an 8-way grid where every tile
+// costs at least 1
+fn heuristic(from: Tile, to: Tile, diagonals: DiagonalRule) -> f32 {
+    let dx = from.0.abs_diff(to.0) as f32;
+    let dy = from.1.abs_diff(to.1) as f32;
+    if diagonals == DiagonalRule::Never {
+        dx + dy
+    } else {
+        dx.max(dy) + (DIAGONAL_COST - 1.0) * dx.min(dy)
+    }
+}
+
+// Walkable neighbours of a tile, each with the length of the step to it (1 or √2).
+// Corner rules only depend on the two tiles beside a step, so moves are symmetric.
+pub(super) fn neighbours(grid: &CollisionGrid, (x, y): Tile, diagonals: DiagonalRule) -> Vec<(Tile, f32)> {
+    let mut result = Vec::with_capacity(8);
+    for (dx, dy) in [(-1, 0), (1, 0), (0, -1), (0, 1), (-1, -1), (1, -1), (-1, 1), (1, 1)] {
+        let diagonal = dx != 0 && dy != 0;
+        if diagonal && diagonals == DiagonalRule::Never {
+            continue;
+        }
+        let (Some(nx), Some(ny)) = (x.checked_add_signed(dx), y.checked_add_signed(dy)) else { continue };
+        if grid.is_solid(nx, ny) {
+            continue;
+        }
+
+        let mut step = 1.0;
+        if diagonal {
+            // No cutting past a wall corner
+            if grid.is_solid(nx, y) || grid.is_solid(x, ny) {
+                continue;
+            }
+            step = DIAGONAL_COST;
+        }
+        result.push(((nx, ny), step));
+    }
+    result
+}
+
+// Cheapest path from `start` to `goal`, both included. The start tile itself may be
+// blocked (an entity brushing a wall); None if the goal can't be reached.
+pub fn find_path(grid: &CollisionGrid, start: Tile, goal: Tile, options: &PathOptions) -> Option<Vec<Tile>> {
+    grid.cost(goal.0, goal.1)?;
+    if start == goal {
+        return Some(vec![start]);
+    }
+
+    let mut open = BinaryHeap::new();
+    let mut came_from: HashMap<Tile, Tile> = HashMap::new();
+    let mut best: HashMap<Tile, f32> = HashMap::new();
+    open.push(Open { estimate: heuristic(start, goal, options.diagonals), cost: 0.0, tile: start });
+    best.insert(start, 0.0);
+
+    let mut expanded = 0;
+    while let Some(Open { tile, cost: cost_here, .. }) = open.pop() {
+        if tile == goal {
+            let mut path = vec![goal];
+            let mut current = goal;
+            while let Some(&previous) = came_from.get(&current) {
+                path.push(previous);
+                current = previous;
+            }
+            path.reverse();
+            return Some(path);
+        }
+
+        // Already reached more cheaply since this entry was queued
+        if best.get(&tile).is_some_and(|&known| known < cost_here) {
+            continue;
+        }
+        expanded += 1;
+        if expanded > options.max_expanded {
+            return None;
+        }
+
+        for (next, step) in neighbours(grid, tile, options.diagonals) {
+            let cost = cost_here + step * grid.cost(next.0, next.1).unwrap_or(1.0);
+            if best.get(&next).is_none_or(|&known| cost < known) {
+                best.insert(next, cost);
+                came_from.insert(next, tile);
+                open.push(Open { estimate: cost + heuristic(next, goal, options.diagonals), cost, tile: next });
+            }
+        }
+    }
+    None
+}
+
+// Clearance kept from blocked tiles when cutting corners, in tiles
+const SMOOTHING_CLEARANCE: f32 = 0.25;
+
+// Whether a straight walk between two tile centres stays on tiles that are walkable
+// and cost no more than `max_cost`, keeping a little clearance from walls
+fn line_is_clear(grid: &CollisionGrid, from: Tile, to: Tile, max_cost: f32) -> bool {
+    let (x0, y0) = (from.0 as f32 + 0.5, from.1 as f32 + 0.5);
+    let (x1, y1) = (to.0 as f32 + 0.5, to.1 as f32 + 0.5);
+    let steps = (from.0.abs_diff(to.0).max(from.1.abs_diff(to.1)) * 4).max(1);
+    let walkable = |x: f32, y: f32| {
+        x >= 0.0 && y >= 0.0 && grid.cost(x as usize, y as usize).is_some_and(|cost| cost <= max_cost)
+    };
+    (0..=steps).all(|step| {
+        let t = step as f32 / steps as f32;
+        let (x, y) = (x0 + (x1 - x0) * t, y0 + (y1 - y0) * t);
+        [(-1.0, -1.0), (1.0, -1.0), (-1.0, 1.0), (1.0, 1.0)].iter()
+            .all(|(ox, oy)| walkable(x + ox * SMOOTHING_CLEARANCE, y + oy * SMOOTHING_CLEARANCE))
+    })
+}
+
+// Drop waypoints that can be skipped by walking straight, so entities move in lines
+// instead of grid staircases. A shortcut is only taken if it doesn't cross anything
+// more expensive than the tiles it replaces, so smoothing never leads into water.
+pub fn smooth_path(grid: &CollisionGrid, path: &[Tile]) -> Vec<Tile> {
+    let Some(&first) = path.first() else { return Vec::new() };
+    let mut smoothed = vec![first];
+    let mut anchor = 0;
+    while anchor < path.len() - 1 {
+        let mut furthest = anchor + 1;
+        let mut max_cost = grid.cost(path[furthest].0, path[furthest].1).unwrap_or(1.0);
+        for candidate in anchor + 2..path.len() {
+            let (x, y) = path[candidate];
+            max_cost = max_cost.max(grid.cost(x, y).unwrap_or(1.0));
+            if line_is_clear(grid, path[anchor], path[candidate], max_cost) {
+                furthest = candidate;
+            }
+        }
+        smoothed.push(path[furthest]);
+        anchor = furthest;
+    }
+    smoothed
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::navigation::grid;
+
+    fn path_cost(grid: &CollisionGrid, path: &[Tile]) -> f32 {
+        path.windows(2)
+            .map(|step| {
+                let (from, to) = (step[0], step[1]);
+                let length = if from.0 != to.0 && from.1 != to.1 { DIAGONAL_COST } else { 1.0 };
+                length * grid.cost(to.0, to.1).unwrap()
+            })
+            .sum()
+    }
+
+    #[test]
+    fn goes_around_walls() {
+        let grid = grid(&[
+            ".....",
+            ".###.",
+            ".#...",
+            ".#.#.",
+            "...#.",
+        ]);
+        let path = find_path(&grid, (2, 2), (0, 0), &PathOptions::default()).unwrap();
+        assert_eq!(path.first(), Some(&(2, 2)));
+        assert_eq!(path.last(), Some(&(0, 0)));
+        assert!(path.iter().all(|&(x, y)| !grid.is_solid(x, y)));
+        // Out either gap and round; the walls leave no corner to cut on the way
+        assert_eq!(path_cost(&grid, &path), 8.0);
+    }
+
+    #[test]
+    fn four_way_paths_have_no_diagonal_steps() {
+        let grid = grid(&[
+            "....",
+            "....",
+            "....",
+        ]);
+        let options = PathOptions { diagonals: DiagonalRule::Never, ..PathOptions::default() };
+        let path = find_path(&grid, (0, 0), (3, 2), &options).unwrap();
+        assert_eq!(path.len(), 6);
+        assert!(path.windows(2).all(|step| step[0].0.abs_diff(step[1].0) + step[0].1.abs_diff(step[1].1) == 1));
+    }
+
+    #[test]
+    fn never_cuts_wall_corners() {
+        let grid = grid(&[
+            ".#",
+            "..",
+        ]);
+        let path = find_path(&grid, (0, 0), (1, 1), &PathOptions::default()).unwrap();
+        assert_eq!(path, vec![(0, 0), (0, 1), (1, 1)]);
+    }
+
+    #[test]
+    fn detours_around_water_when_cheaper() {
+        let grid = grid(&[
+            ".....",
+            ".~~~.",
+            ".....",
+        ]);
+        let options = PathOptions { diagonals: DiagonalRule::Never, ..PathOptions::default() };
+        let path = find_path(&grid, (0, 1), (4, 1), &options).unwrap();
+        // Wading straight across costs 3 + 3 + 3 + 1; going round costs 6
+        assert!(path.iter().all(|&(x, y)| grid.cost(x, y) == Some(1.0)));
+        assert_eq!(path_cost(&grid, &path), 6.0);
+    }
+
+    #[test]
+    fn unreachable_and_blocked_goals_have_no_path() {
+        let grid = grid(&[
+            "..#..",
+            "..#..",
+            "..#..",
+        ]);
+        assert!(find_path(&grid, (0, 0), (4, 0), &PathOptions::default()).is_none());
+        assert!(find_path(&grid, (0, 0), (2, 1), &PathOptions::default()).is_none());
+        assert!(find_path(&grid, (0, 0), (9, 9), &PathOptions::default()).is_none());
+    }
+
+    #[test]
+    fn gives_up_after_max_expanded() {
+        let grid = grid(&[
+            "..........",
+            "..........",
+            "..........",
+        ]);
+        let options = PathOptions { max_expanded: 3, ..PathOptions::default() };
+        assert!(find_path(&grid, (0, 0), (9, 2), &options).is_none());
+        assert!(find_path(&grid, (0, 0), (9, 2), &PathOptions::default()).is_some());
+    }
+
+    #[test]
+    fn smoothing_keeps_clear_of_walls() {
+        let open = grid(&[
+            ".....",
+            ".....",
+            ".....",
+        ]);
+        let path = find_path(&open, (0, 0), (4, 2), &PathOptions::default()).unwrap();
+        assert_eq!(smooth_path(&open, &path), vec![(0, 0), (4, 2)]);
+
+        let walled = grid(&[
+            ".....",
+            "..#..",
+            ".....",
+        ]);
+        let path = find_path(&walled, (0, 1), (4, 1), &PathOptions::default()).unwrap();
+        let smoothed = smooth_path(&walled, &path);
+        assert_eq!(smoothed.first(), Some(&(0, 1)));
+        assert_eq!(smoothed.last(), Some(&(4, 1)));
+        assert!(smoothed.len() > 2, "Cut straight through the wall: {:?}", smoothed);
+    }
+}
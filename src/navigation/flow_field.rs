@@ -0,0 +1,209 @@
+use super::astar::{neighbours, DiagonalRule, Open, Tile};
+use crate::components::{CollisionGrid, TileChange};
+use std::collections::BinaryHeap;
+
+// Distance map towards a set of goal tiles plus, for every tile, the neighbour to step
+// to next. Built once per goal change with Dijkstra; after that any number of
+// entities can look up where to go in O(1), instead of each running its own A*.
+pub struct FlowField {
+    width: usize,
+    height: usize,
+    goals: Vec<Tile>,
+    distances: Vec<f32>, // Cost to the nearest goal, INFINITY if unreachable or out of range
+    next: Vec<Option<Tile>>,
+    max_distance: f32, // Tiles further than this (in path cost) are left unreached
+    diagonals: DiagonalRule,
+    dirty: bool, // The map changed under the current goals
+}
+
+impl FlowField {
+    pub fn new(max_distance: f32, diagonals: DiagonalRule) -> Self {
+        FlowField {
+            width: 0,
+            height: 0,
+            goals: Vec::new(),
+            distances: Vec::new(),
+            next: Vec::new(),
+            max_distance,
+            diagonals,
+            dirty: true,
+        }
+    }
+
+    // Point the field at new goals. Only recomputes when the goal tiles (or the map)
+    // actually changed, so calling this every frame with the player's tile is cheap.
+    pub fn set_goals(&mut self, grid: &CollisionGrid, goals: &[Tile]) {
+        let resized = self.width != grid.width || self.height != grid.height;
+        if !self.dirty && !resized && self.goals == goals {
+            return;
+        }
+        self.goals = goals.to_vec();
+        self.rebuild(grid);
+    }
+
+    // Any tile change can open or close a route, so the next set_goals recomputes
+    pub fn invalidate(&mut self, changes: &[TileChange]) {
+        if !changes.is_empty() {
+            self.dirty = true;
+        }
+    }
+
+    // For a whole new map
+    pub fn invalidate_all(&mut self) {
+        self.dirty = true;
+    }
+
+    fn rebuild(&mut self, grid: &CollisionGrid) {
+        self.width = grid.width;
+        self.height = grid.height;
+        self.distances = vec![f32::INFINITY; grid.width * grid.height];
+        self.next = vec![None; grid.width * grid.height];
+        self.dirty = false;
+
+        let mut open = BinaryHeap::new();
+        for &goal in &self.goals {
+            if grid.cost(goal.0, goal.1).is_some() {
+                self.distances[goal.1 * grid.width + goal.0] = 0.0;
+                open.push(Open { estimate: 0.0, cost: 0.0, tile: goal });
+            }
+        }
+
+        // Dijkstra outwards from the goals. Stepping from `from` onto `tile` costs
+        // the step length times `tile`'s cost, the same as A* charges.
+        while let Some(Open { tile, cost, .. }) = open.pop() {
+            if cost > self.distances[tile.1 * grid.width + tile.0] {
+                continue;
+            }
+            let tile_cost = grid.cost(tile.0, tile.1).unwrap_or(1.0);
+            for (from, step) in neighbours(grid, tile, self.diagonals) {
+                let distance = cost + step * tile_cost;
+                let index = from.1 * grid.width + from.0;
+                if distance < self.distances[index] && distance <= self.max_distance {
+                    self.distances[index] = distance;
+                    self.next[index] = Some(tile);
+                    open.push(Open { estimate: distance, cost: distance, tile: from });
+                }
+            }
+        }
+    }
+
+    fn index(&self, (x, y): Tile) -> Option<usize> {
+        (x < self.width && y < self.height).then(|| y * self.width + x)
+    }
+
+    // Path cost from a tile to the nearest goal
+    pub fn distance(&self, tile: Tile) -> Option<f32> {
+        self.index(tile)
+            .map(|index| self.distances[index])
+            .filter(|distance| distance.is_finite())
+    }
+
+    // The tile to step onto next from `tile`. None on a goal, or where no goal is
+    // reachable within range.
+    pub fn next_tile(&self, tile: Tile) -> Option<Tile> {
+        self.index(tile).and_then(|index| self.next[index])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::components::TileId;
+    use crate::navigation::astar::{find_path, PathOptions};
+    use crate::navigation::grid;
+
+    #[test]
+    fn following_the_field_reaches_the_goal_as_cheaply_as_a_star() {
+        let grid = grid(&[
+            "......",
+            ".####.",
+            ".#~~..",
+            ".#.#..",
+            "......",
+        ]);
+        let mut field = FlowField::new(100.0, DiagonalRule::NoCornerCutting);
+        field.set_goals(&grid, &[(2, 3)]);
+
+        let start = (5, 0);
+        let mut tile = start;
+        let mut walked = 0.0;
+        while let Some(next) = field.next_tile(tile) {
+            let step = if next.0 != tile.0 && next.1 != tile.1 { std::f32::consts::SQRT_2 } else { 1.0 };
+            walked += step * grid.cost(next.0, next.1).unwrap();
+            tile = next;
+        }
+        assert_eq!(tile, (2, 3));
+        assert!((walked - field.distance(start).unwrap()).abs() < 1e-4);
+
+        // Same cost as the path A* plans for the same trip the other way round
+        let path = find_path(&grid, (2, 3), start, &PathOptions::default()).unwrap();
+        let a_star: f32 = path.windows(2)
+            .map(|step| {
+                let diagonal = step[0].0 != step[1].0 && step[0].1 != step[1].1;
+                let length = if diagonal { std::f32::consts::SQRT_2 } else { 1.0 };
+                length * grid.cost(step[0].0, step[0].1).unwrap()
+            })
+            .sum();
+        assert!((a_star - walked).abs() < 1e-4, "A* {} vs flow field {}", a_star, walked);
+    }
+
+    #[test]
+    fn heads_for_the_nearest_goal() {
+        let grid = grid(&[
+            "..........",
+        ]);
+        let mut field = FlowField::new(100.0, DiagonalRule::Never);
+        field.set_goals(&grid, &[(0, 0), (9, 0)]);
+        assert_eq!(field.next_tile((3, 0)), Some((2, 0)));
+        assert_eq!(field.next_tile((6, 0)), Some((7, 0)));
+        assert_eq!(field.distance((6, 0)), Some(3.0));
+        assert_eq!(field.next_tile((0, 0)), None);
+        assert_eq!(field.distance((0, 0)), Some(0.0));
+    }
+
+    #[test]
+    fn stops_spreading_past_max_distance() {
+        let grid = grid(&[
+            "..........",
+        ]);
+        let mut field = FlowField::new(4.0, DiagonalRule::Never);
+        field.set_goals(&grid, &[(0, 0)]);
+        assert_eq!(field.distance((4, 0)), Some(4.0));
+        assert_eq!(field.distance((5, 0)), None);
+        assert_eq!(field.next_tile((5, 0)), None);
+    }
+
+    #[test]
+    fn walls_cut_tiles_off() {
+        let grid = grid(&[
+            "..#..",
+            "..#..",
+        ]);
+        let mut field = FlowField::new(100.0, DiagonalRule::NoCornerCutting);
+        field.set_goals(&grid, &[(0, 0)]);
+        assert!(field.distance((1, 1)).is_some());
+        assert_eq!(field.distance((3, 0)), None);
+        assert_eq!(field.next_tile((4, 1)), None);
+    }
+
+    #[test]
+    fn recomputes_only_after_the_map_changes() {
+        let closed = grid(&[
+            "..#..",
+        ]);
+        let open = grid(&[
+            ".....",
+        ]);
+        let mut field = FlowField::new(100.0, DiagonalRule::Never);
+        field.set_goals(&closed, &[(0, 0)]);
+        assert_eq!(field.distance((4, 0)), None);
+
+        // Same goals and size: the old field is kept until told the map changed
+        field.set_goals(&open, &[(0, 0)]);
+        assert_eq!(field.distance((4, 0)), None);
+
+        field.invalidate(&[TileChange { x: 2, y: 0, old: TileId(1), new: TileId(0) }]);
+        field.set_goals(&open, &[(0, 0)]);
+        assert_eq!(field.distance((4, 0)), Some(4.0));
+    }
+}
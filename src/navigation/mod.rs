@@ -0,0 +1,32 @@
+// Grid navigation over the level's CollisionGrid: A* paths with per-tile costs,
+// smoothed into straight legs and cached per entity, flow fields for crowds
+// heading to the same place, and steering to follow them smoothly.
+pub mod astar;
+pub mod flow_field;
+pub mod path_cache;
+pub mod steering;
+
+pub use self::astar::{DiagonalRule, PathOptions};
+pub use self::flow_field::FlowField;
+pub use self::path_cache::PathCache;
+pub use self::steering::Steering;
+
+// A grid of 32 pixel tiles drawn as text: '#' is a wall, '~' water and anything
+// else floor
+#[cfg(test)]
+pub(crate) fn grid(rows: &[&str]) -> crate::components::CollisionGrid {
+    use crate::components::{CollisionGrid, TileId, TileType, Tilemap};
+    use std::collections::HashMap;
+
+    let tiles = rows.iter()
+        .map(|row| row.chars()
+            .map(|c| match c {
+                '#' => TileId(1),
+                '~' => TileId(2),
+                _ => TileId(0),
+            })
+            .collect())
+        .collect();
+    let tile_types = HashMap::from([(TileId(1), TileType::Wall), (TileId(2), TileType::Water)]);
+    CollisionGrid::from_tilemap(&Tilemap::from_tiles(tiles, 32, None), &tile_types)
+}
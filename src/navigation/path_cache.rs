@@ -0,0 +1,161 @@
+use super::astar::{find_path, smooth_path, PathOptions, Tile};
+use crate::components::{CollisionGrid, TileChange};
+use std::collections::HashMap;
+
+// Re-plan at least this often (in AI updates) so a path doesn't go stale while the
+// target wanders around inside the same tile
+const MAX_PATH_AGE: u32 = 90;
+
+// After failing to find a path, wait this many AI updates before searching again,
+// so an unreachable target doesn't cost a full search every frame
+const RETRY_DELAY: u32 = 30;
+
+// How close (in pixels) an entity has to get to a waypoint before moving on to the next
+const WAYPOINT_RADIUS: f32 = 6.0;
+
+struct CachedPath {
+    goal: Tile,
+    waypoints: Vec<Tile>, // Smoothed; the first one is where the path started. Empty if there was no path.
+    next: usize,
+    age: u32,
+}
+
+// One planned path per entity, kept until its goal moves to another tile, the map
+// changes or it gets old. Failed searches are kept too, until RETRY_DELAY is up.
+pub struct PathCache {
+    paths: HashMap<usize, CachedPath>, // Entity id -> its path
+    options: PathOptions,
+}
+
+impl PathCache {
+    pub fn new(options: PathOptions) -> Self {
+        PathCache {
+            paths: HashMap::new(),
+            options,
+        }
+    }
+
+    // Where an entity whose feet are at `from` should head next to reach `to`.
+    // None if there's no grid path, in which case callers walk straight at it.
+    pub fn next_waypoint(&mut self, grid: &CollisionGrid, entity: usize, from: (f32, f32), to: (f32, f32)) -> Option<(f32, f32)> {
+        let start = grid.tile_at(from.0, from.1)?;
+        let goal = grid.tile_at(to.0, to.1)?;
+
+        let stale = self.paths.get(&entity).is_none_or(|path| {
+            if path.waypoints.is_empty() {
+                // Whatever the goal; it's likely just as unreachable a tile over
+                path.age >= RETRY_DELAY
+            } else {
+                path.goal != goal || path.age >= MAX_PATH_AGE || path.next >= path.waypoints.len()
+            }
+        });
+        if stale {
+            let waypoints = find_path(grid, start, goal, &self.options)
+                .map(|path| smooth_path(grid, &path))
+                .unwrap_or_default();
+            // The first waypoint is the tile we're standing on
+            self.paths.insert(entity, CachedPath { goal, waypoints, next: 1, age: 0 });
+        }
+
+        let path = self.paths.get_mut(&entity)?;
+        path.age += 1;
+        if path.waypoints.is_empty() {
+            return None;
+        }
+        while let Some(&(x, y)) = path.waypoints.get(path.next) {
+            // The last leg goes to the exact target rather than its tile's centre
+            let target = if path.next + 1 == path.waypoints.len() { to } else { grid.tile_center(x, y) };
+            let (dx, dy) = (target.0 - from.0, target.1 - from.1);
+            if dx * dx + dy * dy > WAYPOINT_RADIUS * WAYPOINT_RADIUS {
+                return Some(target);
+            }
+            path.next += 1;
+        }
+        Some(to)
+    }
+
+    // A door opening can make a shorter path possible and a wall appearing can block
+    // one, so any tile change sends every entity back to planning
+    pub fn invalidate(&mut self, changes: &[TileChange]) {
+        if !changes.is_empty() {
+            self.paths.clear();
+        }
+    }
+
+    // Drop the path of an entity that's been removed
+    pub fn forget(&mut self, entity: usize) {
+        self.paths.remove(&entity);
+    }
+
+    pub fn clear(&mut self) {
+        self.paths.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::components::TileId;
+    use crate::navigation::grid;
+
+    fn centre(x: usize, y: usize) -> (f32, f32) {
+        (x as f32 * 32.0 + 16.0, y as f32 * 32.0 + 16.0)
+    }
+
+    #[test]
+    fn leads_around_walls_to_the_target() {
+        let grid = grid(&[
+            ".....",
+            ".###.",
+            ".....",
+        ]);
+        let mut paths = PathCache::new(PathOptions::default());
+        let target = centre(2, 0);
+
+        // Heads for an end of the wall first, not straight at the target
+        let waypoint = paths.next_waypoint(&grid, 1, centre(2, 2), target).unwrap();
+        assert!(waypoint == centre(0, 2) || waypoint == centre(4, 2), "{:?}", waypoint);
+
+        // The last leg ends at the exact target, not its tile's centre
+        let close = (target.0 - 40.0, target.1);
+        assert_eq!(paths.next_waypoint(&grid, 2, close, (target.0 + 3.0, target.1)), Some((target.0 + 3.0, target.1)));
+    }
+
+    #[test]
+    fn failed_searches_wait_before_retrying() {
+        let closed = grid(&[
+            "..#..",
+            "..#..",
+        ]);
+        let open = grid(&[
+            ".....",
+            ".....",
+        ]);
+        let mut paths = PathCache::new(PathOptions::default());
+        assert_eq!(paths.next_waypoint(&closed, 1, centre(0, 0), centre(4, 0)), None);
+
+        // Still remembered as unreachable, even for a nearby goal
+        for _ in 1..RETRY_DELAY {
+            assert_eq!(paths.next_waypoint(&open, 1, centre(0, 0), centre(4, 1)), None);
+        }
+        assert!(paths.next_waypoint(&open, 1, centre(0, 0), centre(4, 1)).is_some());
+    }
+
+    #[test]
+    fn forgotten_and_invalidated_paths_are_planned_again() {
+        let closed = grid(&[
+            "..#..",
+        ]);
+        let open = grid(&[
+            ".....",
+        ]);
+        let mut paths = PathCache::new(PathOptions::default());
+        assert_eq!(paths.next_waypoint(&closed, 1, centre(0, 0), centre(4, 0)), None);
+        paths.forget(1);
+        assert!(paths.next_waypoint(&open, 1, centre(0, 0), centre(4, 0)).is_some());
+
+        assert_eq!(paths.next_waypoint(&closed, 2, centre(0, 0), centre(4, 0)), None);
+        paths.invalidate(&[TileChange { x: 2, y: 0, old: TileId(1), new: TileId(0) }]);
+        assert!(paths.next_waypoint(&open, 2, centre(0, 0), centre(4, 0)).is_some());
+    }
+}
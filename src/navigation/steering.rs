@@ -0,0 +1,266 @@
+use super::{FlowField, PathCache};
+use crate::components::ai::Ai;
+use crate::components::{ActionState, CollisionGrid, Position};
+
+// Steering behaviours. Each one returns the direction an entity wants to move in,
+// with a length from 0 (stay put) to 1 (full speed); they're added up with weights
+// and clamped back to length 1, so separation nudges a seek rather than replacing it.
+pub type Vector = (f32, f32);
+
+// Inside this distance (in pixels) `arrive` slows down
+const SLOWING_RADIUS: f32 = 32.0;
+// `pursue` aims where the target will be in at most this many frames
+const MAX_PREDICTION: f32 = 30.0;
+// Entities closer than this (in pixels) to another with the same target push apart
+const SEPARATION_RADIUS: f32 = 48.0;
+const SEPARATION_WEIGHT: f32 = 0.8;
+// How far ahead (in pixels) to probe for walls
+const LOOK_AHEAD: f32 = 24.0;
+// Directions shorter than this are treated as standing still, so entities don't
+// shuffle on the spot
+const MIN_MOVEMENT: f32 = 0.05;
+
+pub fn length((x, y): Vector) -> f32 {
+    (x * x + y * y).sqrt()
+}
+
+pub fn normalize(vector: Vector) -> Vector {
+    let length = length(vector);
+    if length > 0.0 { (vector.0 / length, vector.1 / length) } else { (0.0, 0.0) }
+}
+
+fn clamp_length(vector: Vector, max: f32) -> Vector {
+    let length = length(vector);
+    if length > max { (vector.0 / length * max, vector.1 / length * max) } else { vector }
+}
+
+fn rotate((x, y): Vector, degrees: f32) -> Vector {
+    let (sin, cos) = degrees.to_radians().sin_cos();
+    (x * cos - y * sin, x * sin + y * cos)
+}
+
+// Full speed towards a point
+pub fn seek(from: Vector, to: Vector) -> Vector {
+    normalize((to.0 - from.0, to.1 - from.1))
+}
+
+// Full speed away from a point
+pub fn flee(from: Vector, threat: Vector) -> Vector {
+    let (x, y) = seek(from, threat);
+    (-x, -y)
+}
+
+// Towards a point, slowing down to stop on it
+pub fn arrive(from: Vector, to: Vector) -> Vector {
+    let offset = (to.0 - from.0, to.1 - from.1);
+    let distance = length(offset);
+    clamp_length(normalize(offset), distance / SLOWING_RADIUS)
+}
+
+// Towards where a moving target will be by the time we get there. Velocities and
+// speed are in pixels per frame.
+pub fn pursue(from: Vector, target: Vector, target_velocity: Vector, speed: f32) -> Vector {
+    let frames = (length((target.0 - from.0, target.1 - from.1)) / speed.max(0.1)).min(MAX_PREDICTION);
+    seek(from, (target.0 + target_velocity.0 * frames, target.1 + target_velocity.1 * frames))
+}
+
+// Aimless but smooth strolling. The heading drifts with time, and `seed` (e.g. the
+// entity id) keeps wanderers from drifting in step.
+pub fn wander(time: f32, seed: usize) -> Vector {
+    let phase = seed as f32 * 2.399; // Golden angle, to spread seeds out
+    let angle = (time * 0.4 + phase).sin() * std::f32::consts::PI + (time * 0.17 + phase * 1.7).sin() * 2.0;
+    let (sin, cos) = angle.sin_cos();
+    (cos * 0.5, sin * 0.5) // Half speed
+}
+
+// Push away from neighbours inside SEPARATION_RADIUS, harder the closer they are
+pub fn separation(from: Vector, neighbours: impl Iterator<Item = Vector>) -> Vector {
+    let mut push = (0.0, 0.0);
+    for neighbour in neighbours {
+        let offset = (from.0 - neighbour.0, from.1 - neighbour.1);
+        let distance = length(offset);
+        if distance >= SEPARATION_RADIUS {
+            continue;
+        }
+        // Entities standing on the same pixel still need to split up somehow
+        let away = if distance > 0.0 { normalize(offset) } else { (1.0, 0.0) };
+        let strength = 1.0 - distance / SEPARATION_RADIUS;
+        push = (push.0 + away.0 * strength, push.1 + away.1 * strength);
+    }
+    clamp_length(push, 1.0)
+}
+
+// Turn a direction that would run into a wall to the nearest one that doesn't,
+// trying either side in widening steps
+pub fn avoid_tiles(grid: &CollisionGrid, from: Vector, direction: Vector) -> Vector {
+    let probe = |direction: Vector| {
+        let ahead = normalize(direction);
+        !grid.is_solid_at(from.0 + ahead.0 * LOOK_AHEAD, from.1 + ahead.1 * LOOK_AHEAD)
+    };
+    if length(direction) == 0.0 || probe(direction) {
+        return direction;
+    }
+    for degrees in [30.0, -30.0, 60.0, -60.0, 90.0, -90.0] {
+        let turned = rotate(direction, degrees);
+        if probe(turned) {
+            return turned;
+        }
+    }
+    // Boxed in; movement slides along whatever it hits
+    direction
+}
+
+// Turns where AI entities want to go into analog movement, keeping them off walls
+// and apart from others after the same target
+pub struct Steering<'s> {
+    positions: &'s [Position],
+    targets: Vec<Option<usize>>, // Entities sharing a target keep apart
+    collision: Option<&'s CollisionGrid>,
+}
+
+impl<'s> Steering<'s> {
+    pub fn new(positions: &'s [Position], ais: &[Ai], collision: Option<&'s CollisionGrid>) -> Self {
+        Steering {
+            positions,
+            targets: ais.iter().map(Ai::target).collect(),
+            collision,
+        }
+    }
+
+    pub fn steer(&self, index: usize, desired: Vector) -> ActionState {
+        let feet = self.positions[index].feet();
+        let target = self.targets[index];
+        let allies = self.positions.iter().enumerate()
+            .filter(|&(other, _)| other != index && target.is_some() && self.targets[other] == target)
+            .map(|(_, position)| position.feet());
+        let apart = separation(feet, allies);
+
+        let mut direction = clamp_length((desired.0 + apart.0 * SEPARATION_WEIGHT, desired.1 + apart.1 * SEPARATION_WEIGHT), 1.0);
+        if let Some(grid) = self.collision {
+            direction = avoid_tiles(grid, feet, direction);
+        }
+        if length(direction) < MIN_MOVEMENT {
+            return ActionState::None;
+        }
+        ActionState::Steering { x: direction.0, y: direction.1 }
+    }
+
+    // Head for a point (in feet coordinates) around walls, slowing to a stop on it
+    pub fn travel(&self, index: usize, entity: usize, goal: Vector, paths: &mut PathCache) -> ActionState {
+        let feet = self.positions[index].feet();
+        let waypoint = self.collision
+            .and_then(|grid| paths.next_waypoint(grid, entity, feet, goal))
+            .unwrap_or(goal);
+        let desired = if waypoint == goal { arrive(feet, goal) } else { seek(feet, waypoint) };
+        self.steer(index, desired)
+    }
+
+    // Run after a moving target: straight at where it's heading when nothing is in
+    // the way, otherwise along the shared flow field (if there is one) or a path
+    pub fn chase(&self, index: usize, entity: usize, goal: Vector, goal_velocity: Vector, field: Option<&FlowField>, paths: &mut PathCache) -> ActionState {
+        let position = &self.positions[index];
+        let feet = position.feet();
+        let grid = match self.collision {
+            Some(grid) if !grid.has_line_of_sight(feet, goal) => grid,
+            _ => return self.steer(index, pursue(feet, goal, goal_velocity, position.speed)),
+        };
+
+        let waypoint = Self::field_waypoint(grid, feet, goal, field)
+            .or_else(|| paths.next_waypoint(grid, entity, feet, goal))
+            .unwrap_or(goal);
+        self.steer(index, seek(feet, waypoint))
+    }
+
+    // Centre of the neighbouring tile the flow field points at. None when there's no
+    // field or it doesn't reach this tile.
+    fn field_waypoint(grid: &CollisionGrid, feet: Vector, goal: Vector, field: Option<&FlowField>) -> Option<Vector> {
+        let tile = grid.tile_at(feet.0, feet.1)?;
+        let field = field?;
+        field.distance(tile)?;
+        match field.next_tile(tile) {
+            Some((x, y)) => Some(grid.tile_center(x, y)),
+            None => Some(goal), // Already on the goal tile
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::navigation::grid;
+
+    fn close(a: Vector, b: Vector) -> bool {
+        (a.0 - b.0).abs() < 1e-4 && (a.1 - b.1).abs() < 1e-4
+    }
+
+    #[test]
+    fn arrive_slows_down_to_stop_on_the_goal() {
+        assert!(close(arrive((0.0, 0.0), (100.0, 0.0)), (1.0, 0.0)));
+        assert!(close(arrive((0.0, 0.0), (0.0, -16.0)), (0.0, -0.5)));
+        assert!(close(arrive((0.0, 0.0), (8.0, 0.0)), (0.25, 0.0)));
+        assert_eq!(arrive((5.0, 5.0), (5.0, 5.0)), (0.0, 0.0));
+    }
+
+    #[test]
+    fn pursue_leads_a_moving_target() {
+        // Standing still is just a seek
+        assert!(close(pursue((0.0, 0.0), (100.0, 0.0), (0.0, 0.0), 2.0), (1.0, 0.0)));
+
+        // 50 frames away at 2px a frame, but it only looks 30 frames ahead: (100, 90)
+        let ahead = pursue((0.0, 0.0), (100.0, 0.0), (0.0, 3.0), 2.0);
+        assert!(close(ahead, normalize((100.0, 90.0))));
+
+        // Closer targets get less lead: 10 frames, so (20, 30)
+        let near = pursue((0.0, 0.0), (20.0, 0.0), (0.0, 3.0), 2.0);
+        assert!(close(near, normalize((20.0, 30.0))));
+
+        // Something that can't move still aims somewhere sensible
+        let stuck = pursue((0.0, 0.0), (100.0, 0.0), (0.0, 1.0), 0.0);
+        assert!(close(stuck, normalize((100.0, 30.0))));
+    }
+
+    #[test]
+    fn separation_pushes_harder_the_closer_neighbours_are() {
+        assert_eq!(separation((0.0, 0.0), std::iter::empty()), (0.0, 0.0));
+        assert_eq!(separation((0.0, 0.0), [(48.0, 0.0), (0.0, 60.0)].into_iter()), (0.0, 0.0));
+        assert!(close(separation((0.0, 0.0), [(36.0, 0.0)].into_iter()), (-0.25, 0.0)));
+        assert!(close(separation((0.0, 0.0), [(0.0, -12.0)].into_iter()), (0.0, 0.75)));
+
+        // Pushes from either side cancel out, and a crowd is capped at full speed
+        assert!(close(separation((0.0, 0.0), [(12.0, 0.0), (-12.0, 0.0)].into_iter()), (0.0, 0.0)));
+        assert!(close(separation((0.0, 0.0), [(6.0, 0.0), (6.0, 0.0)].into_iter()), (-1.0, 0.0)));
+    }
+
+    #[test]
+    fn separation_splits_entities_on_the_same_pixel() {
+        assert_eq!(separation((10.0, 10.0), [(10.0, 10.0)].into_iter()), (1.0, 0.0));
+        assert!(close(separation((10.0, 10.0), [(10.0, 10.0), (10.0, 34.0)].into_iter()), normalize((1.0, -0.5))));
+    }
+
+    #[test]
+    fn avoid_tiles_turns_away_from_walls() {
+        let grid = grid(&[
+            "...",
+            "..#",
+            "...",
+        ]);
+        let centre = (48.0, 48.0);
+
+        // Nothing ahead, or not moving at all
+        assert_eq!(avoid_tiles(&grid, centre, (-1.0, 0.0)), (-1.0, 0.0));
+        assert_eq!(avoid_tiles(&grid, centre, (0.0, 0.0)), (0.0, 0.0));
+
+        // 30 degrees either way still clips the wall, 60 clears it
+        assert!(close(avoid_tiles(&grid, centre, (1.0, 0.0)), rotate((1.0, 0.0), 60.0)));
+    }
+
+    #[test]
+    fn avoid_tiles_keeps_going_when_boxed_in() {
+        let grid = grid(&[
+            "###",
+            "#.#",
+            "###",
+        ]);
+        assert_eq!(avoid_tiles(&grid, (48.0, 48.0), (1.0, 0.0)), (1.0, 0.0));
+    }
+}
@@ -0,0 +1,103 @@
+use crate::procgen::grid::Grid;
+use crate::procgen::rng::Rng;
+
+#[derive(Clone, Copy, Debug)]
+pub struct Room {
+    pub x: usize,
+    pub y: usize,
+    pub width: usize,
+    pub height: usize,
+}
+
+impl Room {
+    pub fn center(&self) -> (usize, usize) {
+        (self.x + self.width / 2, self.y + self.height / 2)
+    }
+}
+
+const MIN_LEAF_SIZE: usize = 8;
+const MIN_ROOM_SIZE: usize = 4;
+
+// Binary space partition: split the map into leaves, put a room in each leaf
+// and join sibling subtrees with L-shaped corridors. Every split is joined,
+// so the result is connected by construction.
+pub fn generate(grid: &mut Grid, rng: &mut Rng) -> Vec<Room> {
+    let mut rooms = Vec::new();
+    let area = Room { x: 1, y: 1, width: grid.width.saturating_sub(2), height: grid.height.saturating_sub(2) };
+    split(grid, rng, area, &mut rooms);
+    rooms
+}
+
+// Returns a representative point inside the subtree so the parent can connect to it
+fn split(grid: &mut Grid, rng: &mut Rng, area: Room, rooms: &mut Vec<Room>) -> (usize, usize) {
+    let can_split_h = area.height >= MIN_LEAF_SIZE * 2;
+    let can_split_v = area.width >= MIN_LEAF_SIZE * 2;
+
+    if !can_split_h && !can_split_v {
+        return carve_room(grid, rng, area, rooms);
+    }
+
+    // Prefer cutting across the longer side so leaves stay roughly square
+    let split_vertically = if can_split_h && can_split_v {
+        if area.width > area.height { true } else if area.height > area.width { false } else { rng.chance(0.5) }
+    } else {
+        can_split_v
+    };
+
+    let (first, second) = if split_vertically {
+        let cut = rng.range(MIN_LEAF_SIZE, area.width - MIN_LEAF_SIZE + 1);
+        (
+            Room { width: cut, ..area },
+            Room { x: area.x + cut, width: area.width - cut, ..area },
+        )
+    } else {
+        let cut = rng.range(MIN_LEAF_SIZE, area.height - MIN_LEAF_SIZE + 1);
+        (
+            Room { height: cut, ..area },
+            Room { y: area.y + cut, height: area.height - cut, ..area },
+        )
+    };
+
+    let a = split(grid, rng, first, rooms);
+    let b = split(grid, rng, second, rooms);
+    carve_corridor(grid, rng, a, b);
+    if rng.chance(0.5) { a } else { b }
+}
+
+fn carve_room(grid: &mut Grid, rng: &mut Rng, leaf: Room, rooms: &mut Vec<Room>) -> (usize, usize) {
+    // Leave a one tile margin inside the leaf so neighbouring rooms don't merge
+    let max_width = leaf.width.saturating_sub(2).max(MIN_ROOM_SIZE);
+    let max_height = leaf.height.saturating_sub(2).max(MIN_ROOM_SIZE);
+    let width = rng.range(MIN_ROOM_SIZE, max_width + 1);
+    let height = rng.range(MIN_ROOM_SIZE, max_height + 1);
+    let x = leaf.x + 1 + rng.range(0, leaf.width.saturating_sub(width + 1));
+    let y = leaf.y + 1 + rng.range(0, leaf.height.saturating_sub(height + 1));
+
+    let room = Room { x, y, width, height };
+    for ty in room.y..room.y + room.height {
+        for tx in room.x..room.x + room.width {
+            grid.carve(tx, ty);
+        }
+    }
+
+    rooms.push(room);
+    room.center()
+}
+
+pub fn carve_corridor(grid: &mut Grid, rng: &mut Rng, from: (usize, usize), to: (usize, usize)) {
+    let (x1, y1) = from;
+    let (x2, y2) = to;
+
+    // Randomly choose whether the corridor bends horizontally or vertically first
+    let corner = if rng.chance(0.5) { (x2, y1) } else { (x1, y2) };
+    carve_line(grid, (x1, y1), corner);
+    carve_line(grid, corner, (x2, y2));
+}
+
+fn carve_line(grid: &mut Grid, from: (usize, usize), to: (usize, usize)) {
+    for x in from.0.min(to.0)..=from.0.max(to.0) {
+        for y in from.1.min(to.1)..=from.1.max(to.1) {
+            grid.carve(x, y);
+        }
+    }
+}
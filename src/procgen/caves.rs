@@ -0,0 +1,32 @@
+use crate::procgen::grid::Grid;
+use crate::procgen::rng::Rng;
+
+const INITIAL_FLOOR_CHANCE: f32 = 0.55;
+const SMOOTHING_PASSES: usize = 5;
+
+// Cellular automata caves: random noise smoothed with the classic 4-5 rule
+// (a cell becomes wall with 5+ wall neighbours, floor with 3 or fewer).
+// Disconnected pockets are filled in afterwards by the caller.
+pub fn generate(grid: &mut Grid, rng: &mut Rng) {
+    for y in 1..grid.height.saturating_sub(1) {
+        for x in 1..grid.width.saturating_sub(1) {
+            if rng.chance(INITIAL_FLOOR_CHANCE) {
+                grid.carve(x, y);
+            }
+        }
+    }
+
+    for _ in 0..SMOOTHING_PASSES {
+        let previous = grid.clone();
+        for y in 1..grid.height.saturating_sub(1) {
+            for x in 1..grid.width.saturating_sub(1) {
+                let walls = previous.wall_neighbours(x, y);
+                if walls >= 5 {
+                    grid.set(x, y, false);
+                } else if walls <= 3 {
+                    grid.carve(x, y);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,35 @@
+use crate::procgen::grid::Grid;
+use crate::procgen::rng::Rng;
+
+const TARGET_FLOOR_RATIO: f32 = 0.4;
+
+// Drunkard's walk tunnels: a single walker starting in the middle carves
+// as it stumbles around until enough of the map is open. A single walker
+// only ever carves next to existing floor, so the result is always connected.
+pub fn generate(grid: &mut Grid, rng: &mut Rng) {
+    let interior = grid.width.saturating_sub(2) * grid.height.saturating_sub(2);
+    let target = (interior as f32 * TARGET_FLOOR_RATIO) as usize;
+    // Hard cap so a degenerate map size can't spin forever
+    let max_steps = interior * 50;
+
+    let mut x = grid.width / 2;
+    let mut y = grid.height / 2;
+    grid.carve(x, y);
+
+    let mut floor = grid.floor_count();
+    let mut steps = 0;
+    while floor < target && steps < max_steps {
+        match rng.range(0, 4) {
+            0 if x > 1 => x -= 1,
+            1 if x + 2 < grid.width => x += 1,
+            2 if y > 1 => y -= 1,
+            3 if y + 2 < grid.height => y += 1,
+            _ => {}
+        }
+        if !grid.is_floor(x, y) {
+            grid.carve(x, y);
+            floor += 1;
+        }
+        steps += 1;
+    }
+}
@@ -0,0 +1,145 @@
+use std::collections::VecDeque;
+
+// Boolean carve grid used by the generators: true = floor, false = wall.
+// Kept separate from Tilemap so the algorithms don't care about tile IDs.
+#[derive(Clone)]
+pub struct Grid {
+    pub width: usize,
+    pub height: usize,
+    cells: Vec<bool>,
+}
+
+impl Grid {
+    pub fn new(width: usize, height: usize) -> Self {
+        Grid {
+            width,
+            height,
+            cells: vec![false; width * height],
+        }
+    }
+
+    pub fn in_bounds(&self, x: i32, y: i32) -> bool {
+        x >= 0 && y >= 0 && (x as usize) < self.width && (y as usize) < self.height
+    }
+
+    pub fn is_floor(&self, x: usize, y: usize) -> bool {
+        x < self.width && y < self.height && self.cells[y * self.width + x]
+    }
+
+    pub fn set(&mut self, x: usize, y: usize, floor: bool) {
+        if x < self.width && y < self.height {
+            self.cells[y * self.width + x] = floor;
+        }
+    }
+
+    // Carve floor, but never touch the outer border so maps stay enclosed
+    pub fn carve(&mut self, x: usize, y: usize) {
+        if x > 0 && y > 0 && x + 1 < self.width && y + 1 < self.height {
+            self.set(x, y, true);
+        }
+    }
+
+    pub fn floor_count(&self) -> usize {
+        self.cells.iter().filter(|&&floor| floor).count()
+    }
+
+    pub fn floor_cells(&self) -> Vec<(usize, usize)> {
+        let mut cells = Vec::new();
+        for y in 0..self.height {
+            for x in 0..self.width {
+                if self.is_floor(x, y) {
+                    cells.push((x, y));
+                }
+            }
+        }
+        cells
+    }
+
+    // Count walls in the 8-neighbourhood (out of bounds counts as wall)
+    pub fn wall_neighbours(&self, x: usize, y: usize) -> usize {
+        let mut count = 0;
+        for dy in -1i32..=1 {
+            for dx in -1i32..=1 {
+                if dx == 0 && dy == 0 {
+                    continue;
+                }
+                let nx = x as i32 + dx;
+                let ny = y as i32 + dy;
+                if !self.in_bounds(nx, ny) || !self.is_floor(nx as usize, ny as usize) {
+                    count += 1;
+                }
+            }
+        }
+        count
+    }
+
+    // Breadth-first distances (4-way) from a start cell; None for unreachable cells
+    pub fn distances_from(&self, start: (usize, usize)) -> Vec<Option<usize>> {
+        let mut distances = vec![None; self.width * self.height];
+        if !self.is_floor(start.0, start.1) {
+            return distances;
+        }
+
+        let mut queue = VecDeque::new();
+        distances[start.1 * self.width + start.0] = Some(0);
+        queue.push_back(start);
+
+        while let Some((x, y)) = queue.pop_front() {
+            let distance = distances[y * self.width + x].unwrap_or(0);
+            for (dx, dy) in [(1i32, 0i32), (-1, 0), (0, 1), (0, -1)] {
+                let nx = x as i32 + dx;
+                let ny = y as i32 + dy;
+                if !self.in_bounds(nx, ny) {
+                    continue;
+                }
+                let (nx, ny) = (nx as usize, ny as usize);
+                let index = ny * self.width + nx;
+                if self.is_floor(nx, ny) && distances[index].is_none() {
+                    distances[index] = Some(distance + 1);
+                    queue.push_back((nx, ny));
+                }
+            }
+        }
+
+        distances
+    }
+
+    // Label 4-connected floor regions; returns each region's cells, largest first
+    pub fn regions(&self) -> Vec<Vec<(usize, usize)>> {
+        let mut visited = vec![false; self.width * self.height];
+        let mut regions = Vec::new();
+
+        for (x, y) in self.floor_cells() {
+            if visited[y * self.width + x] {
+                continue;
+            }
+
+            let distances = self.distances_from((x, y));
+            let mut region = Vec::new();
+            for (index, distance) in distances.iter().enumerate() {
+                if distance.is_some() {
+                    visited[index] = true;
+                    region.push((index % self.width, index / self.width));
+                }
+            }
+            regions.push(region);
+        }
+
+        regions.sort_by_key(|region| std::cmp::Reverse(region.len()));
+        regions
+    }
+
+    // Fill in every floor region except the largest, guaranteeing a single connected area
+    pub fn keep_largest_region(&mut self) {
+        let regions = self.regions();
+        for region in regions.iter().skip(1) {
+            for &(x, y) in region {
+                self.set(x, y, false);
+            }
+        }
+    }
+
+    pub fn is_connected(&self) -> bool {
+        self.regions().len() == 1
+    }
+}
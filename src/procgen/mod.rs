@@ -0,0 +1,373 @@
+// Seeded procedural level generation. Every generator carves a boolean Grid,
+// which is then checked for connectivity, turned into a Tilemap and populated
+// with a player start, enemies from entities.ron and exits.
+pub mod rng;
+pub mod grid;
+pub mod bsp;
+pub mod caves;
+pub mod drunkard;
+
+use crate::components::{Tilemap, TileId};
+use crate::components::position::{FEET_OFFSET_X, FEET_OFFSET_Y};
+use crate::entity_definitions::{EntityDefinitions, EntityType};
+use crate::level_definitions::{LevelDefinition, SpawnDefinition, TriggerDefinition};
+use self::grid::Grid;
+use std::collections::HashMap;
+use self::rng::Rng;
+
+pub const FLOOR_TILE: TileId = TileId(1);
+pub const WALL_TILE: TileId = TileId(2);
+
+// Give up after this many reseeded attempts to get a usable map
+const MAX_ATTEMPTS: u64 = 16;
+// A map with less open space than this is considered a failed attempt
+const MIN_FLOOR_RATIO: f32 = 0.2;
+// Enemies never spawn closer to the player start than this many tiles (path distance)
+const MIN_ENEMY_DISTANCE: usize = 8;
+// Where the generate command's exits lead unless told otherwise
+const DEFAULT_EXIT_LEVEL: &str = "assets/levels/level1.ron";
+const DEFAULT_EXIT_ENTRY: &str = "from_cellar";
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum GeneratorKind {
+    Bsp,
+    Caves,
+    DrunkardsWalk,
+}
+
+impl GeneratorKind {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "bsp" | "dungeon" => Some(GeneratorKind::Bsp),
+            "caves" | "cellular" => Some(GeneratorKind::Caves),
+            "drunkard" | "tunnels" => Some(GeneratorKind::DrunkardsWalk),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct GeneratorConfig {
+    pub kind: GeneratorKind,
+    pub seed: u64,
+    pub width: usize,
+    pub height: usize,
+    pub tile_size: u32,
+    pub enemy_count: usize,
+    pub exit_count: usize,
+}
+
+impl GeneratorConfig {
+    pub fn new(kind: GeneratorKind, seed: u64) -> Self {
+        GeneratorConfig {
+            kind,
+            seed,
+            width: 64,
+            height: 48,
+            tile_size: 32,
+            enemy_count: 6,
+            exit_count: 1,
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct SpawnPoint {
+    pub entity_name: String,
+    pub x: f32, // Entity position in world pixels
+    pub y: f32,
+}
+
+pub struct GeneratedLevel<'a> {
+    pub tilemap: Tilemap<'a>,
+    pub player_start: (f32, f32),
+    pub spawns: Vec<SpawnPoint>,
+    pub exits: Vec<(usize, usize)>, // Tile coordinates
+    pub seed: u64,                  // Seed of the attempt that succeeded
+}
+
+pub fn generate<'a>(config: &GeneratorConfig, definitions: &EntityDefinitions) -> Result<GeneratedLevel<'a>, String> {
+    if config.width < 16 || config.height < 16 {
+        return Err(format!("Map too small to generate: {}x{}", config.width, config.height));
+    }
+
+    // Each retry derives a new seed from the original, so the output stays reproducible
+    for attempt in 0..MAX_ATTEMPTS {
+        let seed = config.seed.wrapping_add(attempt.wrapping_mul(0x9E37_79B9));
+        let mut rng = Rng::new(seed);
+        let mut grid = Grid::new(config.width, config.height);
+
+        let player_tile = match config.kind {
+            GeneratorKind::Bsp => {
+                let rooms = bsp::generate(&mut grid, &mut rng);
+                rooms.first().map(|room| room.center())
+            },
+            GeneratorKind::Caves => {
+                caves::generate(&mut grid, &mut rng);
+                grid.keep_largest_region();
+                None
+            },
+            GeneratorKind::DrunkardsWalk => {
+                drunkard::generate(&mut grid, &mut rng);
+                None
+            },
+        };
+
+        let interior = (config.width - 2) * (config.height - 2);
+        if (grid.floor_count() as f32) < interior as f32 * MIN_FLOOR_RATIO || !grid.is_connected() {
+            continue;
+        }
+
+        let player_tile = match player_tile {
+            Some(tile) if grid.is_floor(tile.0, tile.1) => tile,
+            _ => match rng.pick(&grid.floor_cells()) {
+                Some(&tile) => tile,
+                None => continue,
+            },
+        };
+
+        return Ok(populate(config, definitions, &grid, &mut rng, player_tile, seed));
+    }
+
+    Err(format!("Failed to generate a connected {:?} map from seed {} after {} attempts",
+                config.kind, config.seed, MAX_ATTEMPTS))
+}
+
+fn populate<'a>(
+    config: &GeneratorConfig,
+    definitions: &EntityDefinitions,
+    grid: &Grid,
+    rng: &mut Rng,
+    player_tile: (usize, usize),
+    seed: u64,
+) -> GeneratedLevel<'a> {
+    let mut tilemap = Tilemap::new(config.width, config.height, config.tile_size);
+    for y in 0..config.height {
+        for x in 0..config.width {
+            tilemap.set_tile(x, y, if grid.is_floor(x, y) { FLOOR_TILE } else { WALL_TILE });
+        }
+    }
+
+    let distances = grid.distances_from(player_tile);
+    let mut reachable: Vec<((usize, usize), usize)> = distances.iter()
+        .enumerate()
+        .filter_map(|(index, distance)| distance.map(|d| ((index % grid.width, index / grid.width), d)))
+        .collect();
+
+    // Exits go on the cells furthest from the start so the whole level gets crossed
+    reachable.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+    let exits: Vec<(usize, usize)> = reachable.iter()
+        .take(config.exit_count)
+        .map(|(tile, _)| *tile)
+        .collect();
+
+    // Sort the names: HashMap order isn't stable and would break seed reproducibility
+    let mut enemy_names: Vec<&String> = definitions.entities.iter()
+        .filter(|(_, definition)| definition.entity_type == Some(EntityType::Enemy))
+        .map(|(name, _)| name)
+        .collect();
+    enemy_names.sort();
+
+    let mut candidates: Vec<(usize, usize)> = reachable.iter()
+        .filter(|(tile, distance)| *distance >= MIN_ENEMY_DISTANCE && !exits.contains(tile))
+        .map(|(tile, _)| *tile)
+        .collect();
+    candidates.sort();
+
+    let mut spawns = Vec::new();
+    for _ in 0..config.enemy_count {
+        if candidates.is_empty() {
+            break;
+        }
+        let name = match rng.pick(&enemy_names) {
+            Some(name) => (*name).clone(),
+            None => break,
+        };
+        let tile = candidates.swap_remove(rng.range(0, candidates.len()));
+        let (x, y) = spawn_position(tile, config.tile_size);
+        spawns.push(SpawnPoint { entity_name: name, x, y });
+    }
+
+    GeneratedLevel {
+        tilemap,
+        player_start: spawn_position(player_tile, config.tile_size),
+        spawns,
+        exits,
+        seed,
+    }
+}
+
+impl<'a> GeneratedLevel<'a> {
+    // Describe the generated level in the regular level format so it can be saved
+    // and loaded like a hand-authored one. Every exit tile becomes a trigger sending
+    // the player to `exit_entry` in `exit_level`.
+    pub fn to_level_definition(&self, tilemap_path: &str, tileset_path: &str, exit_level: &str, exit_entry: &str) -> LevelDefinition {
+        let mut spawns = vec![SpawnDefinition::new("player", self.player_start.0, self.player_start.1)];
+        spawns.extend(self.spawns.iter().map(|spawn| SpawnDefinition::new(&spawn.entity_name, spawn.x, spawn.y)));
+        let tile_size = self.tilemap.tile_size as f32;
+        let triggers = self.exits.iter()
+            .map(|&(x, y)| TriggerDefinition {
+                x: x as f32 * tile_size,
+                y: y as f32 * tile_size,
+                width: tile_size,
+                height: tile_size,
+                target_level: exit_level.to_string(),
+                entry: exit_entry.to_string(),
+            })
+            .collect();
+        
+        LevelDefinition {
+            tilemap: tilemap_path.to_string(),
+            world: None,
+            tileset: tileset_path.to_string(),
+            tile_size: self.tilemap.tile_size,
+            spawns,
+            triggers,
+            entries: HashMap::new(),
+            pickups: Vec::new(),
+        }
+    }
+}
+
+// Entity position that puts its feet in the middle of the given tile
+fn spawn_position(tile: (usize, usize), tile_size: u32) -> (f32, f32) {
+    let half = tile_size as f32 / 2.0;
+    (
+        tile.0 as f32 * tile_size as f32 + half - FEET_OFFSET_X,
+        tile.1 as f32 * tile_size as f32 + half - FEET_OFFSET_Y,
+    )
+}
+
+// `generate <bsp|caves|drunkard> <seed> <output.ron> [<exit level> <entry>]`
+// Writes the level file plus its tile layer next to it (`<output>.csv`). Exits lead
+// to the given entry of another level, by default back to the first level.
+pub fn run_cli(args: &[String]) -> Result<(), String> {
+    let usage = "Usage: generate <bsp|caves|drunkard> <seed> <output.ron> [<exit level> <entry>]";
+    let (kind, seed, output, exit_level, exit_entry) = match args {
+        [kind, seed, output] => (kind, seed, output, DEFAULT_EXIT_LEVEL, DEFAULT_EXIT_ENTRY),
+        [kind, seed, output, exit_level, exit_entry] => (kind, seed, output, exit_level.as_str(), exit_entry.as_str()),
+        _ => return Err(usage.to_string()),
+    };
+
+    let kind = GeneratorKind::from_name(kind)
+        .ok_or_else(|| format!("Unknown generator '{}'. {}", kind, usage))?;
+    let seed = seed.parse::<u64>()
+        .map_err(|e| format!("Invalid seed '{}': {}", seed, e))?;
+
+    let definitions = EntityDefinitions::load("assets/entities.ron")?;
+    let level = generate(&GeneratorConfig::new(kind, seed), &definitions)?;
+
+    let tilemap_path = format!("{}.csv", output.trim_end_matches(".ron"));
+    level.tilemap.save_to_file(&tilemap_path)?;
+    level.to_level_definition(&tilemap_path, "assets/tilesets/Texture/TX Tileset Grass.png", exit_level, exit_entry)
+        .save(output)?;
+
+    println!("Generated {:?} level from seed {} -> {}", kind, level.seed, output);
+    println!("Player start: ({}, {})", level.player_start.0, level.player_start.1);
+    for spawn in &level.spawns {
+        println!("Spawn {} at ({}, {})", spawn.entity_name, spawn.x, spawn.y);
+    }
+    for exit in &level.exits {
+        println!("Exit at tile ({}, {}) -> {} ({})", exit.0, exit.1, exit_level, exit_entry);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const KINDS: [GeneratorKind; 3] = [GeneratorKind::Bsp, GeneratorKind::Caves, GeneratorKind::DrunkardsWalk];
+
+    fn definitions() -> EntityDefinitions {
+        EntityDefinitions::parse(r#"(
+            entities: {
+                "player": (entity_type: Player),
+                "goblin": (entity_type: Enemy),
+                "orc": (entity_type: Enemy),
+            },
+        )"#).unwrap()
+    }
+
+    fn tiles(level: &GeneratedLevel) -> Vec<u32> {
+        let map = &level.tilemap;
+        (0..map.height).flat_map(|y| (0..map.width).map(move |x| map.get_tile(x, y).unwrap().0)).collect()
+    }
+
+    // The generator's grid, read back from the tilemap it produced
+    fn floor_grid(level: &GeneratedLevel) -> Grid {
+        let map = &level.tilemap;
+        let mut grid = Grid::new(map.width, map.height);
+        for y in 0..map.height {
+            for x in 0..map.width {
+                grid.set(x, y, map.get_tile(x, y) == Some(&FLOOR_TILE));
+            }
+        }
+        grid
+    }
+
+    // Tile under an entity's feet (inverse of spawn_position)
+    fn feet_tile((x, y): (f32, f32), tile_size: u32) -> (usize, usize) {
+        (((x + FEET_OFFSET_X) / tile_size as f32) as usize, ((y + FEET_OFFSET_Y) / tile_size as f32) as usize)
+    }
+
+    #[test]
+    fn the_same_seed_gives_the_same_level() {
+        let definitions = definitions();
+        for kind in KINDS {
+            let a = generate(&GeneratorConfig::new(kind, 42), &definitions).unwrap();
+            let b = generate(&GeneratorConfig::new(kind, 42), &definitions).unwrap();
+            assert_eq!(tiles(&a), tiles(&b), "{:?}", kind);
+            assert_eq!(a.player_start, b.player_start, "{:?}", kind);
+            assert_eq!(a.exits, b.exits, "{:?}", kind);
+            let spawns = |level: &GeneratedLevel| level.spawns.iter()
+                .map(|spawn| (spawn.entity_name.clone(), spawn.x, spawn.y))
+                .collect::<Vec<_>>();
+            assert_eq!(spawns(&a), spawns(&b), "{:?}", kind);
+        }
+    }
+
+    #[test]
+    fn levels_are_connected() {
+        let definitions = definitions();
+        for kind in KINDS {
+            for seed in 0..8 {
+                let level = generate(&GeneratorConfig::new(kind, seed), &definitions).unwrap();
+                assert!(floor_grid(&level).is_connected(), "{:?} seed {}", kind, seed);
+            }
+        }
+    }
+
+    #[test]
+    fn enemies_keep_their_distance_from_the_player() {
+        let definitions = definitions();
+        for kind in KINDS {
+            for seed in 0..8 {
+                let config = GeneratorConfig::new(kind, seed);
+                let level = generate(&config, &definitions).unwrap();
+                let grid = floor_grid(&level);
+                let distances = grid.distances_from(feet_tile(level.player_start, config.tile_size));
+                assert!(!level.spawns.is_empty(), "{:?} seed {}", kind, seed);
+                for spawn in &level.spawns {
+                    assert_ne!(spawn.entity_name, "player");
+                    let (x, y) = feet_tile((spawn.x, spawn.y), config.tile_size);
+                    let distance = distances[y * grid.width + x].expect("enemy spawned out of reach");
+                    assert!(distance >= MIN_ENEMY_DISTANCE, "{:?} seed {}: enemy {} tiles away", kind, seed, distance);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn small_maps_are_rejected() {
+        let definitions = definitions();
+        for kind in KINDS {
+            let mut config = GeneratorConfig::new(kind, 1);
+            config.width = 15;
+            assert!(generate(&config, &definitions).is_err(), "{:?}", kind);
+            config.width = 16;
+            config.height = 8;
+            assert!(generate(&config, &definitions).is_err(), "{:?}", kind);
+        }
+    }
+}
@@ -0,0 +1,811 @@
+// Reading saves written by older builds. Each past SaveGame layout that can't be
+// parsed as the current one keeps a frozen copy here (a `v1` module with its own
+// structs) plus a step that converts it to the next version; `upgrade` decodes a
+// file with the layout it was written in and runs the steps up to SAVE_VERSION.
+//
+// Additive changes don't need a new version for RON saves (give the new field
+// #[serde(default)]), but binary saves aren't self-describing, so any change to
+// SaveGame or a saved component does.
+use super::{decode_current, SaveFormat, SaveGame, SAVE_VERSION};
+
+// Oldest version this build can still read
+const OLDEST_SUPPORTED: u32 = 1;
+
+// Rewrites one version's data into the next one's meaning
+type Step = fn(&mut SaveGame);
+
+// Upgrade steps that don't change the layout, only what the data means (e.g. a
+// rescaled field), applied in order to every save written before `from + 1`
+const STEPS: &[(u32, Step)] = &[];
+
+pub(super) fn upgrade(version: u32, bytes: &[u8], format: SaveFormat) -> Result<SaveGame, String> {
+    if version < OLDEST_SUPPORTED {
+        return Err(format!("Save version {} is too old to load (oldest supported: {})", version, OLDEST_SUPPORTED));
+    }
+
+    // When the layout changes, match on `version` here: older versions decode into
+    // their frozen structs and convert, the current one uses decode_current
+    let mut game: SaveGame = match version {
+        1 => v2::upgrade(v1::upgrade(decode_current(bytes, format)?)),
+        2 => v2::upgrade(decode_current(bytes, format)?),
+        _ => decode_current(bytes, format)?,
+    };
+
+    for (from, step) in STEPS {
+        if version <= *from && *from < SAVE_VERSION {
+            step(&mut game);
+        }
+    }
+    Ok(game)
+}
+
+// Version 1: Chase was a single struct variant that always had a target. Every
+// saved shape is copied here as it was then, so later changes to the live
+// components can't break reading these saves; newer versions reuse the ones that
+// haven't changed since.
+mod v1 {
+    use crate::behaviour_tree;
+    use crate::components::{self, ai};
+    use crate::save;
+    use super::v2;
+    use serde::Deserialize;
+    use std::collections::{BTreeMap, HashMap};
+
+    #[derive(Deserialize)]
+    pub struct Position {
+        pub x: f32,
+        pub y: f32,
+        pub facing_right: bool,
+        pub speed: f32,
+    }
+
+    #[derive(Deserialize)]
+    pub struct Health {
+        pub current: u32,
+        pub max: u32,
+        pub damage_queue: Vec<u32>,
+        pub healing_queue: Vec<u32>,
+        pub invulnerability_timer: f32,
+        pub invulnerability_duration: f32,
+        pub regeneration_rate: f32,
+        pub regeneration_accumulator: f32,
+        pub is_dead: bool,
+    }
+
+    #[derive(Deserialize)]
+    pub enum AnimationState {
+        Idle,
+        Walk,
+        Attack,
+        Hurt,
+        Death,
+    }
+
+    #[derive(Deserialize)]
+    pub struct Animation {
+        pub current_frame: usize,
+        pub frame_duration: u32,
+        pub state: AnimationState,
+        pub elapsed_time: u32,
+        pub is_attack_in_progress: bool,
+        pub idle_frames: usize,
+        pub walk_frames: usize,
+        pub attack_frames: usize,
+    }
+
+    #[derive(Deserialize)]
+    pub struct Sprite {
+        pub frame_width: u32,
+        pub frame_height: u32,
+        pub origin: (u32, u32),
+        pub scale: f32,
+    }
+
+    #[derive(Deserialize)]
+    pub enum BlackboardValue {
+        Entity(usize),
+        Point(f32, f32),
+        Points(Vec<(f32, f32)>),
+    }
+
+    #[derive(Deserialize)]
+    pub struct Blackboard {
+        pub values: BTreeMap<String, BlackboardValue>,
+    }
+
+    #[derive(Deserialize)]
+    pub struct NodeMemory {
+        pub index: usize,
+        pub since: Option<f32>,
+    }
+
+    #[derive(Deserialize)]
+    pub struct BehaviourState {
+        pub tree: String,
+        pub blackboard: Blackboard,
+        pub memory: Vec<NodeMemory>,
+        pub time: f32,
+    }
+
+    #[derive(Deserialize)]
+    pub enum ActionState {
+        None,
+        Moving { right: bool, left: bool, up: bool, down: bool },
+        Attacking,
+        Steering { x: f32, y: f32 },
+    }
+
+    #[derive(Deserialize)]
+    pub struct Inventory {
+        pub items: HashMap<String, u32>,
+    }
+
+    #[derive(Deserialize)]
+    pub struct Light {
+        pub radius: f32,
+        pub color: (u8, u8, u8),
+        pub intensity: f32,
+    }
+
+    #[derive(Deserialize)]
+    pub struct Velocity {
+        pub x: f32,
+        pub y: f32,
+    }
+
+    #[derive(Deserialize)]
+    pub struct Damage {
+        pub amount: u32,
+    }
+
+    #[derive(Deserialize)]
+    pub struct TileId(pub u32);
+
+    #[derive(Deserialize)]
+    pub struct SavedTile {
+        pub x: usize,
+        pub y: usize,
+        pub tile: TileId,
+    }
+
+    #[derive(Deserialize)]
+    pub enum AiState {
+        Idle,
+        Patrol { waypoints: Vec<(f32, f32)>, current_waypoint: usize },
+        Chase { target_entity: usize, detection_range: f32, attack_range: f32 },
+        Behaviour(BehaviourState),
+    }
+
+    #[derive(Deserialize)]
+    pub struct Ai {
+        pub behavior: AiState,
+    }
+
+    #[derive(Deserialize)]
+    pub struct SavedEntity {
+        pub definition: String,
+        pub position: Position,
+        pub health: Option<Health>,
+        pub animation: Animation,
+        pub sprite: Option<Sprite>,
+        pub ai: Ai,
+        pub action_state: ActionState,
+        pub inventory: Inventory,
+        pub light: Option<Light>,
+        pub velocity: Option<Velocity>,
+        pub damage: Option<Damage>,
+    }
+
+    #[derive(Deserialize)]
+    pub struct SaveGame {
+        pub level: String,
+        pub camera: (i32, i32),
+        pub tiles: Vec<SavedTile>,
+        pub tile_damage: Vec<((usize, usize), u32)>,
+        pub entities: Vec<SavedEntity>,
+    }
+
+    impl From<Position> for components::Position {
+        fn from(position: Position) -> Self {
+            let Position { x, y, facing_right, speed } = position;
+            components::Position { x, y, facing_right, speed }
+        }
+    }
+
+    impl From<Health> for components::Health {
+        fn from(health: Health) -> Self {
+            components::Health {
+                current: health.current,
+                max: health.max,
+                damage_queue: health.damage_queue,
+                healing_queue: health.healing_queue,
+                invulnerability_timer: health.invulnerability_timer,
+                invulnerability_duration: health.invulnerability_duration,
+                regeneration_rate: health.regeneration_rate,
+                regeneration_accumulator: health.regeneration_accumulator,
+                is_dead: health.is_dead,
+            }
+        }
+    }
+
+    impl From<AnimationState> for components::AnimationState {
+        fn from(state: AnimationState) -> Self {
+            match state {
+                AnimationState::Idle => components::AnimationState::Idle,
+                AnimationState::Walk => components::AnimationState::Walk,
+                AnimationState::Attack => components::AnimationState::Attack,
+                AnimationState::Hurt => components::AnimationState::Hurt,
+                AnimationState::Death => components::AnimationState::Death,
+            }
+        }
+    }
+
+    impl From<Animation> for components::Animation {
+        fn from(animation: Animation) -> Self {
+            components::Animation {
+                current_frame: animation.current_frame,
+                frame_duration: animation.frame_duration,
+                state: animation.state.into(),
+                elapsed_time: animation.elapsed_time,
+                is_attack_in_progress: animation.is_attack_in_progress,
+                idle_frames: animation.idle_frames,
+                walk_frames: animation.walk_frames,
+                attack_frames: animation.attack_frames,
+            }
+        }
+    }
+
+    impl From<Sprite> for components::Sprite {
+        fn from(sprite: Sprite) -> Self {
+            let Sprite { frame_width, frame_height, origin, scale } = sprite;
+            components::Sprite { frame_width, frame_height, origin, scale }
+        }
+    }
+
+    impl From<BlackboardValue> for behaviour_tree::BlackboardValue {
+        fn from(value: BlackboardValue) -> Self {
+            match value {
+                BlackboardValue::Entity(entity) => behaviour_tree::BlackboardValue::Entity(entity),
+                BlackboardValue::Point(x, y) => behaviour_tree::BlackboardValue::Point(x, y),
+                BlackboardValue::Points(points) => behaviour_tree::BlackboardValue::Points(points),
+            }
+        }
+    }
+
+    impl From<BehaviourState> for behaviour_tree::BehaviourState {
+        fn from(state: BehaviourState) -> Self {
+            let mut blackboard = behaviour_tree::Blackboard::default();
+            for (key, value) in state.blackboard.values {
+                blackboard.set(&key, value.into());
+            }
+            behaviour_tree::BehaviourState {
+                tree: state.tree,
+                blackboard,
+                memory: state.memory.into_iter()
+                    .map(|memory| behaviour_tree::tree::NodeMemory { index: memory.index, since: memory.since })
+                    .collect(),
+                time: state.time,
+            }
+        }
+    }
+
+    impl From<Inventory> for components::Inventory {
+        fn from(inventory: Inventory) -> Self {
+            components::Inventory { items: inventory.items }
+        }
+    }
+
+    impl From<Light> for components::Light {
+        fn from(light: Light) -> Self {
+            let Light { radius, color, intensity } = light;
+            components::Light { radius, color, intensity }
+        }
+    }
+
+    impl From<Velocity> for components::Velocity {
+        fn from(velocity: Velocity) -> Self {
+            components::Velocity { x: velocity.x, y: velocity.y }
+        }
+    }
+
+    impl From<Damage> for components::Damage {
+        fn from(damage: Damage) -> Self {
+            components::Damage { amount: damage.amount }
+        }
+    }
+
+    impl From<SavedTile> for save::SavedTile {
+        fn from(tile: SavedTile) -> Self {
+            save::SavedTile { x: tile.x, y: tile.y, tile: components::TileId(tile.tile.0) }
+        }
+    }
+
+    impl From<ActionState> for v2::ActionState {
+        fn from(state: ActionState) -> Self {
+            match state {
+                ActionState::None => v2::ActionState::None,
+                ActionState::Moving { right, left, up, down } => v2::ActionState::Moving { right, left, up, down },
+                ActionState::Attacking => v2::ActionState::Attacking,
+                ActionState::Steering { x, y } => v2::ActionState::Steering { x, y },
+            }
+        }
+    }
+
+    // Chasers didn't remember where they came from, so wherever they were saved
+    // becomes home; the leash and cooldowns fall back to the defaults
+    fn upgrade_ai(ai: Ai, position: &Position) -> v2::Ai {
+        let behavior = match ai.behavior {
+            AiState::Idle => v2::AiState::Idle,
+            AiState::Patrol { waypoints, current_waypoint } => v2::AiState::Patrol { waypoints, current_waypoint },
+            AiState::Chase { target_entity, detection_range, attack_range } => v2::AiState::Chase(v2::Chase {
+                detection_range,
+                attack_range,
+                leash_range: ai::DEFAULT_LEASH_RANGE,
+                attack_cooldown: ai::DEFAULT_ATTACK_COOLDOWN,
+                wind_up: ai::DEFAULT_WIND_UP,
+                search_time: ai::DEFAULT_SEARCH_TIME,
+                home: (position.x, position.y),
+                patrol: Vec::new(),
+                current_waypoint: 0,
+                target_entity: Some(target_entity),
+                phase: v2::ChasePhase::Hunting,
+                cooldown: 0.0,
+            }),
+            AiState::Behaviour(state) => v2::AiState::Behaviour(state),
+        };
+        v2::Ai { behavior }
+    }
+
+    pub fn upgrade(game: SaveGame) -> v2::SaveGame {
+        let entities = game.entities.into_iter()
+            .map(|entity| v2::SavedEntity {
+                ai: upgrade_ai(entity.ai, &entity.position),
+                definition: entity.definition,
+                position: entity.position,
+                health: entity.health,
+                animation: entity.animation,
+                sprite: entity.sprite,
+                action_state: entity.action_state.into(),
+                inventory: entity.inventory,
+                light: entity.light,
+                velocity: entity.velocity,
+                damage: entity.damage,
+            })
+            .collect();
+        v2::SaveGame {
+            level: game.level,
+            camera: game.camera,
+            tiles: game.tiles,
+            tile_damage: game.tile_damage,
+            entities,
+        }
+    }
+}
+
+// Version 2: entities had no spawner. Only the shapes that changed since version 1
+// are copied again.
+mod v2 {
+    use crate::components::{self, ai};
+    use crate::save;
+    use crate::utility_ai::{self, curve};
+    use super::v1::{Animation, BehaviourState, Damage, Health, Inventory, Light, Position, SavedTile, Sprite, Velocity};
+    use serde::Deserialize;
+
+    #[derive(Deserialize)]
+    pub enum Input {
+        TargetDistance(f32),
+        CanSeeTarget,
+        Health,
+        Allies { radius: f32, max: u32 },
+        AllyHealth(f32),
+        CooldownReady,
+    }
+
+    #[derive(Deserialize)]
+    pub enum Curve {
+        Linear,
+        Inverse,
+        Power(f32),
+        Logistic { midpoint: f32, steepness: f32 },
+        Step(f32),
+    }
+
+    #[derive(Deserialize)]
+    pub struct Consideration {
+        pub input: Input,
+        pub curve: Curve,
+    }
+
+    #[derive(Deserialize)]
+    pub enum UtilityAction {
+        Idle,
+        Attack,
+        Chase,
+        Flee,
+        Wander,
+        ReturnHome,
+        Heal { amount: u32, radius: f32 },
+    }
+
+    #[derive(Deserialize)]
+    pub struct UtilityOption {
+        pub action: UtilityAction,
+        pub considerations: Vec<Consideration>,
+        pub weight: f32,
+        pub cooldown: f32,
+    }
+
+    #[derive(Deserialize)]
+    pub struct UtilityState {
+        pub options: Vec<UtilityOption>,
+        pub hysteresis: f32,
+        pub current: Option<usize>,
+        pub cooldowns: Vec<f32>,
+        pub target: Option<usize>,
+        pub home: (f32, f32),
+        pub time: f32,
+    }
+
+    #[derive(Deserialize)]
+    pub enum ChasePhase {
+        Guarding,
+        Hunting,
+        WindingUp { remaining: f32 },
+        Searching { at: (f32, f32), remaining: f32 },
+        Returning,
+    }
+
+    #[derive(Deserialize)]
+    pub struct Chase {
+        pub detection_range: f32,
+        pub attack_range: f32,
+        pub leash_range: f32,
+        pub attack_cooldown: f32,
+        pub wind_up: f32,
+        pub search_time: f32,
+        pub home: (f32, f32),
+        pub patrol: Vec<(f32, f32)>,
+        pub current_waypoint: usize,
+        pub target_entity: Option<usize>,
+        pub phase: ChasePhase,
+        pub cooldown: f32,
+    }
+
+    #[derive(Deserialize)]
+    pub enum AiState {
+        Idle,
+        Patrol { waypoints: Vec<(f32, f32)>, current_waypoint: usize },
+        Chase(Chase),
+        Behaviour(BehaviourState),
+        Utility(UtilityState),
+    }
+
+    #[derive(Deserialize)]
+    pub struct Ai {
+        pub behavior: AiState,
+    }
+
+    #[derive(Deserialize)]
+    pub enum ActionState {
+        None,
+        Moving { right: bool, left: bool, up: bool, down: bool },
+        Attacking,
+        Steering { x: f32, y: f32 },
+        Shooting,
+    }
+
+    #[derive(Deserialize)]
+    pub struct SavedEntity {
+        pub definition: String,
+        pub position: Position,
+        pub health: Option<Health>,
+        pub animation: Animation,
+        pub sprite: Option<Sprite>,
+        pub ai: Ai,
+        pub action_state: ActionState,
+        pub inventory: Inventory,
+        pub light: Option<Light>,
+        pub velocity: Option<Velocity>,
+        pub damage: Option<Damage>,
+    }
+
+    #[derive(Deserialize)]
+    pub struct SaveGame {
+        pub level: String,
+        pub camera: (i32, i32),
+        pub tiles: Vec<SavedTile>,
+        pub tile_damage: Vec<((usize, usize), u32)>,
+        pub entities: Vec<SavedEntity>,
+    }
+
+    impl From<Input> for utility_ai::Input {
+        fn from(input: Input) -> Self {
+            match input {
+                Input::TargetDistance(range) => utility_ai::Input::TargetDistance(range),
+                Input::CanSeeTarget => utility_ai::Input::CanSeeTarget,
+                Input::Health => utility_ai::Input::Health,
+                Input::Allies { radius, max } => utility_ai::Input::Allies { radius, max },
+                Input::AllyHealth(radius) => utility_ai::Input::AllyHealth(radius),
+                Input::CooldownReady => utility_ai::Input::CooldownReady,
+            }
+        }
+    }
+
+    impl From<Curve> for curve::Curve {
+        fn from(curve: Curve) -> Self {
+            match curve {
+                Curve::Linear => curve::Curve::Linear,
+                Curve::Inverse => curve::Curve::Inverse,
+                Curve::Power(exponent) => curve::Curve::Power(exponent),
+                Curve::Logistic { midpoint, steepness } => curve::Curve::Logistic { midpoint, steepness },
+                Curve::Step(threshold) => curve::Curve::Step(threshold),
+            }
+        }
+    }
+
+    impl From<UtilityAction> for utility_ai::UtilityAction {
+        fn from(action: UtilityAction) -> Self {
+            match action {
+                UtilityAction::Idle => utility_ai::UtilityAction::Idle,
+                UtilityAction::Attack => utility_ai::UtilityAction::Attack,
+                UtilityAction::Chase => utility_ai::UtilityAction::Chase,
+                UtilityAction::Flee => utility_ai::UtilityAction::Flee,
+                UtilityAction::Wander => utility_ai::UtilityAction::Wander,
+                UtilityAction::ReturnHome => utility_ai::UtilityAction::ReturnHome,
+                UtilityAction::Heal { amount, radius } => utility_ai::UtilityAction::Heal { amount, radius },
+            }
+        }
+    }
+
+    impl From<UtilityState> for utility_ai::UtilityState {
+        fn from(state: UtilityState) -> Self {
+            let options = state.options.into_iter()
+                .map(|option| utility_ai::UtilityOption {
+                    action: option.action.into(),
+                    considerations: option.considerations.into_iter()
+                        .map(|consideration| utility_ai::reasoner::Consideration {
+                            input: consideration.input.into(),
+                            curve: consideration.curve.into(),
+                        })
+                        .collect(),
+                    weight: option.weight,
+                    cooldown: option.cooldown,
+                })
+                .collect();
+            utility_ai::UtilityState {
+                options,
+                hysteresis: state.hysteresis,
+                current: state.current,
+                cooldowns: state.cooldowns,
+                target: state.target,
+                home: state.home,
+                time: state.time,
+            }
+        }
+    }
+
+    impl From<ChasePhase> for ai::ChasePhase {
+        fn from(phase: ChasePhase) -> Self {
+            match phase {
+                ChasePhase::Guarding => ai::ChasePhase::Guarding,
+                ChasePhase::Hunting => ai::ChasePhase::Hunting,
+                ChasePhase::WindingUp { remaining } => ai::ChasePhase::WindingUp { remaining },
+                ChasePhase::Searching { at, remaining } => ai::ChasePhase::Searching { at, remaining },
+                ChasePhase::Returning => ai::ChasePhase::Returning,
+            }
+        }
+    }
+
+    impl From<Chase> for ai::Chase {
+        fn from(chase: Chase) -> Self {
+            ai::Chase {
+                detection_range: chase.detection_range,
+                attack_range: chase.attack_range,
+                leash_range: chase.leash_range,
+                attack_cooldown: chase.attack_cooldown,
+                wind_up: chase.wind_up,
+                search_time: chase.search_time,
+                home: chase.home,
+                patrol: chase.patrol,
+                current_waypoint: chase.current_waypoint,
+                target_entity: chase.target_entity,
+                phase: chase.phase.into(),
+                cooldown: chase.cooldown,
+            }
+        }
+    }
+
+    impl From<Ai> for ai::Ai {
+        fn from(ai: Ai) -> Self {
+            let behavior = match ai.behavior {
+                AiState::Idle => ai::AiState::Idle,
+                AiState::Patrol { waypoints, current_waypoint } => ai::AiState::Patrol { waypoints, current_waypoint },
+                AiState::Chase(chase) => ai::AiState::Chase(chase.into()),
+                AiState::Behaviour(state) => ai::AiState::Behaviour(state.into()),
+                AiState::Utility(state) => ai::AiState::Utility(state.into()),
+            };
+            ai::Ai { behavior }
+        }
+    }
+
+    impl From<ActionState> for components::ActionState {
+        fn from(state: ActionState) -> Self {
+            match state {
+                ActionState::None => components::ActionState::None,
+                ActionState::Moving { right, left, up, down } => components::ActionState::Moving { right, left, up, down },
+                ActionState::Attacking => components::ActionState::Attacking,
+                ActionState::Steering { x, y } => components::ActionState::Steering { x, y },
+                ActionState::Shooting => components::ActionState::Shooting,
+            }
+        }
+    }
+
+    pub fn upgrade(game: SaveGame) -> save::SaveGame {
+        let entities = game.entities.into_iter()
+            .map(|entity| save::SavedEntity {
+                definition: entity.definition,
+                position: entity.position.into(),
+                health: entity.health.map(Into::into),
+                animation: entity.animation.into(),
+                sprite: entity.sprite.map(Into::into),
+                ai: entity.ai.into(),
+                action_state: entity.action_state.into(),
+                inventory: entity.inventory.into(),
+                light: entity.light.map(Into::into),
+                velocity: entity.velocity.map(Into::into),
+                damage: entity.damage.map(Into::into),
+                spawner: None,
+            })
+            .collect();
+        save::SaveGame {
+            level: game.level,
+            camera: game.camera,
+            tiles: game.tiles.into_iter().map(Into::into).collect(),
+            tile_damage: game.tile_damage,
+            entities,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::behaviour_tree::BlackboardValue;
+    use crate::components::ai::{AiState, ChasePhase, DEFAULT_LEASH_RANGE};
+    use crate::components::{ActionState, TileId};
+    use crate::save::{self, SaveFormat, SaveGame, SAVE_VERSION};
+    use crate::utility_ai::UtilityAction;
+
+    // One entity as a RON save would have written it, with the parts that changed
+    // between versions filled in by the caller
+    fn entity(ai: &str, action_state: &str) -> String {
+        format!(r#"(
+            definition: "orc",
+            position: (x: 100.0, y: 50.0, facing_right: true, speed: 1.5),
+            health: Some((current: 40, max: 50, damage_queue: [], healing_queue: [],
+                invulnerability_timer: 0.0, invulnerability_duration: 0.5,
+                regeneration_rate: 0.0, regeneration_accumulator: 0.0, is_dead: false)),
+            animation: (current_frame: 2, frame_duration: 100, state: Walk, elapsed_time: 30,
+                is_attack_in_progress: false, idle_frames: 6, walk_frames: 8, attack_frames: 6),
+            sprite: None,
+            ai: (behavior: {}),
+            action_state: {},
+            inventory: (items: {{"gold": 3}}),
+            light: Some((radius: 40.0, color: (255, 200, 100), intensity: 0.8)),
+            velocity: None,
+            damage: Some((amount: 10)),
+        )"#, ai, action_state)
+    }
+
+    fn ron_save(version: u32, entities: &[String]) -> Vec<u8> {
+        format!(r#"(
+            version: {},
+            game: (
+                level: "assets/levels/level1.ron",
+                camera: (10, 20),
+                tiles: [(x: 3, y: 4, tile: (2))],
+                tile_damage: [((5, 6), 1)],
+                entities: [{}],
+            ),
+        )"#, version, entities.join(", ")).into_bytes()
+    }
+
+    #[test]
+    fn upgrades_version_1_chasers() {
+        let bytes = ron_save(1, &[
+            entity("Chase(target_entity: 0, detection_range: 200.0, attack_range: 40.0)", "Steering(x: 1.0, y: 0.0)"),
+        ]);
+        let game = save::decode(&bytes, SaveFormat::Ron).unwrap();
+
+        assert_eq!(game.camera, (10, 20));
+        assert_eq!(game.tiles[0].tile, TileId(2));
+        assert_eq!(game.tile_damage, vec![((5, 6), 1)]);
+        let orc = &game.entities[0];
+        assert_eq!(orc.health.as_ref().unwrap().current, 40);
+        assert_eq!(orc.inventory.items["gold"], 3);
+        assert_eq!(orc.action_state, ActionState::Steering { x: 1.0, y: 0.0 });
+        assert!(orc.spawner.is_none());
+        match &orc.ai.behavior {
+            AiState::Chase(chase) => {
+                assert_eq!(chase.target_entity, Some(0));
+                assert_eq!(chase.phase, ChasePhase::Hunting);
+                assert_eq!(chase.home, (100.0, 50.0));
+                assert_eq!(chase.leash_range, DEFAULT_LEASH_RANGE);
+            },
+            _ => panic!("Expected a chaser"),
+        }
+    }
+
+    #[test]
+    fn upgrades_version_1_behaviour_trees() {
+        let tree = r#"Behaviour((
+            tree: "guard",
+            blackboard: (values: {"home": Point(1.0, 2.0), "target": Entity(0)}),
+            memory: [(index: 1, since: Some(0.5))],
+            time: 2.0,
+        ))"#;
+        let game = save::decode(&ron_save(1, &[entity(tree, "None")]), SaveFormat::Ron).unwrap();
+
+        match &game.entities[0].ai.behavior {
+            AiState::Behaviour(state) => {
+                assert_eq!(state.tree, "guard");
+                assert_eq!(state.blackboard.entity("target"), Some(0));
+                assert_eq!(state.blackboard.get("home"), Some(&BlackboardValue::Point(1.0, 2.0)));
+                assert_eq!(state.memory[0].since, Some(0.5));
+            },
+            _ => panic!("Expected a behaviour tree"),
+        }
+    }
+
+    #[test]
+    fn upgrades_version_2_utility_ai() {
+        let utility = r#"Utility((
+            options: [(
+                action: Heal(amount: 5, radius: 50.0),
+                considerations: [(input: AllyHealth(100.0), curve: Inverse)],
+                weight: 1.0,
+                cooldown: 2.0,
+            )],
+            hysteresis: 0.1,
+            current: Some(0),
+            cooldowns: [0.0],
+            target: None,
+            home: (1.0, 2.0),
+            time: 3.0,
+        ))"#;
+        let game = save::decode(&ron_save(2, &[entity(utility, "Shooting")]), SaveFormat::Ron).unwrap();
+
+        let shaman = &game.entities[0];
+        assert_eq!(shaman.action_state, ActionState::Shooting);
+        assert!(shaman.spawner.is_none());
+        match &shaman.ai.behavior {
+            AiState::Utility(state) => {
+                assert_eq!(state.current, Some(0));
+                assert!(matches!(state.options[0].action, UtilityAction::Heal { amount: 5, .. }));
+            },
+            _ => panic!("Expected utility AI"),
+        }
+    }
+
+    #[test]
+    fn current_saves_round_trip() {
+        let bytes = ron_save(2, &[entity("Idle", "Attacking")]);
+        let game: SaveGame = save::decode(&bytes, SaveFormat::Ron).unwrap();
+
+        for format in [SaveFormat::Ron, SaveFormat::Binary] {
+            let encoded = save::encode(&game, format).unwrap();
+            let decoded = save::decode(&encoded, format).unwrap();
+            assert_eq!(decoded.level, game.level);
+            assert_eq!(decoded.entities.len(), 1);
+            assert_eq!(decoded.entities[0].action_state, ActionState::Attacking);
+        }
+    }
+
+    #[test]
+    fn rejects_unsupported_versions() {
+        let too_old = save::decode(&ron_save(0, &[]), SaveFormat::Ron);
+        assert!(too_old.is_err_and(|e| e.contains("too old")));
+
+        let too_new = save::decode(&ron_save(SAVE_VERSION + 1, &[]), SaveFormat::Ron);
+        assert!(too_new.is_err_and(|e| e.contains("newer")));
+    }
+}
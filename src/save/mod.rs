@@ -0,0 +1,173 @@
+// Save games. A save holds everything needed to rebuild the running world: the
+// level, tile edits made during play, the camera and every entity's components.
+// Textures and input bindings aren't stored; they're rebuilt from the entity's
+// definition when the save is loaded.
+mod migrations;
+
+use crate::components::ai::Ai;
+use crate::components::*;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+// Bump when SaveGame changes in a way older files can't be read as, and add a
+// step to migrations.rs
+pub const SAVE_VERSION: u32 = 3;
+pub const SAVE_DIR: &str = "saves";
+pub const QUICKSAVE_SLOT: u32 = 1;
+
+// Binary saves: magic, u32 little-endian version, then the bincode-encoded SaveGame
+const BINARY_MAGIC: &[u8; 8] = b"SDLSAVE\0";
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum SaveFormat {
+    Ron,    // Readable, handy for debugging and hand-editing
+    Binary, // Compact and quick to parse
+}
+
+impl SaveFormat {
+    pub fn extension(self) -> &'static str {
+        match self {
+            SaveFormat::Ron => "ron",
+            SaveFormat::Binary => "sav",
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug)]
+pub struct SavedTile {
+    pub x: usize,
+    pub y: usize,
+    pub tile: TileId,
+}
+
+// One entity: the definition it was created from plus its live components
+#[derive(Serialize, Deserialize)]
+pub struct SavedEntity {
+    pub definition: String,
+    pub position: Position,
+    pub health: Option<Health>,
+    pub animation: Animation,
+    pub sprite: Option<Sprite>,
+    pub ai: Ai,
+    pub action_state: ActionState,
+    pub inventory: Inventory,
+    pub light: Option<Light>,
+    pub velocity: Option<Velocity>,
+    pub damage: Option<Damage>,
+    pub spawner: Option<Spawner>, // Its enemies are listed by index in SaveGame::entities
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct SaveGame {
+    pub level: String,
+    pub camera: (i32, i32),
+    pub tiles: Vec<SavedTile>, // Cells that differ from the level's tilemap file
+    pub tile_damage: Vec<((usize, usize), u32)>, // Destructible tiles hit but not yet broken
+    pub entities: Vec<SavedEntity>, // The player first, as in GameState
+}
+
+// What a RON save looks like on disk; the version is read first so older layouts
+// can be told apart before the rest is parsed
+#[derive(Serialize)]
+struct RonSave<'s> {
+    version: u32,
+    game: &'s SaveGame,
+}
+
+#[derive(Deserialize)]
+struct RonSaveVersion {
+    version: u32,
+}
+
+pub fn slot_path(slot: u32, format: SaveFormat) -> PathBuf {
+    Path::new(SAVE_DIR).join(format!("slot{}.{}", slot, format.extension()))
+}
+
+pub fn encode(game: &SaveGame, format: SaveFormat) -> Result<Vec<u8>, String> {
+    match format {
+        SaveFormat::Ron => {
+            let save = RonSave { version: SAVE_VERSION, game };
+            ron::ser::to_string_pretty(&save, ron::ser::PrettyConfig::default())
+                .map(String::into_bytes)
+                .map_err(|e| format!("Failed to serialize save: {}", e))
+        },
+        SaveFormat::Binary => {
+            let mut bytes = BINARY_MAGIC.to_vec();
+            bytes.extend_from_slice(&SAVE_VERSION.to_le_bytes());
+            bincode::serialize_into(&mut bytes, game)
+                .map_err(|e| format!("Failed to serialize save: {}", e))?;
+            Ok(bytes)
+        },
+    }
+}
+
+// Decode a save written by this or any older version
+pub fn decode(bytes: &[u8], format: SaveFormat) -> Result<SaveGame, String> {
+    let version = match format {
+        SaveFormat::Ron => {
+            let text = std::str::from_utf8(bytes).map_err(|e| format!("Save is not valid UTF-8: {}", e))?;
+            ron::from_str::<RonSaveVersion>(text)
+                .map_err(|e| format!("Failed to read save version: {}", e))?
+                .version
+        },
+        SaveFormat::Binary => {
+            if !bytes.starts_with(BINARY_MAGIC) || bytes.len() < BINARY_MAGIC.len() + 4 {
+                return Err("Not a binary save file".to_string());
+            }
+            let version = &bytes[BINARY_MAGIC.len()..BINARY_MAGIC.len() + 4];
+            u32::from_le_bytes(version.try_into().map_err(|_| "Truncated save header".to_string())?)
+        },
+    };
+
+    if version > SAVE_VERSION {
+        return Err(format!("Save version {} is newer than this build supports ({})", version, SAVE_VERSION));
+    }
+    migrations::upgrade(version, bytes, format)
+}
+
+// Format + version-specific body parsing, shared with the migrations
+fn decode_current<T: for<'de> Deserialize<'de>>(bytes: &[u8], format: SaveFormat) -> Result<T, String> {
+    #[derive(Deserialize)]
+    struct RonBody<T> {
+        game: T,
+    }
+
+    match format {
+        SaveFormat::Ron => {
+            let text = std::str::from_utf8(bytes).map_err(|e| format!("Save is not valid UTF-8: {}", e))?;
+            ron::from_str::<RonBody<T>>(text)
+                .map(|body| body.game)
+                .map_err(|e| format!("Failed to parse save: {}", e))
+        },
+        SaveFormat::Binary => bincode::deserialize(&bytes[BINARY_MAGIC.len() + 4..])
+            .map_err(|e| format!("Failed to parse save: {}", e)),
+    }
+}
+
+// Written next to the slot and renamed over it, so a crash mid-save can't leave a
+// half-written file behind
+pub fn write_slot(slot: u32, format: SaveFormat, game: &SaveGame) -> Result<PathBuf, String> {
+    let path = slot_path(slot, format);
+    let bytes = encode(game, format)?;
+    let io_error = |e: std::io::Error| format!("Failed to write {}: {}", path.display(), e);
+
+    std::fs::create_dir_all(SAVE_DIR).map_err(io_error)?;
+    let temp = path.with_extension("tmp");
+    std::fs::write(&temp, bytes).map_err(io_error)?;
+    std::fs::rename(&temp, &path).map_err(io_error)?;
+    Ok(path)
+}
+
+// A slot can have a save in either format; the most recent one wins
+pub fn read_slot(slot: u32) -> Result<SaveGame, String> {
+    let modified = |path: &Path| std::fs::metadata(path).and_then(|meta| meta.modified()).ok();
+    let (path, format) = [SaveFormat::Binary, SaveFormat::Ron].into_iter()
+        .map(|format| (slot_path(slot, format), format))
+        .filter_map(|(path, format)| modified(&path).map(|time| (time, path, format)))
+        .max_by_key(|(time, _, _)| *time)
+        .map(|(_, path, format)| (path, format))
+        .ok_or_else(|| format!("Save slot {} is empty", slot))?;
+
+    let bytes = std::fs::read(&path).map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    decode(&bytes, format).map_err(|e| format!("{}: {}", path.display(), e))
+}
@@ -0,0 +1,53 @@
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum TransitionPhase {
+    FadeOut, // Screen going dark, old level still shown
+    FadeIn,  // New level loaded, screen brightening
+}
+
+// Fade out, swap levels while the screen is black, fade back in
+pub struct SceneTransition {
+    pub target_level: String,
+    pub entry: String,
+    pub phase: TransitionPhase,
+    elapsed: f32,
+    fade_duration: f32, // Seconds for each half of the transition
+}
+
+impl SceneTransition {
+    pub fn new(target_level: &str, entry: &str) -> Self {
+        SceneTransition {
+            target_level: target_level.to_string(),
+            entry: entry.to_string(),
+            phase: TransitionPhase::FadeOut,
+            elapsed: 0.0,
+            fade_duration: 0.4,
+        }
+    }
+    
+    // Advance the fade. Returns true exactly once, on the frame the screen is fully
+    // black and the level should be swapped.
+    pub fn update(&mut self, delta_time: f32) -> bool {
+        self.elapsed += delta_time;
+        
+        if self.phase == TransitionPhase::FadeOut && self.elapsed >= self.fade_duration {
+            self.phase = TransitionPhase::FadeIn;
+            self.elapsed = 0.0;
+            return true;
+        }
+        false
+    }
+    
+    pub fn is_finished(&self) -> bool {
+        self.phase == TransitionPhase::FadeIn && self.elapsed >= self.fade_duration
+    }
+    
+    // Opacity of the black overlay
+    pub fn alpha(&self) -> u8 {
+        let progress = (self.elapsed / self.fade_duration).clamp(0.0, 1.0);
+        let opacity = match self.phase {
+            TransitionPhase::FadeOut => progress,
+            TransitionPhase::FadeIn => 1.0 - progress,
+        };
+        (opacity * 255.0) as u8
+    }
+}
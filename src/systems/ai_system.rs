@@ -1,19 +1,60 @@
-use crate::components::{AiState, Entity, Position};
-use crate::components::ai::Ai;  // Changed from AiState to Ai
+use crate::components::AiState;
+use crate::components::ai::{Ai, Chase, ChasePhase};  // Changed from AiState to Ai
 use crate::components::ActionState;
+use crate::components::position::{FEET_OFFSET_X, FEET_OFFSET_Y};
+use crate::navigation::steering::Vector;
+use crate::navigation::{FlowField, PathCache, Steering};
+use crate::systems::ai_world::AiWorld;
+use crate::systems::perception_system::PerceptionSystem;
+
+// How close (in pixels, feet to feet) counts as having reached home or a waypoint
+const ARRIVE_DISTANCE: f32 = 8.0;
+
+// Everything the AI can read about the world this frame
+struct Frame<'f> {
+    world: &'f AiWorld<'f>,
+    velocities: Vec<Vector>, // Pixels per frame, from each entity's action state
+    steering: Steering<'f>,
+    crowd: Option<(usize, &'f FlowField)>,
+    delta_time: f32,
+}
+
+fn distance(a: Vector, b: Vector) -> f32 {
+    ((a.0 - b.0).powi(2) + (a.1 - b.1).powi(2)).sqrt()
+}
+
+// Positions (waypoints, home) are where an entity stands; movement runs between feet
+fn feet_at((x, y): Vector) -> Vector {
+    (x + FEET_OFFSET_X, y + FEET_OFFSET_Y)
+}
 
 // systems/ai_system.rs
 pub struct AiSystem;
 
 impl AiSystem {
+    // Takes every entity; index 0 is the player, whose actions come from input.
+    // `crowd` is a flow field leading to one entity, shared by everything chasing it.
     pub fn run(
-        entities: &[Entity],
-        positions: &[Position],
+        world: &AiWorld,
         ais: &mut [Ai],  // Changed from AiState to Ai
         action_states: &mut [ActionState],
+        paths: &mut PathCache,
+        crowd: Option<(usize, &FlowField)>,
+        delta_time: f32,
     ) {
-        let _ = entities;
-        for (i, ai) in ais.iter_mut().enumerate() {
+        let (entities, positions) = (world.entities, world.positions);
+        let frame = Frame {
+            world,
+            velocities: action_states.iter()
+                .zip(positions)
+                .map(|(action_state, position)| action_state.velocity(position.speed))
+                .collect(),
+            steering: Steering::new(positions, ais, world.collision),
+            crowd,
+            delta_time,
+        };
+        let steering = &frame.steering;
+        for (i, ai) in ais.iter_mut().enumerate().skip(1) {
             match &mut ai.behavior {  // Access the AiState through the behavior field
                 AiState::Idle => {
                     // Random chance to start patrolling
@@ -40,33 +81,332 @@ impl AiSystem {
                         *current_waypoint = (*current_waypoint + 1) % waypoints.len();
                         action_states[i] = ActionState::None;
                     } else {
-                        // Move toward waypoint
-                        let right = dx > 0.0;
-                        let left = dx < 0.0;
-                        let up = dy < 0.0;
-                        let down = dy > 0.0;
-                        action_states[i] = ActionState::Moving { right, left, up, down };
+                        // Waypoints are where the entity stands, paths run between feet
+                        let goal = (target_x + FEET_OFFSET_X, target_y + FEET_OFFSET_Y);
+                        action_states[i] = steering.travel(i, entities[i].0, goal, paths);
                     }
                 },
-                AiState::Chase { target_entity, detection_range, attack_range } => {
-                    // Chase behavior implementation
-                    if *target_entity < positions.len() {
-                        let dx = positions[*target_entity].x - positions[i].x;
-                        let dy = positions[*target_entity].y - positions[i].y;
-                        let distance = (dx*dx + dy*dy).sqrt();
-                        
-                        if distance < *attack_range {
-                            action_states[i] = ActionState::Attacking;
-                        } else if distance < *detection_range {
-                            let right = dx > 0.0;
-                            let left = dx < 0.0;
-                            let up = dy < 0.0;
-                            let down = dy > 0.0;
-                            action_states[i] = ActionState::Moving { right, left, up, down };
-                        }
+                AiState::Chase(chase) => {
+                    action_states[i] = frame.chase(i, chase, paths);
+                },
+                AiState::Behaviour(_) => {}, // See BehaviourSystem
+                AiState::Utility(_) => {},   // See UtilitySystem
+            }
+        }
+    }
+}
+
+impl Frame<'_> {
+    // One tick of a chaser's state machine
+    fn chase(&self, i: usize, chase: &mut Chase, paths: &mut PathCache) -> ActionState {
+        let entity = self.world.entities[i].0;
+        let feet = self.world.positions[i].feet();
+        let home = feet_at(chase.home);
+        chase.cooldown = (chase.cooldown - self.delta_time).max(0.0);
+        
+        // Targets that vanish, die or stop being hostile are dropped, and anything that
+        // lures it too far from home is given up on
+        if chase.target_entity.is_some_and(|target| !self.world.is_target(i, target))
+            || (chase.target_entity.is_some() && distance(feet, home) > chase.leash_range) {
+            chase.target_entity = None;
+            chase.phase = ChasePhase::Returning;
+        }
+        
+        match chase.phase {
+            ChasePhase::Guarding => {
+                if let Some(target) = self.find_target(i, chase) {
+                    chase.target_entity = Some(target);
+                    chase.phase = ChasePhase::Hunting;
+                    return ActionState::None;
+                }
+                self.guard(i, chase, paths)
+            },
+            ChasePhase::Returning => {
+                if distance(feet, home) <= ARRIVE_DISTANCE {
+                    chase.phase = ChasePhase::Guarding;
+                    return ActionState::None;
+                }
+                self.steering.travel(i, entity, home, paths)
+            },
+            ChasePhase::Hunting => {
+                let Some(target) = chase.target_entity else {
+                    chase.phase = ChasePhase::Returning;
+                    return ActionState::None;
+                };
+                if !self.sees(i, target, chase) {
+                    chase.phase = ChasePhase::Searching { at: self.last_known(i, target), remaining: chase.search_time };
+                    return ActionState::None;
+                }
+                
+                let target_feet = self.world.positions[target].feet();
+                if distance(feet, target_feet) < chase.attack_range {
+                    // Holds its ground until it can swing again
+                    if chase.cooldown > 0.0 {
+                        return ActionState::None;
+                    }
+                    if chase.wind_up > 0.0 {
+                        chase.phase = ChasePhase::WindingUp { remaining: chase.wind_up };
+                        return ActionState::None;
                     }
+                    chase.cooldown = chase.attack_cooldown;
+                    return ActionState::Attacking;
+                }
+                
+                let field = self.crowd
+                    .filter(|(field_target, _)| *field_target == target)
+                    .map(|(_, field)| field);
+                self.steering.chase(i, entity, target_feet, self.velocities[target], field, paths)
+            },
+            ChasePhase::WindingUp { remaining } => {
+                let remaining = remaining - self.delta_time;
+                if remaining > 0.0 {
+                    chase.phase = ChasePhase::WindingUp { remaining };
+                    return ActionState::None;
                 }
+                // Lands even if the target has stepped away; that's what the wind-up is for
+                chase.phase = ChasePhase::Hunting;
+                chase.cooldown = chase.attack_cooldown;
+                ActionState::Attacking
+            },
+            ChasePhase::Searching { at, remaining } => {
+                if chase.target_entity.is_some_and(|target| self.sees(i, target, chase)) {
+                    chase.phase = ChasePhase::Hunting;
+                    return ActionState::None;
+                }
+                let remaining = remaining - self.delta_time;
+                if remaining <= 0.0 {
+                    chase.target_entity = None;
+                    chase.phase = ChasePhase::Returning;
+                    return ActionState::None;
+                }
+                // Hearing the target again moves the search
+                let at = self.world.perceptions[i].as_ref().and_then(|perception| perception.last_known).unwrap_or(at);
+                chase.phase = ChasePhase::Searching { at, remaining };
+                self.steering.travel(i, entity, at, paths)
+            },
+        }
+    }
+    
+    // Walk the patrol route, or wait at home without one
+    fn guard(&self, i: usize, chase: &mut Chase, paths: &mut PathCache) -> ActionState {
+        let feet = self.world.positions[i].feet();
+        let goal = match chase.patrol.get(chase.current_waypoint) {
+            Some(&waypoint) => {
+                let goal = feet_at(waypoint);
+                if distance(feet, goal) <= ARRIVE_DISTANCE {
+                    chase.current_waypoint = (chase.current_waypoint + 1) % chase.patrol.len();
+                    return ActionState::None;
+                }
+                goal
+            },
+            None => feet_at(chase.home),
+        };
+        self.steering.travel(i, self.world.entities[i].0, goal, paths)
+    }
+    
+    // Whether a chaser can tell where its target is right now. With a Perception it
+    // only knows what it has seen; without one it senses anything in detection_range.
+    fn sees(&self, i: usize, target: usize, chase: &Chase) -> bool {
+        match &self.world.perceptions[i] {
+            Some(perception) => perception.sees_target,
+            None => distance(self.world.positions[i].feet(), self.world.positions[target].feet()) < chase.detection_range,
+        }
+    }
+    
+    fn last_known(&self, i: usize, target: usize) -> Vector {
+        self.world.perceptions[i].as_ref()
+            .and_then(|perception| perception.last_known)
+            .unwrap_or_else(|| self.world.positions[target].feet())
+    }
+    
+    // Nearest target the chaser notices: in its vision cone or heard with a
+    // Perception, otherwise anywhere within detection_range
+    fn find_target(&self, i: usize, chase: &Chase) -> Option<usize> {
+        let feet = self.world.positions[i].feet();
+        (0..self.world.positions.len())
+            .filter(|&other| self.world.is_target(i, other))
+            .filter(|&other| {
+                let other_feet = self.world.positions[other].feet();
+                match &self.world.perceptions[i] {
+                    Some(perception) => perception.heard.iter().any(|noise| noise.source == other)
+                        || PerceptionSystem::can_see(perception, &self.world.positions[i], &self.world.positions[other], self.world.collision),
+                    None => distance(feet, other_feet) < chase.detection_range,
+                }
+            })
+            .min_by(|&a, &b| {
+                let to_a = distance(feet, self.world.positions[a].feet());
+                let to_b = distance(feet, self.world.positions[b].feet());
+                to_a.total_cmp(&to_b)
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+    use crate::components::{Entity, Faction, Health, Position};
+    use crate::factions::{FactionDefinition, FactionTable, Relation};
+    use crate::navigation::PathOptions;
+
+    // The player (0) and one orc (1) guarding where it stands, on open ground
+    struct Scene {
+        positions: Vec<Position>,
+        ais: Vec<Ai>,
+        action_states: Vec<ActionState>,
+        paths: PathCache,
+    }
+
+    impl Scene {
+        fn new(configure: impl FnOnce(&mut Chase)) -> Self {
+            let mut chase = Chase::new(200.0, 40.0, (300.0, 0.0));
+            chase.leash_range = 400.0;
+            chase.attack_cooldown = 1.0;
+            chase.wind_up = 0.0;
+            chase.search_time = 2.0;
+            configure(&mut chase);
+            Scene {
+                positions: vec![Position::new(0.0, 0.0, true), Position::new(300.0, 0.0, false)],
+                ais: vec![Ai { behavior: AiState::Idle }, Ai { behavior: AiState::Chase(chase) }],
+                action_states: vec![ActionState::None, ActionState::None],
+                paths: PathCache::new(PathOptions::default()),
+            }
+        }
+
+        // Puts the player this many pixels from the orc
+        fn player_at(&mut self, distance: f32) {
+            self.positions[0].x = self.positions[1].x - distance;
+        }
+
+        fn tick(&mut self, delta_time: f32) -> ActionState {
+            let entities = [Entity(0), Entity(1)];
+            let factions = [Some(Faction { name: "player".to_string() }), Some(Faction { name: "orcs".to_string() })];
+            let relations = FactionTable::new(HashMap::from([(
+                "orcs".to_string(),
+                FactionDefinition { relations: HashMap::from([("player".to_string(), Relation::Hostile)]), friendly_fire: false },
+            )]));
+            let world = AiWorld {
+                entities: &entities,
+                positions: &self.positions,
+                factions: &factions,
+                relations: &relations,
+                healths: &[Some(Health::new(10, 10)), None],
+                hurtboxes: &[None, None],
+                projectiles: &[None, None],
+                perceptions: &[None, None],
+                collision: None,
+            };
+            AiSystem::run(&world, &mut self.ais, &mut self.action_states, &mut self.paths, None, delta_time);
+            self.action_states[1].clone()
+        }
+
+        fn chase(&self) -> &Chase {
+            match &self.ais[1].behavior {
+                AiState::Chase(chase) => chase,
+                _ => unreachable!(),
             }
         }
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn notices_targets_in_detection_range() {
+        let mut scene = Scene::new(|_| {});
+        scene.player_at(250.0);
+        scene.tick(0.1);
+        assert_eq!(scene.chase().phase, ChasePhase::Guarding);
+
+        scene.player_at(150.0);
+        scene.tick(0.1);
+        assert_eq!(scene.chase().phase, ChasePhase::Hunting);
+        assert_eq!(scene.chase().target_entity, Some(0));
+    }
+
+    #[test]
+    fn holds_its_ground_until_the_cooldown_runs_out() {
+        let mut scene = Scene::new(|_| {});
+        scene.player_at(30.0);
+        scene.tick(0.5);
+        assert_eq!(scene.tick(0.5), ActionState::Attacking);
+        assert_eq!(scene.tick(0.5), ActionState::None);
+        assert_eq!(scene.chase().phase, ChasePhase::Hunting);
+        assert_eq!(scene.tick(0.5), ActionState::Attacking);
+    }
+
+    #[test]
+    fn winds_up_before_attacking() {
+        let mut scene = Scene::new(|chase| chase.wind_up = 0.5);
+        scene.player_at(30.0);
+        scene.tick(0.25);
+        assert_eq!(scene.tick(0.25), ActionState::None);
+        assert_eq!(scene.chase().phase, ChasePhase::WindingUp { remaining: 0.5 });
+        assert_eq!(scene.tick(0.25), ActionState::None);
+
+        // Lands even though the player stepped back out of reach
+        scene.player_at(100.0);
+        assert_eq!(scene.tick(0.25), ActionState::Attacking);
+        assert_eq!(scene.chase().phase, ChasePhase::Hunting);
+    }
+
+    #[test]
+    fn gives_up_once_lured_past_the_leash() {
+        let mut scene = Scene::new(|_| {});
+        scene.player_at(100.0);
+        scene.tick(0.1);
+        assert_eq!(scene.chase().phase, ChasePhase::Hunting);
+
+        // Still inside the leash, so it keeps going
+        scene.positions[1].x = -50.0;
+        scene.player_at(100.0);
+        scene.tick(0.1);
+        assert_eq!(scene.chase().phase, ChasePhase::Hunting);
+
+        scene.positions[1].x = -150.0;
+        scene.player_at(100.0);
+        assert!(matches!(scene.tick(0.1), ActionState::Steering { .. }));
+        assert_eq!(scene.chase().phase, ChasePhase::Returning);
+        assert_eq!(scene.chase().target_entity, None);
+
+        // Ignores the player on the way home, then guards again once there
+        scene.tick(0.1);
+        assert_eq!(scene.chase().phase, ChasePhase::Returning);
+        scene.positions[1].x = 300.0;
+        scene.player_at(100.0);
+        scene.tick(0.1);
+        assert_eq!(scene.chase().phase, ChasePhase::Guarding);
+        scene.tick(0.1);
+        assert_eq!(scene.chase().phase, ChasePhase::Hunting);
+    }
+
+    #[test]
+    fn searches_for_a_lost_target_then_returns() {
+        let mut scene = Scene::new(|_| {});
+        scene.player_at(100.0);
+        scene.tick(0.1);
+
+        scene.player_at(300.0);
+        scene.tick(0.1);
+        assert!(matches!(scene.chase().phase, ChasePhase::Searching { remaining, .. } if remaining == 2.0));
+        scene.tick(1.0);
+        assert!(matches!(scene.chase().phase, ChasePhase::Searching { remaining, .. } if remaining == 1.0));
+        assert_eq!(scene.chase().target_entity, Some(0));
+
+        scene.tick(1.0);
+        assert_eq!(scene.chase().phase, ChasePhase::Returning);
+        assert_eq!(scene.chase().target_entity, None);
+    }
+
+    #[test]
+    fn finding_the_target_again_ends_the_search() {
+        let mut scene = Scene::new(|_| {});
+        scene.player_at(100.0);
+        scene.tick(0.1);
+        scene.player_at(300.0);
+        scene.tick(0.1);
+        scene.tick(1.5);
+
+        scene.player_at(100.0);
+        scene.tick(0.1);
+        assert_eq!(scene.chase().phase, ChasePhase::Hunting);
+        assert_eq!(scene.chase().target_entity, Some(0));
+    }
+}
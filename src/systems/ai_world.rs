@@ -0,0 +1,83 @@
+use crate::components::{CollisionGrid, Entity, Faction, Health, Hurtbox, Perception, Position, Projectile};
+use crate::factions::FactionTable;
+
+// What the AI systems can read about the world this frame: one entry per entity
+// in each column, index 0 being the player
+pub struct AiWorld<'w> {
+    pub entities: &'w [Entity],
+    pub positions: &'w [Position],
+    pub factions: &'w [Option<Faction>],
+    pub relations: &'w FactionTable,
+    pub healths: &'w [Option<Health>],
+    pub hurtboxes: &'w [Option<Hurtbox>],
+    pub projectiles: &'w [Option<Projectile>],
+    pub perceptions: &'w [Option<Perception>],
+    pub collision: Option<&'w CollisionGrid>,
+}
+
+impl AiWorld<'_> {
+    pub fn is_alive(&self, i: usize) -> bool {
+        self.healths[i].as_ref().is_none_or(|health| !health.is_dead)
+    }
+
+    pub fn is_hostile(&self, i: usize, other: usize) -> bool {
+        if other == i || other >= self.positions.len() {
+            return false;
+        }
+        self.relations.is_hostile(self.factions[i].as_ref(), self.factions[other].as_ref())
+    }
+
+    // Whether `other` is worth going after: hostile, alive and something that can be
+    // hurt. Projectiles carry their shooter's faction but aren't targets themselves.
+    pub fn is_target(&self, i: usize, other: usize) -> bool {
+        self.is_hostile(i, other)
+            && self.is_alive(other)
+            && (self.healths[other].is_some() || self.hurtboxes[other].is_some())
+            && self.projectiles[other].is_none()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::components::TileHit;
+    use crate::factions::{FactionDefinition, Relation};
+    use std::collections::HashMap;
+
+    #[test]
+    fn only_living_hurtable_hostiles_are_targets() {
+        // 0 the player, 1 an orc, 2 a dead player-side guard, 3 a player-side
+        // banner with nothing to hit, 4 the player's arrow, 5 a player-side crate
+        let entities: Vec<Entity> = (0..6).map(Entity).collect();
+        let positions = vec![Position::new(0.0, 0.0, true); 6];
+        let player = || Some(Faction { name: "player".to_string() });
+        let factions = [player(), Some(Faction { name: "orcs".to_string() }), player(), player(), player(), player()];
+        let relations = FactionTable::new(HashMap::from([(
+            "orcs".to_string(),
+            FactionDefinition { relations: HashMap::from([("player".to_string(), Relation::Hostile)]), friendly_fire: false },
+        )]));
+        let mut dead = Health::new(0, 10);
+        dead.is_dead = true;
+        let healths = [Some(Health::new(10, 10)), Some(Health::new(10, 10)), Some(dead), None, None, None];
+        let crate_box = Hurtbox { width: 32.0, height: 32.0, offset_x: 0.0, offset_y: 0.0 };
+        let hurtboxes = [None, None, None, None, None, Some(crate_box)];
+        let projectiles = [None, None, None, None, Some(Projectile::new(1.0, 0, TileHit::Stop)), None];
+        let world = AiWorld {
+            entities: &entities,
+            positions: &positions,
+            factions: &factions,
+            relations: &relations,
+            healths: &healths,
+            hurtboxes: &hurtboxes,
+            projectiles: &projectiles,
+            perceptions: &[None, None, None, None, None, None],
+            collision: None,
+        };
+
+        let targets: Vec<usize> = (0..8).filter(|&other| world.is_target(1, other)).collect();
+        assert_eq!(targets, [0, 5]);
+        assert!(world.is_hostile(1, 4));
+        assert!(!world.is_hostile(1, 1));
+        assert!(!world.is_target(0, 2));
+    }
+}
@@ -0,0 +1,438 @@
+use crate::behaviour_tree::blackboard::{LAST_SEEN, TARGET, WAYPOINTS};
+use crate::behaviour_tree::{BehaviourState, BehaviourTree, BlackboardValue, NodeKind, Status};
+use crate::components::ai::Ai;
+use crate::components::position::{FEET_OFFSET_X, FEET_OFFSET_Y};
+use crate::components::{ActionState, AiState, Perception, Position};
+use crate::navigation::steering::{self, Vector};
+use crate::navigation::{PathCache, Steering};
+use crate::systems::ai_world::AiWorld;
+use std::collections::HashMap;
+
+// How close (in pixels, feet to feet) MoveTo and Patrol have to get to arrive
+const ARRIVE_DISTANCE: f32 = 8.0;
+
+// One entity's tick through its tree: the world it can see and the action it picks
+struct Tick<'t> {
+    entity: usize,
+    world: &'t AiWorld<'t>,
+    positions: &'t [Position],
+    velocities: &'t [Vector], // Pixels per frame, from each entity's action state
+    perception: Option<&'t Perception>,
+    paths: &'t mut PathCache,
+    steering: &'t Steering<'t>,
+    action: ActionState,
+    visited: Vec<bool>, // Nodes reached this tick; the rest are halted afterwards
+}
+
+// Runs the entities whose AI is AiState::Behaviour. The rest are left to AiSystem.
+pub struct BehaviourSystem;
+
+impl BehaviourSystem {
+    pub fn run(
+        world: &AiWorld,
+        ais: &mut [Ai],
+        action_states: &mut [ActionState],
+        trees: Option<&HashMap<String, BehaviourTree>>,
+        paths: &mut PathCache,
+        delta_time: f32,
+    ) {
+        let positions = world.positions;
+        let steering = Steering::new(positions, ais, world.collision);
+        let velocities: Vec<Vector> = action_states.iter()
+            .zip(positions)
+            .map(|(action_state, position)| action_state.velocity(position.speed))
+            .collect();
+        for (i, ai) in ais.iter_mut().enumerate().skip(1) {
+            let AiState::Behaviour(state) = &mut ai.behavior else { continue };
+            // A tree removed by hot reload leaves the entity standing around
+            let Some(tree) = trees.and_then(|trees| trees.get(&state.tree)) else {
+                action_states[i] = ActionState::None;
+                continue;
+            };
+            state.fit(tree);
+            state.time += delta_time;
+            
+            // Always after the nearest target (see AiWorld::is_target)
+            let feet = positions[i].feet();
+            let distance = |other: usize| {
+                let (x, y) = positions[other].feet();
+                steering::length((x - feet.0, y - feet.1))
+            };
+            let target = (0..positions.len())
+                .filter(|&other| world.is_target(i, other))
+                .min_by(|&a, &b| distance(a).total_cmp(&distance(b)));
+            match target {
+                Some(target) => state.blackboard.set(TARGET, BlackboardValue::Entity(target)),
+                None => state.blackboard.remove(TARGET),
+            }
+            
+            let perception = world.perceptions[i].as_ref();
+            if let Some(perception) = perception {
+                match perception.last_known {
+                    Some((x, y)) => state.blackboard.set(LAST_SEEN, BlackboardValue::Point(x - FEET_OFFSET_X, y - FEET_OFFSET_Y)),
+                    None => state.blackboard.remove(LAST_SEEN),
+                }
+            }
+
+            let mut tick = Tick {
+                entity: i,
+                world,
+                positions,
+                velocities: &velocities,
+                perception,
+                paths: &mut *paths,
+                steering: &steering,
+                action: ActionState::None,
+                visited: vec![false; tree.nodes.len()],
+            };
+            tick.tick(tree, state, 0);
+
+            for (id, visited) in tick.visited.iter().enumerate() {
+                if !visited {
+                    state.halt(tree, id);
+                }
+            }
+            action_states[i] = tick.action;
+        }
+    }
+}
+
+impl Tick<'_> {
+    fn tick(&mut self, tree: &BehaviourTree, state: &mut BehaviourState, id: usize) -> Status {
+        self.visited[id] = true;
+        let node = &tree.nodes[id];
+        match &node.kind {
+            NodeKind::Sequence => {
+                let resume = state.memory[id].index.min(node.children.len());
+                // Conditions before the running child still guard it
+                for &child in &node.children[..resume] {
+                    if tree.is_condition(child) && self.tick(tree, state, child) == Status::Failure {
+                        state.memory[id].index = 0;
+                        return Status::Failure;
+                    }
+                }
+                for (index, &child) in node.children.iter().enumerate().skip(resume) {
+                    match self.tick(tree, state, child) {
+                        Status::Success => {},
+                        Status::Running => {
+                            state.memory[id].index = index;
+                            return Status::Running;
+                        },
+                        Status::Failure => {
+                            state.memory[id].index = 0;
+                            return Status::Failure;
+                        },
+                    }
+                }
+                state.memory[id].index = 0;
+                Status::Success
+            },
+            NodeKind::Selector => {
+                for &child in &node.children {
+                    match self.tick(tree, state, child) {
+                        Status::Failure => {},
+                        status => return status,
+                    }
+                }
+                Status::Failure
+            },
+            NodeKind::Parallel => {
+                let mut status = Status::Success;
+                for &child in &node.children {
+                    match self.tick(tree, state, child) {
+                        Status::Failure => return Status::Failure,
+                        Status::Running => status = Status::Running,
+                        Status::Success => {},
+                    }
+                }
+                status
+            },
+            NodeKind::Inverter => match self.tick(tree, state, node.children[0]) {
+                Status::Success => Status::Failure,
+                Status::Failure => Status::Success,
+                Status::Running => Status::Running,
+            },
+            NodeKind::Cooldown(seconds) => {
+                if state.memory[id].since.is_some_and(|since| state.time - since < *seconds) {
+                    return Status::Failure;
+                }
+                let status = self.tick(tree, state, node.children[0]);
+                if status == Status::Success {
+                    state.memory[id].since = Some(state.time);
+                }
+                status
+            },
+            NodeKind::Repeat(times) => match self.tick(tree, state, node.children[0]) {
+                Status::Success => {
+                    let memory = &mut state.memory[id];
+                    memory.index += 1;
+                    if *times > 0 && memory.index >= *times as usize {
+                        memory.index = 0;
+                        Status::Success
+                    } else {
+                        Status::Running
+                    }
+                },
+                Status::Failure => {
+                    state.memory[id].index = 0;
+                    Status::Failure
+                },
+                Status::Running => Status::Running,
+            },
+            NodeKind::MoveTo(key) => match state.blackboard.get(key) {
+                // Entities get chased, aiming ahead of where they're going
+                Some(BlackboardValue::Entity(target)) => match self.positions.get(*target) {
+                    Some(position) => self.chase(position.feet(), self.velocities[*target]),
+                    None => Status::Failure,
+                },
+                _ => match self.goal(state, key) {
+                    Some(goal) => self.walk_to(goal),
+                    None => Status::Failure,
+                },
+            },
+            NodeKind::Attack => {
+                self.action = ActionState::Attacking;
+                Status::Success
+            },
+            NodeKind::InRange(range) => Self::status(self.target_distance(state).is_some_and(|distance| distance <= *range)),
+            NodeKind::CanSee(range) => {
+                let in_range = self.target_distance(state).is_some_and(|distance| distance <= *range);
+                Self::status(in_range && self.sees_target(state))
+            },
+            NodeKind::Wait(seconds) => {
+                let since = *state.memory[id].since.get_or_insert(state.time);
+                if state.time - since >= *seconds {
+                    state.memory[id].since = None;
+                    Status::Success
+                } else {
+                    Status::Running
+                }
+            },
+            NodeKind::Patrol => {
+                let waypoints = state.blackboard.points(WAYPOINTS);
+                if waypoints.is_empty() {
+                    return Status::Failure;
+                }
+                let index = state.memory[id].index % waypoints.len();
+                let (x, y) = waypoints[index];
+                let count = waypoints.len();
+                // Waypoints are where the entity stands, paths run between feet
+                let status = self.walk_to((x + FEET_OFFSET_X, y + FEET_OFFSET_Y));
+                if status == Status::Success {
+                    state.memory[id].index = (index + 1) % count;
+                }
+                status
+            },
+            NodeKind::Flee(key, distance) => {
+                let Some(threat) = self.goal(state, key) else { return Status::Failure };
+                let feet = self.positions[self.entity].feet();
+                if steering::length((feet.0 - threat.0, feet.1 - threat.1)) >= *distance {
+                    return Status::Success;
+                }
+                self.action = self.steering.steer(self.entity, steering::flee(feet, threat));
+                Status::Running
+            },
+            NodeKind::Wander => {
+                let direction = steering::wander(state.time, self.world.entities[self.entity].0);
+                self.action = self.steering.steer(self.entity, direction);
+                Status::Running
+            },
+        }
+    }
+
+    fn status(condition: bool) -> Status {
+        if condition { Status::Success } else { Status::Failure }
+    }
+
+    // Feet position of whatever `key` holds: an entity, or a point where one stands
+    fn goal(&self, state: &BehaviourState, key: &str) -> Option<(f32, f32)> {
+        match state.blackboard.get(key)? {
+            BlackboardValue::Entity(entity) => self.positions.get(*entity).map(Position::feet),
+            BlackboardValue::Point(x, y) => Some((x + FEET_OFFSET_X, y + FEET_OFFSET_Y)),
+            BlackboardValue::Points(_) => None,
+        }
+    }
+
+    // Distance between sprite origins, as AiState::Chase measures it
+    fn target_distance(&self, state: &BehaviourState) -> Option<f32> {
+        let target = self.positions.get(state.blackboard.entity(TARGET)?)?;
+        let position = &self.positions[self.entity];
+        let (dx, dy) = (target.x - position.x, target.y - position.y);
+        Some((dx * dx + dy * dy).sqrt())
+    }
+
+    // Entities with a Perception see what it saw this frame. Otherwise only walls
+    // block the view, and without a collision grid nothing does.
+    fn sees_target(&self, state: &BehaviourState) -> bool {
+        if let Some(perception) = self.perception {
+            return perception.sees_target;
+        }
+        let Some(target) = state.blackboard.entity(TARGET).and_then(|target| self.positions.get(target)) else { return false };
+        self.world.collision.is_none_or(|grid| grid.has_line_of_sight(self.positions[self.entity].feet(), target.feet()))
+    }
+
+    // Feet within ARRIVE_DISTANCE of a point
+    fn arrived(&self, goal: (f32, f32)) -> bool {
+        let (feet_x, feet_y) = self.positions[self.entity].feet();
+        let (dx, dy) = (goal.0 - feet_x, goal.1 - feet_y);
+        dx * dx + dy * dy <= ARRIVE_DISTANCE * ARRIVE_DISTANCE
+    }
+
+    fn walk_to(&mut self, goal: (f32, f32)) -> Status {
+        if self.arrived(goal) {
+            return Status::Success;
+        }
+        self.action = self.steering.travel(self.entity, self.world.entities[self.entity].0, goal, self.paths);
+        Status::Running
+    }
+
+    fn chase(&mut self, goal: (f32, f32), velocity: Vector) -> Status {
+        if self.arrived(goal) {
+            return Status::Success;
+        }
+        self.action = self.steering.chase(self.entity, self.world.entities[self.entity].0, goal, velocity, None, self.paths);
+        Status::Running
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::behaviour_tree::tree::NodeMemory;
+    use crate::behaviour_tree::{BehaviourNode, Blackboard};
+    use crate::components::{Entity, Faction, Health};
+    use crate::factions::{FactionDefinition, FactionTable, Relation};
+    use crate::navigation::PathOptions;
+
+    // The player (0) and one orc (1) running the tree under test, on open ground
+    struct Scene {
+        positions: Vec<Position>,
+        ais: Vec<Ai>,
+        action_states: Vec<ActionState>,
+        trees: HashMap<String, BehaviourTree>,
+        paths: PathCache,
+    }
+
+    impl Scene {
+        fn new(tree: &str) -> Self {
+            let root: BehaviourNode = ron::from_str(tree).unwrap();
+            let orc = BehaviourState::new("test", Blackboard::default());
+            Scene {
+                positions: vec![Position::new(0.0, 0.0, true), Position::new(300.0, 0.0, false)],
+                ais: vec![Ai { behavior: AiState::Idle }, Ai { behavior: AiState::Behaviour(orc) }],
+                action_states: vec![ActionState::None, ActionState::None],
+                trees: HashMap::from([("test".to_string(), BehaviourTree::compile(&root))]),
+                paths: PathCache::new(PathOptions::default()),
+            }
+        }
+
+        // Puts the player this many pixels from the orc
+        fn player_at(&mut self, distance: f32) {
+            self.positions[0].x = self.positions[1].x - distance;
+        }
+
+        fn tick(&mut self, delta_time: f32) -> ActionState {
+            let entities = [Entity(0), Entity(1)];
+            let factions = [Some(Faction { name: "player".to_string() }), Some(Faction { name: "orcs".to_string() })];
+            let relations = FactionTable::new(HashMap::from([(
+                "orcs".to_string(),
+                FactionDefinition { relations: HashMap::from([("player".to_string(), Relation::Hostile)]), friendly_fire: false },
+            )]));
+            let world = AiWorld {
+                entities: &entities,
+                positions: &self.positions,
+                factions: &factions,
+                relations: &relations,
+                healths: &[Some(Health::new(10, 10)), None],
+                hurtboxes: &[None, None],
+                projectiles: &[None, None],
+                perceptions: &[None, None],
+                collision: None,
+            };
+            BehaviourSystem::run(&world, &mut self.ais, &mut self.action_states, Some(&self.trees), &mut self.paths, delta_time);
+            self.action_states[1].clone()
+        }
+
+        fn memory(&self, id: usize) -> NodeMemory {
+            match &self.ais[1].behavior {
+                AiState::Behaviour(state) => state.memory[id],
+                _ => unreachable!(),
+            }
+        }
+    }
+
+    #[test]
+    fn selector_prefers_the_first_branch_that_does_not_fail() {
+        let mut scene = Scene::new("Selector([Sequence([InRange(50.0), Attack]), Wander])");
+        assert!(matches!(scene.tick(0.1), ActionState::Steering { .. }));
+        scene.player_at(40.0);
+        assert_eq!(scene.tick(0.1), ActionState::Attacking);
+    }
+
+    #[test]
+    fn sequences_recheck_conditions_before_the_running_child() {
+        // Root 0, InRange 1, Wait 2, Attack 3
+        let mut scene = Scene::new("Sequence([InRange(100.0), Wait(1.0), Attack])");
+        scene.player_at(50.0);
+        assert_eq!(scene.tick(0.5), ActionState::None);
+        assert_eq!(scene.memory(0).index, 1);
+
+        // The player stepping away interrupts the wait, which starts over
+        scene.player_at(200.0);
+        scene.tick(0.5);
+        assert_eq!(scene.memory(0).index, 0);
+        assert!(scene.memory(2).since.is_none());
+
+        scene.player_at(50.0);
+        assert_eq!(scene.tick(0.5), ActionState::None);
+        assert_eq!(scene.tick(0.5), ActionState::None);
+        assert_eq!(scene.tick(0.5), ActionState::Attacking);
+    }
+
+    #[test]
+    fn cooldown_fails_until_its_time_is_up() {
+        let mut scene = Scene::new("Cooldown(seconds: 1.0, child: Attack)");
+        assert_eq!(scene.tick(0.1), ActionState::Attacking);
+        assert_eq!(scene.tick(0.5), ActionState::None);
+        assert_eq!(scene.tick(0.6), ActionState::Attacking);
+    }
+
+    #[test]
+    fn repeat_runs_its_child_the_given_number_of_times() {
+        // Root 0, Inverter 1, InRange 2, Repeat 3, Attack 4
+        let mut scene = Scene::new("Sequence([Inverter(InRange(50.0)), Repeat(times: 2, child: Attack)])");
+        scene.tick(0.1);
+        assert_eq!(scene.memory(3).index, 1);
+        assert_eq!(scene.memory(0).index, 1);
+        scene.tick(0.1);
+        assert_eq!(scene.memory(3).index, 0);
+        assert_eq!(scene.memory(0).index, 0);
+
+        // Once the Inverter fails the Repeat isn't reached and starts over
+        scene.tick(0.1);
+        assert_eq!(scene.memory(3).index, 1);
+        scene.player_at(10.0);
+        assert_eq!(scene.tick(0.1), ActionState::None);
+        assert_eq!(scene.memory(3).index, 0);
+    }
+
+    #[test]
+    fn parallel_fails_as_soon_as_one_child_does() {
+        let mut scene = Scene::new("Selector([Parallel([Wait(1.0), InRange(50.0)]), Attack])");
+        assert_eq!(scene.tick(0.1), ActionState::Attacking);
+        scene.player_at(10.0);
+        assert_eq!(scene.tick(0.1), ActionState::None);
+        match &scene.ais[1].behavior {
+            AiState::Behaviour(state) => assert_eq!(state.blackboard.entity(TARGET), Some(0)),
+            _ => unreachable!(),
+        }
+    }
+
+    #[test]
+    fn unknown_trees_leave_the_entity_standing() {
+        let mut scene = Scene::new("Attack");
+        scene.trees.clear();
+        scene.action_states[1] = ActionState::Attacking;
+        assert_eq!(scene.tick(0.1), ActionState::None);
+    }
+}
@@ -0,0 +1,156 @@
+use crate::assets::AssetServer;
+use crate::components::position::{FEET_OFFSET_X, FEET_OFFSET_Y};
+use crate::components::tilemap::Tilemap;
+use crate::editor::{Editor, EditorTool, PALETTE_COLUMNS, PALETTE_PADDING, PALETTE_TILE_SIZE, PALETTE_WIDTH, SCREEN_HEIGHT, SPAWN_MARKER_RADIUS};
+use crate::level_definitions::LevelDefinition;
+use sdl2::pixels::Color;
+use sdl2::rect::Rect;
+use sdl2::render::{BlendMode, Canvas};
+use sdl2::video::Window;
+
+const TOOLS: [EditorTool; 5] = [
+    EditorTool::Paint,
+    EditorTool::Erase,
+    EditorTool::Fill,
+    EditorTool::Rectangle,
+    EditorTool::Spawn,
+];
+
+// Draws the editor overlay on top of the normal scene: tile cursor, rectangle preview,
+// spawn markers, the tool bar and the tile palette
+pub struct EditorRenderSystem;
+
+impl EditorRenderSystem {
+    pub fn render<'a>(
+        canvas: &mut Canvas<Window>,
+        assets: &AssetServer<'a>,
+        editor: &Editor,
+        tilemap: &Tilemap<'a>,
+        level: Option<&LevelDefinition>,
+        camera_x: i32,
+        camera_y: i32
+    ) {
+        canvas.set_blend_mode(BlendMode::Blend);
+
+        if let Some(level) = level {
+            Self::render_spawns(canvas, level, camera_x, camera_y);
+        }
+        Self::render_cursor(canvas, editor, tilemap, camera_x, camera_y);
+        Self::render_toolbar(canvas, editor);
+        Self::render_palette(canvas, assets, editor, tilemap);
+
+        canvas.set_blend_mode(BlendMode::None);
+    }
+
+    fn render_spawns(canvas: &mut Canvas<Window>, level: &LevelDefinition, camera_x: i32, camera_y: i32) {
+        let size = (SPAWN_MARKER_RADIUS * 2.0) as u32;
+        for spawn in &level.spawns {
+            let color = if spawn.entity == "player" {
+                Color::RGBA(0, 200, 0, 200)
+            } else {
+                Color::RGBA(220, 40, 40, 200)
+            };
+            let marker = Rect::new(
+                (spawn.x + FEET_OFFSET_X - SPAWN_MARKER_RADIUS) as i32 - camera_x,
+                (spawn.y + FEET_OFFSET_Y - SPAWN_MARKER_RADIUS) as i32 - camera_y,
+                size,
+                size
+            );
+            canvas.set_draw_color(color);
+            let _ = canvas.fill_rect(marker);
+
+            // Patrol routes as connected lines between waypoints
+            if let Some(patrol) = &spawn.patrol {
+                let points: Vec<sdl2::rect::Point> = patrol.iter()
+                    .map(|(x, y)| sdl2::rect::Point::new(
+                        (x + FEET_OFFSET_X) as i32 - camera_x,
+                        (y + FEET_OFFSET_Y) as i32 - camera_y
+                    ))
+                    .collect();
+                let _ = canvas.draw_lines(points.as_slice());
+            }
+        }
+    }
+
+    fn render_cursor<'a>(canvas: &mut Canvas<Window>, editor: &Editor, tilemap: &Tilemap<'a>, camera_x: i32, camera_y: i32) {
+        if editor.tool == EditorTool::Spawn {
+            return;
+        }
+        let hovered = match editor.hovered_tile(tilemap, camera_x, camera_y) {
+            Some(tile) => tile,
+            None => return,
+        };
+
+        // While dragging a rectangle, outline the whole area it will fill
+        let (start, end) = match editor.rect_start {
+            Some(start) => (start, hovered),
+            None => (hovered, hovered),
+        };
+        let tile_size = tilemap.tile_size as i32;
+        let min_x = start.0.min(end.0) as i32;
+        let min_y = start.1.min(end.1) as i32;
+        let width = (start.0.max(end.0) as i32 - min_x + 1) * tile_size;
+        let height = (start.1.max(end.1) as i32 - min_y + 1) * tile_size;
+        let area = Rect::new(
+            min_x * tile_size - camera_x,
+            min_y * tile_size - camera_y,
+            width as u32,
+            height as u32
+        );
+
+        canvas.set_draw_color(Color::RGBA(255, 255, 255, 60));
+        let _ = canvas.fill_rect(area);
+        canvas.set_draw_color(Color::RGBA(255, 255, 255, 220));
+        let _ = canvas.draw_rect(area);
+    }
+
+    fn render_toolbar(canvas: &mut Canvas<Window>, editor: &Editor) {
+        for (i, tool) in TOOLS.iter().enumerate() {
+            let slot = Rect::new(8 + i as i32 * 20, 8, 16, 16);
+            canvas.set_draw_color(if *tool == editor.tool {
+                Color::RGBA(255, 220, 0, 230)
+            } else {
+                Color::RGBA(80, 80, 80, 200)
+            });
+            let _ = canvas.fill_rect(slot);
+        }
+    }
+
+    fn render_palette<'a>(canvas: &mut Canvas<Window>, assets: &AssetServer<'a>, editor: &Editor, tilemap: &Tilemap<'a>) {
+        let panel_x = Editor::palette_x();
+        canvas.set_draw_color(Color::RGBA(20, 20, 20, 220));
+        let _ = canvas.fill_rect(Rect::new(panel_x, 0, PALETTE_WIDTH as u32, SCREEN_HEIGHT as u32));
+
+        let (tileset, texture) = match tilemap.tileset.as_ref().and_then(|handle| assets.tileset_texture(handle)) {
+            Some(loaded) => loaded,
+            None => return,
+        };
+
+        let tile_count = (tileset.columns * tileset.rows) as i32;
+        let visible_rows = (SCREEN_HEIGHT - PALETTE_PADDING * 2) / PALETTE_TILE_SIZE;
+        for row in 0..visible_rows {
+            for column in 0..PALETTE_COLUMNS {
+                let index = (row + editor.palette_scroll) * PALETTE_COLUMNS + column;
+                if index >= tile_count {
+                    return;
+                }
+
+                let dest = Rect::new(
+                    panel_x + PALETTE_PADDING + column * PALETTE_TILE_SIZE,
+                    PALETTE_PADDING + row * PALETTE_TILE_SIZE,
+                    PALETTE_TILE_SIZE as u32,
+                    PALETTE_TILE_SIZE as u32
+                );
+                if let Some(src) = tileset.get_tile_rect(index as u32) {
+                    let _ = canvas.copy(&texture.handle, Some(src), Some(dest));
+                }
+
+                // Palette index 0 is tile ID 1
+                if editor.selected_tile.0 == index as u32 + 1 {
+                    canvas.set_draw_color(Color::RGBA(255, 220, 0, 255));
+                    let _ = canvas.draw_rect(dest);
+                }
+            }
+        }
+    }
+}
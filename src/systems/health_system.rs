@@ -3,9 +3,10 @@ use crate::components::{Entity, Health};
 pub struct HealthSystem;
 
 impl HealthSystem {
-    pub fn update(entities: &[Entity], healths: &mut [Health], delta_time: f32) {
+    pub fn update(entities: &[Entity], healths: &mut [Option<Health>], delta_time: f32) {
         for (i, _entity) in entities.iter().enumerate() {
-            if let Some(health) = healths.get_mut(i) {
+            // Entities without a Health component can't be hurt
+            if let Some(health) = healths.get_mut(i).and_then(Option::as_mut) {
                 // Process damage queue
                 if !health.damage_queue.is_empty() {
                     // Only apply damage if not invulnerable
@@ -58,15 +59,15 @@ impl HealthSystem {
     }
     
     // Convenience method to deal damage to an entity
-    pub fn deal_damage(healths: &mut [Health], target_idx: usize, amount: u32) {
-        if let Some(health) = healths.get_mut(target_idx) {
+    pub fn deal_damage(healths: &mut [Option<Health>], target_idx: usize, amount: u32) {
+        if let Some(health) = healths.get_mut(target_idx).and_then(Option::as_mut) {
             health.damage_queue.push(amount);
         }
     }
     
     // Convenience method to heal an entity
-    pub fn heal(healths: &mut [Health], target_idx: usize, amount: u32) {
-        if let Some(health) = healths.get_mut(target_idx) {
+    pub fn heal(healths: &mut [Option<Health>], target_idx: usize, amount: u32) {
+        if let Some(health) = healths.get_mut(target_idx).and_then(Option::as_mut) {
             health.healing_queue.push(amount);
         }
     }
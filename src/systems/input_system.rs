@@ -25,6 +25,7 @@ impl InputSystem {
                 let mut up = false;
                 let mut down = false;
                 let mut attack = false;
+                let mut shoot = false;
                 
                 // Check each key binding - CHANGE THIS PART
                 for (scancode, action) in &bindings.keys { // Use keys not bindings
@@ -35,6 +36,7 @@ impl InputSystem {
                             GameAction::MoveUp => up = true,
                             GameAction::MoveDown => down = true,
                             GameAction::Attack => attack = true,
+                            GameAction::Shoot => shoot = true,
                         }
                     }
                 }
@@ -48,6 +50,8 @@ impl InputSystem {
                 // Set appropriate action state
                 if attack {
                     action_states[i] = ActionState::Attacking;
+                } else if shoot {
+                    action_states[i] = ActionState::Shooting;
                 } else if right || left || up || down {
                     action_states[i] = ActionState::Moving { right, left, up, down };
                 }
@@ -0,0 +1,63 @@
+use crate::components::{Light, Position, Sprite};
+use sdl2::pixels::Color;
+use sdl2::rect::Rect;
+use sdl2::render::{BlendMode, Canvas};
+use sdl2::video::Window;
+
+// Rings per light; each one adds a little more colour towards the centre
+const LIGHT_RINGS: u32 = 8;
+
+pub struct LightSystem;
+
+impl LightSystem {
+    // Additive-looking glow drawn over the entities: stacked translucent discs, so
+    // the centre ends up brightest and the edge fades out
+    pub fn render(
+        canvas: &mut Canvas<Window>,
+        positions: &[Position],
+        sprites: &[Option<Sprite>],
+        lights: &[Option<Light>],
+        camera_x: i32,
+        camera_y: i32
+    ) {
+        canvas.set_blend_mode(BlendMode::Blend);
+        for (i, (position, light)) in positions.iter().zip(lights).enumerate() {
+            let Some(light) = light else { continue };
+            let (x, y) = Self::center(position, sprites.get(i).copied().flatten());
+            let alpha = ((light.intensity.clamp(0.0, 1.0) * 255.0) as u32 / LIGHT_RINGS) as u8;
+            let (r, g, b) = light.color;
+            canvas.set_draw_color(Color::RGBA(r, g, b, alpha));
+
+            for ring in 1..=LIGHT_RINGS {
+                let radius = light.radius * ring as f32 / LIGHT_RINGS as f32;
+                let _ = canvas.fill_rects(&Self::disc(x - camera_x, y - camera_y, radius));
+            }
+        }
+        canvas.set_blend_mode(BlendMode::None);
+    }
+
+    // Sprites glow from their middle; characters from their feet
+    fn center(position: &Position, sprite: Option<Sprite>) -> (i32, i32) {
+        match sprite {
+            Some(sprite) => (
+                (position.x + sprite.frame_width as f32 * sprite.scale / 2.0) as i32,
+                (position.y + sprite.frame_height as f32 * sprite.scale / 2.0) as i32,
+            ),
+            None => {
+                let (feet_x, feet_y) = position.feet();
+                (feet_x as i32, feet_y as i32)
+            },
+        }
+    }
+
+    // A filled circle as one rect per row
+    fn disc(center_x: i32, center_y: i32, radius: f32) -> Vec<Rect> {
+        let radius_px = radius as i32;
+        (-radius_px..=radius_px)
+            .map(|dy| {
+                let half_width = (radius * radius - (dy * dy) as f32).max(0.0).sqrt() as i32;
+                Rect::new(center_x - half_width, center_y + dy, (half_width * 2).max(1) as u32, 1)
+            })
+            .collect()
+    }
+}
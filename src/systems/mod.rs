@@ -4,6 +4,18 @@ pub mod health_system;
 pub mod render_system;
 pub mod tilemap_system;
 pub mod ai_system;
+pub mod trigger_system;
+pub mod pickup_system;
+pub mod terrain_system;
+pub mod editor_render_system;
+pub mod velocity_system;
+pub mod light_system;
+pub mod behaviour_system;
+pub mod perception_system;
+pub mod utility_system;
+pub mod projectile_system;
+pub mod spawner_system;
+pub mod ai_world;
 
 pub use self::input_system::InputSystem;
 pub use self::movement_system::MovementSystem;
@@ -1,5 +1,8 @@
-use crate::components::{ActionState, Entity, Position, Tilemap, TileType, TileId};
-use std::collections::HashMap;
+use crate::components::{ActionState, CollisionGrid, Entity, Position};
+
+// Fraction of full speed an entity has to move sideways before it turns to face
+// that way
+const FACING_THRESHOLD: f32 = 0.2;
 
 pub struct MovementSystem;
 
@@ -8,43 +11,47 @@ impl MovementSystem {
         entities: &[Entity], 
         positions: &mut [Position], 
         action_states: &[ActionState],
-        tilemap: Option<&Tilemap>,
-        tile_types: Option<&HashMap<TileId, TileType>>
+        collision: Option<&CollisionGrid>
     ) {
         for (i, _) in entities.iter().enumerate() {
             if let Some(position) = positions.get_mut(i) {
                 if let Some(action_state) = action_states.get(i) {
                     println!("MovementSystem processing entity {} with state: {:?}", i, action_state);
                     
-                    match action_state {
-                        ActionState::Moving { right, left, up, down } => {
-                            // Calculate movement vector
-                            let mut dx = 0.0;
-                            let mut dy = 0.0;
-                            
-                            if *right { dx += position.speed; }
-                            if *left { dx -= position.speed; }
-                            if *up { dy -= position.speed; }
-                            if *down { dy += position.speed; }
-                            
-                            // Debug output
-                            if dx != 0.0 || dy != 0.0 {
-                                println!("Moving entity {} by ({}, {})", i, dx, dy);
-                            }
-                            
-                            // Update position
-                            position.x += dx;
-                            position.y += dy;
-                            
-                            // Update facing direction
-                            if dx != 0.0 {
-                                position.facing_right = dx > 0.0;
-                            }
-                        },
-                        _ => {}
+                    // Keys and AI steering both come down to a movement vector
+                    let (dx, dy) = action_state.velocity(position.speed);
+                    
+                    // Debug output
+                    if dx != 0.0 || dy != 0.0 {
+                        println!("Moving entity {} by ({}, {})", i, dx, dy);
+                    }
+                    
+                    // Update position, one axis at a time so entities slide along walls
+                    if !Self::blocked(collision, position, dx, 0.0) {
+                        position.x += dx;
+                    }
+                    if !Self::blocked(collision, position, 0.0, dy) {
+                        position.y += dy;
+                    }
+                    
+                    // Update facing direction. Tiny sideways drift while steering
+                    // mostly up or down doesn't turn the sprite around.
+                    if dx.abs() > FACING_THRESHOLD * position.speed {
+                        position.facing_right = dx > 0.0;
                     }
                 }
             }
         }
     }
+    
+    // Would moving by (dx, dy) put the entity's feet inside a solid tile?
+    fn blocked(collision: Option<&CollisionGrid>, position: &Position, dx: f32, dy: f32) -> bool {
+        match collision {
+            Some(grid) => {
+                let (feet_x, feet_y) = position.feet();
+                grid.is_solid_at(feet_x + dx, feet_y + dy)
+            },
+            None => false,
+        }
+    }
 }
\ No newline at end of file
@@ -0,0 +1,196 @@
+use crate::components::ai::Ai;
+use crate::components::{CollisionGrid, Noise, Perception, Position};
+
+pub struct PerceptionSystem;
+
+impl PerceptionSystem {
+    // Update what every entity with a Perception knows about its AI's target.
+    // Without a target it listens for anyone; if its AI picks one of those up, the
+    // noise is where the target was last heard.
+    pub fn run(
+        positions: &[Position],
+        ais: &[Ai],
+        perceptions: &mut [Option<Perception>],
+        noises: &[Noise],
+        collision: Option<&CollisionGrid>,
+        delta_time: f32,
+    ) {
+        for (i, perception) in perceptions.iter_mut().enumerate() {
+            let Some(perception) = perception else { continue };
+            let listener = positions[i].feet();
+            let audible = |noise: &&Noise| {
+                let (dx, dy) = (noise.x - listener.0, noise.y - listener.1);
+                (dx * dx + dy * dy).sqrt() <= perception.hearing_radius * noise.volume
+            };
+            let previously_heard = std::mem::take(&mut perception.heard);
+            let target = ais[i].target().filter(|&target| target != i && target < positions.len());
+            let Some(target) = target else {
+                perception.sees_target = false;
+                perception.last_known = None;
+                perception.heard = noises.iter()
+                    .filter(|noise| noise.source != i)
+                    .filter(audible)
+                    .copied()
+                    .collect();
+                continue;
+            };
+
+            perception.sees_target = Self::can_see(perception, &positions[i], &positions[target], collision);
+            let heard = noises.iter()
+                .chain(&previously_heard)
+                .filter(|noise| noise.source == target)
+                .find(audible);
+
+            if perception.sees_target {
+                perception.last_known = Some(positions[target].feet());
+                perception.since_noticed = 0.0;
+            } else if let Some(noise) = heard {
+                perception.last_known = Some((noise.x, noise.y));
+                perception.since_noticed = 0.0;
+            } else if perception.last_known.is_some() {
+                perception.since_noticed += delta_time;
+                if perception.since_noticed > perception.memory {
+                    perception.last_known = None;
+                }
+            }
+        }
+    }
+
+    // In range, inside the vision cone and not behind a wall. Without a collision
+    // grid nothing blocks the view.
+    pub fn can_see(perception: &Perception, position: &Position, target: &Position, collision: Option<&CollisionGrid>) -> bool {
+        let eye = position.feet();
+        let seen = target.feet();
+        let (dx, dy) = (seen.0 - eye.0, seen.1 - eye.1);
+        let distance = (dx * dx + dy * dy).sqrt();
+        if distance > perception.view_range {
+            return false;
+        }
+
+        // Cosine of the angle between the facing direction and the target
+        let facing = if position.facing_right { 1.0 } else { -1.0 };
+        let half_angle = (perception.view_angle / 2.0).to_radians();
+        if distance > 0.0 && dx * facing / distance < half_angle.cos() {
+            return false;
+        }
+
+        collision.is_none_or(|grid| grid.has_line_of_sight(eye, seen))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::components::ai::{AiState, Chase};
+    use crate::components::position::{FEET_OFFSET_X, FEET_OFFSET_Y};
+    use crate::navigation::grid;
+
+    // Someone whose feet are at (x, y)
+    fn standing(x: f32, y: f32, facing_right: bool) -> Position {
+        Position::new(x - FEET_OFFSET_X, y - FEET_OFFSET_Y, facing_right)
+    }
+
+    // Sees 200px through a 90 degree cone, hears 100px and remembers for 2 seconds
+    fn perception() -> Perception {
+        Perception::new(200.0, 90.0, 100.0, 2.0)
+    }
+
+    // The player (0) and an orc (1) with a Perception, after `target` if given
+    fn ais(target: Option<usize>) -> Vec<Ai> {
+        let mut chase = Chase::new(200.0, 40.0, (0.0, 0.0));
+        chase.target_entity = target;
+        vec![Ai { behavior: AiState::Idle }, Ai { behavior: AiState::Chase(chase) }]
+    }
+
+    fn noise(x: f32, y: f32, volume: f32, source: usize) -> Noise {
+        Noise { x, y, volume, source }
+    }
+
+    #[test]
+    fn sees_within_range_and_the_vision_cone() {
+        let perception = perception();
+        let orc = standing(100.0, 100.0, true);
+        assert!(PerceptionSystem::can_see(&perception, &orc, &standing(250.0, 100.0, true), None));
+        assert!(!PerceptionSystem::can_see(&perception, &orc, &standing(350.0, 100.0, true), None));
+
+        // 45 degrees either side of straight ahead
+        assert!(PerceptionSystem::can_see(&perception, &orc, &standing(200.0, 190.0, true), None));
+        assert!(!PerceptionSystem::can_see(&perception, &orc, &standing(150.0, 190.0, true), None));
+        assert!(!PerceptionSystem::can_see(&perception, &orc, &standing(50.0, 100.0, true), None));
+        assert!(PerceptionSystem::can_see(&perception, &standing(100.0, 100.0, false), &standing(50.0, 100.0, true), None));
+
+        // Something standing right on top of it is always seen
+        assert!(PerceptionSystem::can_see(&perception, &orc, &standing(100.0, 100.0, true), None));
+    }
+
+    #[test]
+    fn walls_block_the_view() {
+        let collision = grid(&[
+            ".....",
+            "..#..",
+            ".....",
+        ]);
+        let perception = perception();
+        let orc = standing(16.0, 48.0, true);
+        assert!(!PerceptionSystem::can_see(&perception, &orc, &standing(144.0, 48.0, true), Some(&collision)));
+        assert!(PerceptionSystem::can_see(&perception, &standing(16.0, 16.0, true), &standing(144.0, 16.0, true), Some(&collision)));
+    }
+
+    #[test]
+    fn remembers_a_lost_target_for_a_while() {
+        let ais = ais(Some(0));
+        let mut perceptions = vec![None, Some(perception())];
+        let mut positions = vec![standing(150.0, 100.0, true), standing(100.0, 100.0, true)];
+        PerceptionSystem::run(&positions, &ais, &mut perceptions, &[], None, 0.5);
+        let seen = perceptions[1].clone().unwrap();
+        assert!(seen.sees_target);
+        assert_eq!(seen.last_known, Some((150.0, 100.0)));
+
+        // Behind it now, and silent
+        positions[0] = standing(50.0, 100.0, true);
+        for _ in 0..4 {
+            PerceptionSystem::run(&positions, &ais, &mut perceptions, &[], None, 0.5);
+        }
+        let lost = perceptions[1].clone().unwrap();
+        assert!(!lost.sees_target);
+        assert_eq!(lost.last_known, Some((150.0, 100.0)));
+        assert_eq!(lost.since_noticed, 2.0);
+
+        PerceptionSystem::run(&positions, &ais, &mut perceptions, &[], None, 0.5);
+        assert_eq!(perceptions[1].as_ref().unwrap().last_known, None);
+    }
+
+    #[test]
+    fn hearing_the_target_updates_where_it_was() {
+        let ais = ais(Some(0));
+        let mut perceptions = vec![None, Some(perception())];
+        let positions = vec![standing(50.0, 100.0, true), standing(100.0, 100.0, true)];
+
+        // Too quiet to carry that far
+        PerceptionSystem::run(&positions, &ais, &mut perceptions, &[noise(50.0, 100.0, 0.4, 0)], None, 0.5);
+        assert_eq!(perceptions[1].as_ref().unwrap().last_known, None);
+
+        PerceptionSystem::run(&positions, &ais, &mut perceptions, &[noise(50.0, 100.0, 1.0, 0)], None, 0.5);
+        let heard = perceptions[1].as_ref().unwrap();
+        assert!(!heard.sees_target);
+        assert_eq!(heard.last_known, Some((50.0, 100.0)));
+        assert_eq!(heard.since_noticed, 0.0);
+    }
+
+    #[test]
+    fn listens_for_anyone_without_a_target() {
+        let ais = ais(None);
+        let mut perceptions = vec![None, Some(perception())];
+        let positions = vec![standing(300.0, 100.0, true), standing(100.0, 100.0, true)];
+        let noises = [
+            noise(180.0, 100.0, 1.0, 0),  // In range
+            noise(300.0, 100.0, 1.0, 0),  // Too far
+            noise(300.0, 100.0, 3.0, 0),  // Far, but loud
+            noise(100.0, 100.0, 1.0, 1),  // Its own
+        ];
+        PerceptionSystem::run(&positions, &ais, &mut perceptions, &noises, None, 0.5);
+        let heard: Vec<f32> = perceptions[1].as_ref().unwrap().heard.iter().map(|noise| noise.volume).collect();
+        assert_eq!(heard, [1.0, 3.0]);
+        assert!(perceptions[1].as_ref().unwrap().last_known.is_none());
+    }
+}
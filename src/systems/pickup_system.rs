@@ -0,0 +1,63 @@
+use crate::components::{Inventory, Position};
+use crate::level_definitions::PickupDefinition;
+use sdl2::pixels::Color;
+use sdl2::rect::Rect;
+use sdl2::render::Canvas;
+use sdl2::video::Window;
+
+// How close the player's feet have to get to an item to pick it up
+const PICKUP_RADIUS: f32 = 24.0;
+const PICKUP_SIZE: u32 = 12;
+
+pub struct PickupSystem;
+
+impl PickupSystem {
+    // Move every item the player is standing on into their inventory. Returns what
+    // was picked up.
+    pub fn collect(pickups: &mut Vec<PickupDefinition>, player: &Position, inventory: &mut Inventory) -> Vec<PickupDefinition> {
+        let (feet_x, feet_y) = player.feet();
+        let (collected, remaining): (Vec<PickupDefinition>, Vec<PickupDefinition>) = pickups.drain(..)
+            .partition(|pickup| (pickup.x - feet_x).hypot(pickup.y - feet_y) <= PICKUP_RADIUS);
+        *pickups = remaining;
+
+        for pickup in &collected {
+            inventory.add(&pickup.item, pickup.count);
+        }
+        collected
+    }
+
+    pub fn render(canvas: &mut Canvas<Window>, pickups: &[PickupDefinition], camera_x: i32, camera_y: i32) {
+        canvas.set_draw_color(Color::RGB(160, 110, 50));
+        for pickup in pickups {
+            let half = PICKUP_SIZE as i32 / 2;
+            let rect = Rect::new(pickup.x as i32 - half - camera_x, pickup.y as i32 - half - camera_y, PICKUP_SIZE, PICKUP_SIZE);
+            let _ = canvas.fill_rect(rect);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn plank(x: f32, y: f32) -> PickupDefinition {
+        PickupDefinition { item: "plank".to_string(), count: 2, x, y }
+    }
+
+    #[test]
+    fn collects_only_what_the_player_stands_on() {
+        let player = Position::new(0.0, 0.0, true);
+        let (feet_x, feet_y) = player.feet();
+        let mut pickups = vec![plank(feet_x + 10.0, feet_y), plank(feet_x + 100.0, feet_y)];
+        let mut inventory = Inventory::default();
+
+        let collected = PickupSystem::collect(&mut pickups, &player, &mut inventory);
+        assert_eq!(collected.len(), 1);
+        assert_eq!(pickups.len(), 1);
+        assert_eq!(inventory.count("plank"), 2);
+
+        // Nothing left in reach
+        assert!(PickupSystem::collect(&mut pickups, &player, &mut inventory).is_empty());
+        assert_eq!(inventory.count("plank"), 2);
+    }
+}
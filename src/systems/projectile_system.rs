@@ -0,0 +1,284 @@
+use crate::components::{CollisionGrid, Damage, Entity, Faction, Health, Hurtbox, Position, Projectile, Sprite, TileHit, Velocity};
+use crate::factions::FactionTable;
+use crate::systems::health_system::HealthSystem;
+
+// The columns ProjectileSystem only reads, one entry per entity
+pub struct ProjectileWorld<'w> {
+    pub entities: &'w [Entity],
+    pub damages: &'w [Option<Damage>],
+    pub sprites: &'w [Option<Sprite>],
+    pub hurtboxes: &'w [Option<Hurtbox>],
+    pub factions: &'w [Option<Faction>],
+    pub relations: &'w FactionTable,
+    pub collision: Option<&'w CollisionGrid>,
+}
+
+pub struct ProjectileSystem;
+
+impl ProjectileSystem {
+    // Runs after VelocitySystem has moved everything. Projectiles hit at their
+    // sprite's centre: walls stop or bounce them, hurtboxes take their Damage.
+    // A projectile is on its shooter's side and flies through anyone that side
+    // can't hurt (see FactionTable::can_damage).
+    // Returns the indices of projectiles that are used up, in ascending order, for
+    // the caller to remove.
+    pub fn run(
+        world: &ProjectileWorld,
+        positions: &mut [Position],
+        velocities: &mut [Option<Velocity>],
+        projectiles: &mut [Option<Projectile>],
+        healths: &mut [Option<Health>],
+        delta_time: f32,
+    ) -> Vec<usize> {
+        let ProjectileWorld { entities, damages, sprites, hurtboxes, factions, relations, collision } = *world;
+        let mut spent = Vec::new();
+        for i in 0..projectiles.len() {
+            let Some(projectile) = &mut projectiles[i] else { continue };
+            projectile.lifetime -= delta_time;
+            if projectile.lifetime <= 0.0 {
+                spent.push(i);
+                continue;
+            }
+
+            let sprite = sprites[i].unwrap_or_default();
+            if let (Some(grid), Some(velocity)) = (collision, &mut velocities[i]) {
+                let (x, y) = sprite.center(&positions[i]);
+                if grid.is_solid_at(x, y) {
+                    match projectile.on_tile {
+                        TileHit::Stop => {
+                            spent.push(i);
+                            continue;
+                        },
+                        TileHit::Bounce => Self::bounce(grid, &mut positions[i], &sprite, velocity, delta_time),
+                    }
+                }
+            }
+
+            let center = sprite.center(&positions[i]);
+            let amount = damages[i].map_or(0, |damage| damage.amount);
+            for target in 0..entities.len() {
+                let id = entities[target].0;
+                if target == i || projectile.owner == Some(id) || projectile.hit.contains(&id) {
+                    continue;
+                }
+                let Some(hurtbox) = &hurtboxes[target] else { continue };
+                let alive = healths[target].as_ref().is_some_and(|health| !health.is_dead);
+                let can_damage = relations.can_damage(factions[i].as_ref(), factions[target].as_ref());
+                if !alive || !can_damage || !hurtbox.contains(&positions[target], center) {
+                    continue;
+                }
+
+                HealthSystem::deal_damage(healths, target, amount);
+                projectile.hit.push(id);
+                if projectile.pierce == 0 {
+                    spent.push(i);
+                    break;
+                }
+                projectile.pierce -= 1;
+            }
+        }
+        spent
+    }
+
+    // Step back out of the wall and reflect off whichever side was hit: flip the
+    // horizontal speed if moving only sideways would have hit it, the vertical speed
+    // if moving only up or down would, and both for a corner
+    fn bounce(grid: &CollisionGrid, position: &mut Position, sprite: &Sprite, velocity: &mut Velocity, delta_time: f32) {
+        let (dx, dy) = (velocity.x * delta_time, velocity.y * delta_time);
+        position.x -= dx;
+        position.y -= dy;
+        let (x, y) = sprite.center(position);
+        let blocked_x = grid.is_solid_at(x + dx, y);
+        let blocked_y = grid.is_solid_at(x, y + dy);
+        if blocked_x || !blocked_y {
+            velocity.x = -velocity.x;
+        }
+        if blocked_y || !blocked_x {
+            velocity.y = -velocity.y;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::factions::FactionDefinition;
+    use crate::navigation::grid;
+    use std::collections::HashMap;
+
+    // Projectiles with an empty sprite hit at their position
+    const POINT: Sprite = Sprite { frame_width: 0, frame_height: 0, origin: (0, 0), scale: 1.0 };
+
+    #[derive(Default)]
+    struct Scene {
+        entities: Vec<Entity>,
+        positions: Vec<Position>,
+        velocities: Vec<Option<Velocity>>,
+        projectiles: Vec<Option<Projectile>>,
+        healths: Vec<Option<Health>>,
+        damages: Vec<Option<Damage>>,
+        sprites: Vec<Option<Sprite>>,
+        hurtboxes: Vec<Option<Hurtbox>>,
+        factions: Vec<Option<Faction>>,
+        relations: FactionTable,
+        collision: Option<CollisionGrid>,
+    }
+
+    impl Scene {
+        fn new() -> Self {
+            let relations = ron::from_str::<HashMap<String, FactionDefinition>>(r#"{
+                "player": (relations: {"orcs": Hostile}),
+                "orcs": (relations: {"goblins": Friendly}, friendly_fire: true),
+                "goblins": (),
+            }"#).map(FactionTable::new).unwrap();
+            Scene { relations, ..Default::default() }
+        }
+
+        fn push(&mut self, x: f32, y: f32, faction: Option<&str>) -> usize {
+            let index = self.entities.len();
+            self.entities.push(Entity(index + 100));
+            self.positions.push(Position::new(x, y, true));
+            self.velocities.push(None);
+            self.projectiles.push(None);
+            self.healths.push(None);
+            self.damages.push(None);
+            self.sprites.push(None);
+            self.hurtboxes.push(None);
+            self.factions.push(faction.map(|name| Faction { name: name.to_string() }));
+            index
+        }
+
+        // A 32x32 hurtbox with its top-left at (x, y)
+        fn target(&mut self, x: f32, y: f32, faction: Option<&str>) -> usize {
+            let index = self.push(x, y, faction);
+            self.healths[index] = Some(Health::new(100, 100));
+            self.hurtboxes[index] = Some(Hurtbox { width: 32.0, height: 32.0, offset_x: 0.0, offset_y: 0.0 });
+            index
+        }
+
+        fn projectile(&mut self, (x, y): (f32, f32), (vx, vy): (f32, f32), projectile: Projectile, faction: Option<&str>) -> usize {
+            let index = self.push(x, y, faction);
+            self.velocities[index] = Some(Velocity { x: vx, y: vy });
+            self.projectiles[index] = Some(projectile);
+            self.damages[index] = Some(Damage { amount: 10 });
+            self.sprites[index] = Some(POINT);
+            index
+        }
+
+        fn run(&mut self, delta_time: f32) -> Vec<usize> {
+            let world = ProjectileWorld {
+                entities: &self.entities,
+                damages: &self.damages,
+                sprites: &self.sprites,
+                hurtboxes: &self.hurtboxes,
+                factions: &self.factions,
+                relations: &self.relations,
+                collision: self.collision.as_ref(),
+            };
+            ProjectileSystem::run(&world, &mut self.positions, &mut self.velocities, &mut self.projectiles, &mut self.healths, delta_time)
+        }
+
+        fn damage_taken(&self, index: usize) -> u32 {
+            self.healths[index].as_ref().map_or(0, |health| health.damage_queue.iter().sum())
+        }
+
+        fn velocity(&self, index: usize) -> (f32, f32) {
+            let velocity = self.velocities[index].unwrap();
+            (velocity.x, velocity.y)
+        }
+    }
+
+    #[test]
+    fn projectiles_expire_with_their_lifetime() {
+        let mut scene = Scene::new();
+        let arrow = scene.projectile((0.0, 0.0), (0.0, 0.0), Projectile::new(0.5, 0, TileHit::Stop), None);
+        assert!(scene.run(0.3).is_empty());
+        assert_eq!(scene.run(0.3), vec![arrow]);
+    }
+
+    #[test]
+    fn stopping_projectiles_are_spent_in_walls() {
+        let mut scene = Scene::new();
+        scene.collision = Some(grid(&["...#."]));
+        let arrow = scene.projectile((100.0, 16.0), (100.0, 0.0), Projectile::new(5.0, 0, TileHit::Stop), None);
+        assert_eq!(scene.run(0.1), vec![arrow]);
+    }
+
+    #[test]
+    fn bouncing_off_a_side_wall_flips_only_the_horizontal_speed() {
+        let mut scene = Scene::new();
+        scene.collision = Some(grid(&["...#.", "...#."]));
+        let arrow = scene.projectile((100.0, 48.0), (100.0, 50.0), Projectile::new(5.0, 0, TileHit::Bounce), None);
+        assert!(scene.run(0.1).is_empty());
+        assert_eq!(scene.velocity(arrow), (-100.0, 50.0));
+        // Stepped back out of the wall
+        assert_eq!((scene.positions[arrow].x, scene.positions[arrow].y), (90.0, 43.0));
+    }
+
+    #[test]
+    fn bouncing_off_a_floor_flips_only_the_vertical_speed() {
+        let mut scene = Scene::new();
+        scene.collision = Some(grid(&["...", "...", "###"]));
+        let arrow = scene.projectile((48.0, 70.0), (50.0, 100.0), Projectile::new(5.0, 0, TileHit::Bounce), None);
+        assert!(scene.run(0.1).is_empty());
+        assert_eq!(scene.velocity(arrow), (50.0, -100.0));
+    }
+
+    #[test]
+    fn bouncing_into_a_corner_flips_both() {
+        let mut scene = Scene::new();
+        scene.collision = Some(grid(&["...", "...", "..#"]));
+        let arrow = scene.projectile((70.0, 70.0), (100.0, 100.0), Projectile::new(5.0, 0, TileHit::Bounce), None);
+        assert!(scene.run(0.1).is_empty());
+        assert_eq!(scene.velocity(arrow), (-100.0, -100.0));
+    }
+
+    #[test]
+    fn piercing_counts_down_and_never_hits_twice() {
+        let mut scene = Scene::new();
+        let first = scene.target(0.0, 0.0, None);
+        let second = scene.target(0.0, 0.0, None);
+        let third = scene.target(0.0, 0.0, None);
+        let arrow = scene.projectile((16.0, 16.0), (0.0, 0.0), Projectile::new(5.0, 1, TileHit::Stop), None);
+
+        // Goes through the first, stops in the second
+        assert_eq!(scene.run(0.1), vec![arrow]);
+        assert_eq!((scene.damage_taken(first), scene.damage_taken(second), scene.damage_taken(third)), (10, 10, 0));
+
+        let mut scene = Scene::new();
+        let target = scene.target(0.0, 0.0, None);
+        let arrow = scene.projectile((16.0, 16.0), (0.0, 0.0), Projectile::new(5.0, 3, TileHit::Stop), None);
+        assert!(scene.run(0.1).is_empty());
+        assert!(scene.run(0.1).is_empty());
+        assert_eq!(scene.damage_taken(target), 10);
+        let projectile = scene.projectiles[arrow].as_ref().unwrap();
+        assert_eq!((projectile.pierce, projectile.hit.clone()), (2, vec![scene.entities[target].0]));
+    }
+
+    #[test]
+    fn projectiles_never_hit_their_owner() {
+        let mut scene = Scene::new();
+        let shooter = scene.target(0.0, 0.0, None);
+        let mut projectile = Projectile::new(5.0, 0, TileHit::Stop);
+        projectile.owner = Some(scene.entities[shooter].0);
+        scene.projectile((16.0, 16.0), (0.0, 0.0), projectile, None);
+        assert!(scene.run(0.1).is_empty());
+        assert_eq!(scene.damage_taken(shooter), 0);
+    }
+
+    #[test]
+    fn projectiles_pass_through_whoever_their_side_cannot_hurt() {
+        let mut scene = Scene::new();
+        let orc = scene.target(0.0, 0.0, Some("orcs"));
+        let goblin_arrow = scene.projectile((16.0, 16.0), (0.0, 0.0), Projectile::new(5.0, 0, TileHit::Stop), Some("goblins"));
+        assert!(scene.run(0.1).is_empty());
+        assert_eq!(scene.damage_taken(orc), 0);
+
+        // Orcs have friendly fire, so their arrows hurt their goblin friends
+        let goblin = scene.target(64.0, 0.0, Some("goblins"));
+        scene.projectiles[goblin_arrow] = None;
+        let orc_arrow = scene.projectile((80.0, 16.0), (0.0, 0.0), Projectile::new(5.0, 0, TileHit::Stop), Some("orcs"));
+        assert_eq!(scene.run(0.1), vec![orc_arrow]);
+        assert_eq!(scene.damage_taken(goblin), 10);
+    }
+}
@@ -1,48 +1,58 @@
 use crate::components::texture::Texture;
-use crate::components::{Entity, Position, Animation, AnimationState};
+use crate::components::{Entity, Position, Animation, AnimationState, Projectile, Sprite, Velocity};
 use crate::systems::tilemap_system::TilemapRenderSystem;
 use crate::components::tilemap::Tilemap;
-use std::sync::Arc;
+use crate::components::chunked_tilemap::ChunkedTilemap;
+use crate::assets::{AssetServer, Handle};
 
 
+// The columns needed to draw entities, one entry per entity
+pub struct Drawables<'r, 'a> {
+    pub entities: &'r [Entity],
+    pub textures: &'r [Vec<Handle<Texture<'a>>>],
+    pub animations: &'r [Animation],
+    pub sprites: &'r [Option<Sprite>],
+    pub positions: &'r [Position],
+    pub velocities: &'r [Option<Velocity>],
+    pub projectiles: &'r [Option<Projectile>],
+}
+
 // src/systems/render_system.rs
 pub struct RenderSystem;
 
 impl RenderSystem {
     pub fn render<'a>(
         canvas: &mut sdl2::render::Canvas<sdl2::video::Window>,
-        entities: &[Entity],
-        textures: &[Vec<Arc<Texture<'a>>>], // Changed to Arc<Texture>
-        animations: &[Animation],
-        positions: &[Position],
+        assets: &AssetServer<'a>,
+        drawables: &Drawables<'_, 'a>,
         camera_x: i32,
         camera_y: i32,
-        tilemap: Option<&Tilemap<'a>>
+        tilemap: Option<&Tilemap<'a>>,
+        world: Option<&ChunkedTilemap<'a>>
     ) {
         // Clear the canvas
         canvas.set_draw_color(sdl2::pixels::Color::RGB(0, 0, 0));
         canvas.clear();
         
         // Render tilemap first (background)
-        if let Some(tilemap) = tilemap {
-            TilemapRenderSystem::render(canvas, tilemap, camera_x, camera_y);
+        if let Some(world) = world {
+            TilemapRenderSystem::render_chunked(canvas, assets, world, camera_x, camera_y);
+        } else if let Some(tilemap) = tilemap {
+            TilemapRenderSystem::render(canvas, assets, tilemap, camera_x, camera_y);
         }
         
         // Render entities on top
-        Self::render_entities(canvas, entities, textures, animations, positions, camera_x, camera_y);
-        
-        canvas.present();
+        Self::render_entities(canvas, assets, drawables, camera_x, camera_y);
     }
 
     fn render_entities<'a>(
         canvas: &mut sdl2::render::Canvas<sdl2::video::Window>,
-        entities: &[Entity],
-        textures: &[Vec<Arc<Texture<'a>>>], // Changed to Arc<Texture>
-        animations: &[Animation],
-        positions: &[Position],
+        assets: &AssetServer<'a>,
+        drawables: &Drawables<'_, 'a>,
         camera_x: i32,
         camera_y: i32
     ) {
+        let Drawables { entities, textures, animations, sprites, positions, velocities, projectiles } = *drawables;
         for (i, _) in entities.iter().enumerate() {
             if let (Some(entity_textures), Some(animation), Some(position)) = 
                 (textures.get(i), animations.get(i), positions.get(i)) {
@@ -59,43 +69,48 @@ impl RenderSystem {
                     AnimationState::Hurt => 5,    // Soldier-Hurt.png is at index 5
                     AnimationState::Death => 6,   // Soldier-Death.png is at index 6
                 };
+                // Single-sheet sprites play the same sheet in every state
+                let texture_index = if entity_textures.len() == 1 { 0 } else { texture_index };
 
-                // Each state has different frame counts - add this
+                // Frame counts come from the entity's definition
                 let frame_count = match animation.state {
-                    AnimationState::Idle => 6,
-                    AnimationState::Walk => 8,
-                    AnimationState::Hurt => 4,
-                    AnimationState::Death => 4,
-                    AnimationState::Attack => 9,
-                };
+                    AnimationState::Walk => animation.walk_frames,
+                    AnimationState::Attack => animation.attack_frames,
+                    _ => animation.idle_frames,
+                }.max(1);
+
+                // Entities without a Sprite use the 100x100 character layout
+                let sprite = sprites.get(i).copied().flatten().unwrap_or_default();
 
                 // Get the right texture for the current entity and animation state
-                if let Some(texture) = entity_textures.get(texture_index) {
+                if let Some(texture) = entity_textures.get(texture_index).and_then(|handle| assets.texture(handle)) {
                     // Calculate correct frame within the sprite sheet
-                    let current_frame = animation.current_frame % frame_count;
-                    
-                    // Define frame dimensions
-                    let frame_width = 100;
-                    let frame_height = 100;
+                    let current_frame = (animation.current_frame % frame_count) as u32;
                     
-                    let x_offset = frame_width * current_frame;
+                    let (origin_x, origin_y) = sprite.origin;
+                    let x_offset = origin_x + sprite.frame_width * current_frame;
                     let clip_rect = sdl2::rect::Rect::new(
-                        x_offset as i32, 0, 
-                        frame_width as u32, frame_height as u32
+                        x_offset as i32, origin_y as i32, 
+                        sprite.frame_width, sprite.frame_height
                     );
                     
-                    let scale_factor = 2.0;
                     let dest_rect = sdl2::rect::Rect::new(
                         screen_x,
                         screen_y,
-                        (frame_width as f32 * scale_factor) as u32,
-                        (frame_height as f32 * scale_factor) as u32
+                        (sprite.frame_width as f32 * sprite.scale) as u32,
+                        (sprite.frame_height as f32 * sprite.scale) as u32
                     );
                     
                     // Replace the canvas.copy call with copy_ex for flip support
                     let flip_horizontal = !position.facing_right;
+                    
+                    // Projectile sprites point right and are turned to face where they're going
+                    let angle = match (projectiles.get(i), velocities.get(i)) {
+                        (Some(Some(_)), Some(Some(velocity))) => velocity.angle(),
+                        _ => 0.0,
+                    };
 
-                    // Access texture handle through Arc
+                    // Access the SDL texture inside the loaded asset
                     let texture_handle = &texture.handle;
 
                     // Use copy_ex instead of copy to support flipping
@@ -103,7 +118,7 @@ impl RenderSystem {
                         texture_handle, 
                         Some(clip_rect), 
                         Some(dest_rect),
-                        0.0,             // Rotation angle (0 = no rotation)
+                        angle,           // Rotation angle in degrees, clockwise
                         None,            // Center of rotation (None = center of dest_rect)
                         flip_horizontal, // Flip horizontally based on facing direction
                         false            // Don't flip vertically
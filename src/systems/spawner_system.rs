@@ -0,0 +1,297 @@
+use crate::components::position::{FEET_OFFSET_X, FEET_OFFSET_Y};
+use crate::components::spawner::SpawnerPhase;
+use crate::components::{CollisionGrid, Entity, Health, Position, Spawner, WaveEvent};
+use crate::procgen::rng::Rng;
+use std::collections::HashMap;
+use std::f32::consts::TAU;
+
+// Tries at finding open floor within a spawner's radius before using its own spot
+const PLACEMENT_ATTEMPTS: usize = 8;
+
+// An enemy a spawner wants created; GameState builds it and reports its id back
+// with SpawnerSystem::spawned
+pub struct SpawnRequest {
+    pub spawner: usize, // Index of the spawner
+    pub entity: String,
+    pub x: f32,
+    pub y: f32,
+    pub wave: usize,    // Which wave it belongs to (0 for continuous spawners)
+}
+
+pub struct SpawnerSystem;
+
+impl SpawnerSystem {
+    // Drops spawned enemies that have died or gone, moves waves along and returns
+    // what should be spawned this frame. Index 0 is the player, whose distance
+    // decides which spawners are active.
+    pub fn run(
+        entities: &[Entity],
+        positions: &[Position],
+        healths: &[Option<Health>],
+        spawners: &mut [Option<Spawner>],
+        collision: Option<&CollisionGrid>,
+        events: &mut Vec<WaveEvent>,
+        delta_time: f32,
+    ) -> Vec<SpawnRequest> {
+        let indices: HashMap<usize, usize> = entities.iter()
+            .enumerate()
+            .map(|(i, entity)| (entity.0, i))
+            .collect();
+        let is_alive = |id: usize| indices.get(&id)
+            .is_some_and(|&i| healths[i].as_ref().is_none_or(|health| !health.is_dead));
+        let player = positions.first().map(Position::feet);
+
+        let mut requests = Vec::new();
+        for (i, spawner) in spawners.iter_mut().enumerate() {
+            let Some(spawner) = spawner else { continue };
+            spawner.alive.retain(|&(id, _)| is_alive(id));
+
+            let feet = positions[i].feet();
+            let active = spawner.activation_range <= 0.0 || player.is_some_and(|player| {
+                let (dx, dy) = (player.0 - feet.0, player.1 - feet.1);
+                (dx * dx + dy * dy).sqrt() <= spawner.activation_range
+            });
+            if !active {
+                continue;
+            }
+            spawner.cooldown = (spawner.cooldown - delta_time).max(0.0);
+
+            let id = entities[i].0;
+            let next = match spawner.phase {
+                SpawnerPhase::Continuous => Some((spawner.entity.clone(), 0)),
+                SpawnerPhase::Waiting { wave, remaining } => {
+                    let remaining = remaining - delta_time;
+                    if remaining > 0.0 {
+                        spawner.phase = SpawnerPhase::Waiting { wave, remaining };
+                        continue;
+                    }
+                    events.push(WaveEvent::Started { spawner: id, wave });
+                    spawner.phase = SpawnerPhase::Running { wave, spawned: 0, elapsed: 0.0 };
+                    spawner.cooldown = 0.0;
+                    spawner.waves[wave].enemies.first().map(|enemy| (enemy.clone(), wave))
+                },
+                SpawnerPhase::Running { wave, spawned, elapsed } => {
+                    let elapsed = elapsed + delta_time;
+                    spawner.phase = SpawnerPhase::Running { wave, spawned, elapsed };
+                    let enemies = &spawner.waves[wave].enemies;
+                    let timed_out = spawner.clear.timeout > 0.0 && elapsed >= spawner.clear.timeout;
+                    let beaten = spawned >= enemies.len() && spawner.alive_in_wave(wave) <= spawner.clear.remaining;
+                    if timed_out || beaten {
+                        Self::complete_wave(spawner, id, wave, events);
+                        continue;
+                    }
+                    enemies.get(spawned).map(|enemy| (enemy.clone(), wave))
+                },
+                SpawnerPhase::Finished => None,
+            };
+
+            let Some((entity, wave)) = next else { continue };
+            if spawner.cooldown > 0.0 || spawner.alive.len() >= spawner.max_alive {
+                continue;
+            }
+            let (x, y) = Self::placement(spawner, id, feet, collision);
+            requests.push(SpawnRequest { spawner: i, entity, x, y, wave });
+            spawner.cooldown = spawner.interval;
+            spawner.spawned += 1;
+            if let SpawnerPhase::Running { spawned, .. } = &mut spawner.phase {
+                *spawned += 1;
+            }
+        }
+        requests
+    }
+
+    // Record an enemy created from a SpawnRequest, so it counts towards max_alive
+    // and its wave
+    pub fn spawned(spawners: &mut [Option<Spawner>], request: &SpawnRequest, entity: &Entity) {
+        if let Some(spawner) = spawners.get_mut(request.spawner).and_then(Option::as_mut) {
+            spawner.alive.push((entity.0, request.wave));
+        }
+    }
+
+    fn complete_wave(spawner: &mut Spawner, id: usize, wave: usize, events: &mut Vec<WaveEvent>) {
+        events.push(WaveEvent::Completed { spawner: id, wave });
+        if wave + 1 < spawner.waves.len() {
+            spawner.phase = SpawnerPhase::Waiting { wave: wave + 1, remaining: spawner.wave_delay };
+        } else {
+            spawner.phase = SpawnerPhase::Finished;
+            events.push(WaveEvent::Finished { spawner: id });
+        }
+    }
+
+    // Where the next enemy stands: a random open spot within the spawner's radius,
+    // seeded by the spawner and how many it has spawned so far
+    fn placement(spawner: &Spawner, id: usize, feet: (f32, f32), collision: Option<&CollisionGrid>) -> (f32, f32) {
+        let mut rng = Rng::new(((id as u64) << 32) | spawner.spawned as u64);
+        let spot = (0..PLACEMENT_ATTEMPTS)
+            .map(|_| {
+                let angle = rng.next_f32() * TAU;
+                let distance = spawner.radius * rng.next_f32().sqrt(); // Even over the disc
+                (feet.0 + angle.cos() * distance, feet.1 + angle.sin() * distance)
+            })
+            .find(|&(x, y)| collision.is_none_or(|grid| !grid.is_solid_at(x, y)))
+            .unwrap_or(feet);
+        // Positions are where the entity stands, feet are offset from it
+        (spot.0 - FEET_OFFSET_X, spot.1 - FEET_OFFSET_Y)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::components::spawner::{ClearCondition, Wave};
+
+    // The player (id 0) and a spawner (id 1), plus whatever it has spawned
+    struct Scene {
+        entities: Vec<Entity>,
+        positions: Vec<Position>,
+        healths: Vec<Option<Health>>,
+        spawners: Vec<Option<Spawner>>,
+        events: Vec<WaveEvent>,
+    }
+
+    impl Scene {
+        fn new(spawner: Spawner) -> Self {
+            Scene {
+                entities: vec![Entity(0), Entity(1)],
+                positions: vec![Position::new(0.0, 0.0, true), Position::new(100.0, 0.0, true)],
+                healths: vec![Some(Health::new(10, 10)), None],
+                spawners: vec![None, Some(spawner)],
+                events: Vec::new(),
+            }
+        }
+
+        // Runs one update and creates what it asked for; returns the names spawned
+        fn update(&mut self, delta_time: f32) -> Vec<String> {
+            let requests = SpawnerSystem::run(
+                &self.entities, &self.positions, &self.healths, &mut self.spawners, None, &mut self.events, delta_time,
+            );
+            for request in &requests {
+                let entity = Entity(self.entities.len());
+                self.entities.push(entity);
+                self.positions.push(Position::new(request.x, request.y, true));
+                self.healths.push(Some(Health::new(10, 10)));
+                self.spawners.push(None);
+                SpawnerSystem::spawned(&mut self.spawners, request, &entity);
+            }
+            requests.into_iter().map(|request| request.entity).collect()
+        }
+
+        fn kill_all(&mut self) {
+            for health in self.healths.iter_mut().skip(2).flatten() {
+                health.is_dead = true;
+            }
+        }
+
+        fn spawner(&self) -> &Spawner {
+            self.spawners[1].as_ref().unwrap()
+        }
+
+        fn take_events(&mut self) -> Vec<WaveEvent> {
+            std::mem::take(&mut self.events)
+        }
+    }
+
+    fn waves(waves: &[&[&str]]) -> Spawner {
+        let waves = waves.iter()
+            .map(|enemies| Wave { enemies: enemies.iter().map(|name| name.to_string()).collect() })
+            .collect();
+        let mut spawner = Spawner::new(String::new(), waves);
+        spawner.interval = 1.0;
+        spawner.wave_delay = 2.0;
+        spawner
+    }
+
+    #[test]
+    fn waves_run_in_order_once_each_is_cleared() {
+        let mut scene = Scene::new(waves(&[&["orc", "archer"], &["troll"]]));
+
+        assert_eq!(scene.update(0.1), ["orc"]);
+        assert_eq!(scene.take_events(), [WaveEvent::Started { spawner: 1, wave: 0 }]);
+        assert!(scene.update(0.5).is_empty());
+        assert_eq!(scene.update(0.5), ["archer"]);
+
+        // Everything's out, but the wave isn't over while they live
+        assert!(scene.update(5.0).is_empty());
+        assert!(scene.take_events().is_empty());
+
+        scene.kill_all();
+        assert!(scene.update(0.1).is_empty());
+        assert_eq!(scene.take_events(), [WaveEvent::Completed { spawner: 1, wave: 0 }]);
+        assert_eq!(scene.spawner().phase, SpawnerPhase::Waiting { wave: 1, remaining: 2.0 });
+
+        assert!(scene.update(1.0).is_empty());
+        assert_eq!(scene.update(1.0), ["troll"]);
+        assert_eq!(scene.take_events(), [WaveEvent::Started { spawner: 1, wave: 1 }]);
+
+        scene.kill_all();
+        scene.update(0.1);
+        assert_eq!(scene.take_events(), [
+            WaveEvent::Completed { spawner: 1, wave: 1 },
+            WaveEvent::Finished { spawner: 1 },
+        ]);
+        assert_eq!(scene.spawner().phase, SpawnerPhase::Finished);
+        assert!(scene.update(10.0).is_empty());
+    }
+
+    #[test]
+    fn waves_can_clear_with_survivors_or_on_a_timeout() {
+        let mut spawner = waves(&[&["orc", "orc"], &["troll", "troll", "troll"], &["dragon"]]);
+        spawner.interval = 0.0;
+        spawner.wave_delay = 0.0;
+        spawner.max_alive = 10;
+        spawner.clear = ClearCondition { remaining: 1, timeout: 3.0 };
+        let mut scene = Scene::new(spawner);
+
+        scene.update(0.1);
+        scene.update(0.1);
+        scene.healths[2].as_mut().unwrap().is_dead = true;
+        scene.update(0.1);
+        assert_eq!(scene.take_events()[1..], [WaveEvent::Completed { spawner: 1, wave: 0 }]);
+
+        // The trolls never die, so their wave ends when time runs out
+        for _ in 0..3 {
+            assert_eq!(scene.update(0.1), ["troll"]);
+        }
+        scene.take_events();
+        assert!(scene.update(2.0).is_empty());
+        assert!(scene.take_events().is_empty());
+        scene.update(1.0);
+        assert_eq!(scene.take_events(), [WaveEvent::Completed { spawner: 1, wave: 1 }]);
+        assert_eq!(scene.update(0.1), ["dragon"]);
+    }
+
+    #[test]
+    fn continuous_spawners_keep_max_alive_topped_up() {
+        let mut spawner = Spawner::new("orc".to_string(), Vec::new());
+        spawner.interval = 1.0;
+        spawner.max_alive = 2;
+        let mut scene = Scene::new(spawner);
+
+        assert_eq!(scene.update(0.1), ["orc"]);
+        assert!(scene.update(0.5).is_empty());
+        assert_eq!(scene.update(0.5), ["orc"]);
+        assert!(scene.update(5.0).is_empty());
+
+        scene.healths[3].as_mut().unwrap().is_dead = true;
+        assert_eq!(scene.update(0.1), ["orc"]);
+        assert_eq!(scene.spawner().alive.len(), 2);
+        assert!(scene.events.is_empty());
+    }
+
+    #[test]
+    fn waits_for_the_player_to_come_in_range() {
+        let mut spawner = Spawner::new("orc".to_string(), Vec::new());
+        spawner.activation_range = 50.0;
+        spawner.radius = 40.0;
+        let mut scene = Scene::new(spawner);
+
+        assert!(scene.update(0.1).is_empty());
+        scene.positions[0].x = 80.0;
+        assert_eq!(scene.update(0.1), ["orc"]);
+
+        // Placed somewhere within the radius
+        let (spawner, orc) = (scene.positions[1].feet(), scene.positions[2].feet());
+        let (dx, dy) = (orc.0 - spawner.0, orc.1 - spawner.1);
+        assert!((dx * dx + dy * dy).sqrt() <= 40.0 + 1e-3);
+    }
+}
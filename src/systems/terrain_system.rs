@@ -0,0 +1,102 @@
+use crate::components::{ChunkedTilemap, DestructibleTile, Inventory, Position, TileId, TileType, Tilemap};
+use std::collections::HashMap;
+
+// How far in front of an entity's feet an attack reaches
+const ATTACK_REACH: f32 = 40.0;
+const ATTACK_TILE_DAMAGE: u32 = 1;
+
+pub const BRIDGE_TILE: TileId = TileId(8);
+pub const BRIDGE_ITEM: &str = "plank";
+
+// Tile storage attacks can reach: a level's tilemap or a streamed world. Edits
+// show up in the storage's take_changes like any other.
+pub trait Terrain {
+    fn tile_size(&self) -> u32;
+    fn tile(&self, x: i32, y: i32) -> Option<TileId>;
+    fn place(&mut self, x: i32, y: i32, tile: TileId);
+    fn hit(&mut self, x: i32, y: i32, amount: u32, destructible: &HashMap<TileId, DestructibleTile>);
+}
+
+impl Terrain for Tilemap<'_> {
+    fn tile_size(&self) -> u32 {
+        self.tile_size
+    }
+
+    fn tile(&self, x: i32, y: i32) -> Option<TileId> {
+        if x < 0 || y < 0 {
+            return None;
+        }
+        self.get_tile(x as usize, y as usize).copied()
+    }
+
+    fn place(&mut self, x: i32, y: i32, tile: TileId) {
+        self.set_tile(x as usize, y as usize, tile);
+    }
+
+    fn hit(&mut self, x: i32, y: i32, amount: u32, destructible: &HashMap<TileId, DestructibleTile>) {
+        self.damage_tile(x as usize, y as usize, amount, destructible);
+    }
+}
+
+impl Terrain for ChunkedTilemap<'_> {
+    fn tile_size(&self) -> u32 {
+        self.tile_size
+    }
+
+    fn tile(&self, x: i32, y: i32) -> Option<TileId> {
+        self.get_tile(x, y)
+    }
+
+    fn place(&mut self, x: i32, y: i32, tile: TileId) {
+        if let Err(e) = self.set_tile(x, y, tile) {
+            eprintln!("Failed to set world tile ({}, {}): {}", x, y, e);
+        }
+    }
+
+    fn hit(&mut self, x: i32, y: i32, amount: u32, destructible: &HashMap<TileId, DestructibleTile>) {
+        if let Err(e) = self.damage_tile(x, y, amount, destructible) {
+            eprintln!("Failed to damage world tile ({}, {}): {}", x, y, e);
+        }
+    }
+}
+
+pub struct TerrainSystem;
+
+impl TerrainSystem {
+    // Resolve attacks that just started against the tile in front of each attacker:
+    // destructible tiles take damage, water gets a bridge if the attacker carries a plank
+    pub fn apply_attacks(
+        attackers: &[usize],
+        positions: &[Position],
+        inventories: &mut [Inventory],
+        terrain: &mut impl Terrain,
+        tile_types: &HashMap<TileId, TileType>,
+        destructible: &HashMap<TileId, DestructibleTile>,
+    ) {
+        for &i in attackers {
+            let position = match positions.get(i) {
+                Some(position) => position,
+                None => continue,
+            };
+            
+            let (feet_x, feet_y) = position.feet();
+            let reach = if position.facing_right { ATTACK_REACH } else { -ATTACK_REACH };
+            let tile_size = terrain.tile_size() as f32;
+            let tile_x = ((feet_x + reach) / tile_size).floor() as i32;
+            let tile_y = (feet_y / tile_size).floor() as i32;
+            
+            let tile_id = match terrain.tile(tile_x, tile_y) {
+                Some(tile_id) => tile_id,
+                None => continue,
+            };
+            
+            if destructible.contains_key(&tile_id) {
+                terrain.hit(tile_x, tile_y, ATTACK_TILE_DAMAGE, destructible);
+            } else if tile_types.get(&tile_id) == Some(&TileType::Water)
+                && let Some(inventory) = inventories.get_mut(i)
+                && inventory.remove(BRIDGE_ITEM, 1) {
+                terrain.place(tile_x, tile_y, BRIDGE_TILE);
+            }
+        }
+    }
+}
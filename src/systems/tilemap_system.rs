@@ -1,5 +1,7 @@
 // Add these imports at the top
-use crate::components::tilemap::{Tilemap, TileId, Tileset};
+use crate::assets::AssetServer;
+use crate::components::tilemap::Tilemap;
+use crate::components::chunked_tilemap::{ChunkedTilemap, CHUNK_SIZE};
 use sdl2::render::Canvas;
 use sdl2::video::Window;
 
@@ -7,10 +9,10 @@ use sdl2::video::Window;
 pub struct TilemapRenderSystem;
 
 impl TilemapRenderSystem {
-    pub fn render<'a>(canvas: &mut Canvas<Window>, tilemap: &Tilemap<'a>, camera_x: i32, camera_y: i32) {
+    pub fn render<'a>(canvas: &mut Canvas<Window>, assets: &AssetServer<'a>, tilemap: &Tilemap<'a>, camera_x: i32, camera_y: i32) {
         // Skip if no tileset is loaded
-        let tileset = match &tilemap.tileset {
-            Some(ts) => ts,
+        let (tileset, texture) = match tilemap.tileset.as_ref().and_then(|handle| assets.tileset_texture(handle)) {
+            Some(loaded) => loaded,
             _none => return,
         };
         
@@ -39,8 +41,7 @@ impl TilemapRenderSystem {
                                 tilemap.tile_size
                             );
                             
-                            // Update this line to use the Arc<Texture>
-                            canvas.copy(&tileset.texture.handle, Some(src_rect), Some(dest_rect))
+                            canvas.copy(&texture.handle, Some(src_rect), Some(dest_rect))
                                 .unwrap_or_else(|e| {
                                     eprintln!("Error rendering tile at ({}, {}): {}", x, y, e);
                                 });
@@ -50,4 +51,48 @@ impl TilemapRenderSystem {
             }
         }
     }
+    
+    pub fn render_chunked<'a>(canvas: &mut Canvas<Window>, assets: &AssetServer<'a>, world: &ChunkedTilemap<'a>, camera_x: i32, camera_y: i32) {
+        // Skip if no tileset is loaded
+        let (tileset, texture) = match world.tileset.as_ref().and_then(|handle| assets.tileset_texture(handle)) {
+            Some(loaded) => loaded,
+            _none => return,
+        };
+        
+        let screen_width = canvas.viewport().width() as i32;
+        let screen_height = canvas.viewport().height() as i32;
+        let tile_size = world.tile_size as i32;
+        
+        // Only walk the loaded chunks that actually overlap the screen
+        for coord in world.chunks_in_view(camera_x, camera_y, screen_width, screen_height, 0) {
+            let chunk = match world.chunks.get(&coord) {
+                Some(chunk) => chunk,
+                None => continue,
+            };
+            let (origin_x, origin_y) = coord.origin();
+            
+            for local_y in 0..CHUNK_SIZE {
+                for local_x in 0..CHUNK_SIZE {
+                    let tile_id = chunk.get(local_x, local_y).0;
+                    
+                    // Only render non-empty tiles
+                    if tile_id > 0
+                        && let Some(src_rect) = tileset.get_tile_rect(tile_id - 1) { // Adjust for 0-based indexing
+                        let dest_rect = sdl2::rect::Rect::new(
+                            (origin_x + local_x as i32) * tile_size - camera_x,
+                            (origin_y + local_y as i32) * tile_size - camera_y,
+                            world.tile_size,
+                            world.tile_size
+                        );
+                        
+                        canvas.copy(&texture.handle, Some(src_rect), Some(dest_rect))
+                            .unwrap_or_else(|e| {
+                                eprintln!("Error rendering chunk tile at ({}, {}): {}", 
+                                          origin_x + local_x as i32, origin_y + local_y as i32, e);
+                            });
+                    }
+                }
+            }
+        }
+    }
 }
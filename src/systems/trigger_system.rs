@@ -0,0 +1,12 @@
+use crate::components::Position;
+use crate::level_definitions::TriggerDefinition;
+
+pub struct TriggerSystem;
+
+impl TriggerSystem {
+    // Index of the trigger region the entity is standing in, if any
+    pub fn find(triggers: &[TriggerDefinition], position: &Position) -> Option<usize> {
+        let (feet_x, feet_y) = position.feet();
+        triggers.iter().position(|trigger| trigger.contains(feet_x, feet_y))
+    }
+}
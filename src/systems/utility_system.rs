@@ -0,0 +1,152 @@
+use crate::components::ai::Ai;
+use crate::components::position::{FEET_OFFSET_X, FEET_OFFSET_Y};
+use crate::components::{ActionState, AiState};
+use crate::navigation::steering::{self, Vector};
+use crate::navigation::{PathCache, Steering};
+use crate::systems::ai_world::AiWorld;
+use crate::utility_ai::{Input, UtilityAction};
+
+// How close (in pixels, feet to feet) ReturnHome has to get to arrive
+const ARRIVE_DISTANCE: f32 = 8.0;
+
+fn distance(a: Vector, b: Vector) -> f32 {
+    steering::length((a.0 - b.0, a.1 - b.1))
+}
+
+// Runs the entities whose AI is AiState::Utility. The rest are left to AiSystem
+// and BehaviourSystem. Returns the heals they cast as (entity index, amount), to
+// be applied once everyone has decided so healing doesn't sway anyone's choice
+// this frame.
+pub struct UtilitySystem;
+
+impl UtilitySystem {
+    pub fn run(
+        world: &AiWorld,
+        ais: &mut [Ai],
+        action_states: &mut [ActionState],
+        paths: &mut PathCache,
+        delta_time: f32,
+    ) -> Vec<(usize, u32)> {
+        let (entities, positions) = (world.entities, world.positions);
+        let steering = Steering::new(positions, ais, world.collision);
+        let velocities: Vec<Vector> = action_states.iter()
+            .zip(positions)
+            .map(|(action_state, position)| action_state.velocity(position.speed))
+            .collect();
+        let mut heals = Vec::new();
+
+        for (i, ai) in ais.iter_mut().enumerate().skip(1) {
+            let AiState::Utility(state) = &mut ai.behavior else { continue };
+            state.tick(delta_time);
+            let target = world.nearest_hostile(i);
+            state.target = target;
+
+            let Some(index) = state.choose(&|input: &Input| world.measure(i, target, input)) else {
+                action_states[i] = ActionState::None;
+                continue;
+            };
+
+            let feet = positions[i].feet();
+            let target = target.map(|target| (target, positions[target].feet()));
+            action_states[i] = match (&state.options[index].action, target) {
+                (UtilityAction::Idle, _) => ActionState::None,
+                (UtilityAction::Attack, Some(_)) => ActionState::Attacking,
+                (UtilityAction::Chase, Some((target, target_feet))) => {
+                    steering.chase(i, entities[i].0, target_feet, velocities[target], None, paths)
+                },
+                (UtilityAction::Flee, Some((_, target_feet))) => steering.steer(i, steering::flee(feet, target_feet)),
+                (UtilityAction::Wander, _) => steering.steer(i, steering::wander(state.time, entities[i].0)),
+                (UtilityAction::ReturnHome, _) => {
+                    let home = (state.home.0 + FEET_OFFSET_X, state.home.1 + FEET_OFFSET_Y);
+                    if distance(feet, home) <= ARRIVE_DISTANCE {
+                        ActionState::None
+                    } else {
+                        steering.travel(i, entities[i].0, home, paths)
+                    }
+                },
+                (UtilityAction::Heal { amount, radius }, _) => {
+                    if let Some(ally) = world.most_hurt_ally(i, *radius) {
+                        heals.push((ally, *amount));
+                    }
+                    ActionState::None
+                },
+                // Target-bound actions with nothing to aim at
+                (UtilityAction::Attack | UtilityAction::Chase | UtilityAction::Flee, None) => ActionState::None,
+            };
+
+            if state.options[index].action.is_instant() {
+                state.finish(index);
+            }
+        }
+        heals
+    }
+}
+
+// The utility AI's own questions about the world
+impl AiWorld<'_> {
+    fn is_ally(&self, i: usize, other: usize) -> bool {
+        other != i && self.relations.is_friendly(self.factions[i].as_ref(), self.factions[other].as_ref())
+    }
+
+    fn nearest_hostile(&self, i: usize) -> Option<usize> {
+        let feet = self.positions[i].feet();
+        (0..self.positions.len())
+            .filter(|&other| self.is_target(i, other))
+            .min_by(|&a, &b| {
+                let to_a = distance(feet, self.positions[a].feet());
+                let to_b = distance(feet, self.positions[b].feet());
+                to_a.total_cmp(&to_b)
+            })
+    }
+
+    // Living allies within `radius` pixels
+    fn allies(&self, i: usize, radius: f32) -> impl Iterator<Item = usize> + '_ {
+        let feet = self.positions[i].feet();
+        (0..self.positions.len())
+            .filter(move |&other| self.is_ally(i, other) && self.is_alive(other))
+            .filter(move |&other| distance(feet, self.positions[other].feet()) <= radius)
+    }
+
+    fn health_fraction(&self, i: usize) -> Option<f32> {
+        let health = self.healths[i].as_ref()?;
+        Some(health.current as f32 / health.max.max(1) as f32)
+    }
+
+    // The ally in range with the least health left, if any of them is hurt
+    fn most_hurt_ally(&self, i: usize, radius: f32) -> Option<usize> {
+        self.allies(i, radius)
+            .filter_map(|ally| self.health_fraction(ally).map(|fraction| (ally, fraction)))
+            .filter(|&(_, fraction)| fraction < 1.0)
+            .min_by(|a, b| a.1.total_cmp(&b.1))
+            .map(|(ally, _)| ally)
+    }
+
+    // Entities with a Perception see what it saw this frame. Otherwise only walls
+    // block the view.
+    fn sees(&self, i: usize, target: usize) -> bool {
+        if let Some(perception) = &self.perceptions[i] {
+            return perception.sees_target;
+        }
+        let (from, to) = (self.positions[i].feet(), self.positions[target].feet());
+        self.collision.is_none_or(|grid| grid.has_line_of_sight(from, to))
+    }
+
+    // An input's value for entity `i`, from 0 to 1. CooldownReady is answered by
+    // UtilityState itself.
+    fn measure(&self, i: usize, target: Option<usize>, input: &Input) -> f32 {
+        let value = match input {
+            Input::TargetDistance(range) => match target {
+                Some(target) => distance(self.positions[i].feet(), self.positions[target].feet()) / range.max(1.0),
+                None => 1.0,
+            },
+            Input::CanSeeTarget => if target.is_some_and(|target| self.sees(i, target)) { 1.0 } else { 0.0 },
+            Input::Health => self.health_fraction(i).unwrap_or(1.0),
+            Input::Allies { radius, max } => self.allies(i, *radius).count() as f32 / (*max).max(1) as f32,
+            Input::AllyHealth(radius) => self.allies(i, *radius)
+                .filter_map(|ally| self.health_fraction(ally))
+                .fold(1.0, f32::min),
+            Input::CooldownReady => 1.0,
+        };
+        value.clamp(0.0, 1.0)
+    }
+}
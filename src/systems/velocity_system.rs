@@ -0,0 +1,16 @@
+use crate::components::{Position, Velocity};
+
+pub struct VelocitySystem;
+
+impl VelocitySystem {
+    // Drift everything that has a velocity. Unlike MovementSystem this ignores tile
+    // collision; whatever owns the entity decides what happens when it hits something.
+    pub fn run(positions: &mut [Position], velocities: &[Option<Velocity>], delta_time: f32) {
+        for (position, velocity) in positions.iter_mut().zip(velocities) {
+            if let Some(velocity) = velocity {
+                position.x += velocity.x * delta_time;
+                position.y += velocity.y * delta_time;
+            }
+        }
+    }
+}
@@ -0,0 +1,81 @@
+use serde::{Deserialize, Serialize};
+
+// Response curve: turns a consideration's input (0 to 1) into a score (0 to 1)
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq, Default)]
+pub enum Curve {
+    // The input as it is
+    #[default]
+    Linear,
+    // 1 minus the input, for things that matter less the more there is of them
+    Inverse,
+    // Input raised to this power: above 1 stays low until near the end, below 1
+    // rises quickly and levels off
+    Power(f32),
+    // S-shaped, crossing 0.5 at `midpoint`. A negative steepness falls instead.
+    Logistic { midpoint: f32, steepness: f32 },
+    // 0 below this input, 1 from it on
+    Step(f32),
+}
+
+impl Curve {
+    pub fn evaluate(&self, input: f32) -> f32 {
+        let x = input.clamp(0.0, 1.0);
+        let score = match self {
+            Curve::Linear => x,
+            Curve::Inverse => 1.0 - x,
+            Curve::Power(exponent) => x.powf(*exponent),
+            Curve::Logistic { midpoint, steepness } => 1.0 / (1.0 + (-steepness * (x - midpoint)).exp()),
+            Curve::Step(threshold) => if x >= *threshold { 1.0 } else { 0.0 },
+        };
+        score.clamp(0.0, 1.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn close(a: f32, b: f32) -> bool {
+        (a - b).abs() < 1e-4
+    }
+
+    #[test]
+    fn linear_and_inverse() {
+        assert_eq!(Curve::Linear.evaluate(0.25), 0.25);
+        assert_eq!(Curve::Inverse.evaluate(0.25), 0.75);
+    }
+
+    #[test]
+    fn power_bends_the_line() {
+        assert!(close(Curve::Power(2.0).evaluate(0.5), 0.25));
+        assert!(close(Curve::Power(0.5).evaluate(0.25), 0.5));
+        assert_eq!(Curve::Power(3.0).evaluate(1.0), 1.0);
+    }
+
+    #[test]
+    fn logistic_crosses_half_at_its_midpoint() {
+        let rising = Curve::Logistic { midpoint: 0.3, steepness: 10.0 };
+        assert!(close(rising.evaluate(0.3), 0.5));
+        assert!(rising.evaluate(0.0) < 0.1);
+        assert!(rising.evaluate(1.0) > 0.99);
+
+        let falling = Curve::Logistic { midpoint: 0.3, steepness: -10.0 };
+        assert!(falling.evaluate(0.0) > 0.9);
+        assert!(falling.evaluate(1.0) < 0.01);
+    }
+
+    #[test]
+    fn step_switches_on_at_its_threshold() {
+        assert_eq!(Curve::Step(0.5).evaluate(0.49), 0.0);
+        assert_eq!(Curve::Step(0.5).evaluate(0.5), 1.0);
+    }
+
+    #[test]
+    fn inputs_and_scores_stay_between_0_and_1() {
+        assert_eq!(Curve::Linear.evaluate(-2.0), 0.0);
+        assert_eq!(Curve::Linear.evaluate(3.0), 1.0);
+        assert_eq!(Curve::Inverse.evaluate(3.0), 0.0);
+        // A negative power would blow up near 0
+        assert_eq!(Curve::Power(-1.0).evaluate(0.1), 1.0);
+    }
+}
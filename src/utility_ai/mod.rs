@@ -0,0 +1,7 @@
+// Utility AI. Instead of following a fixed tree, an entity scores every action it
+// could take and does the best one. Actions and their considerations are written
+// in entities.ron with `ai: Utility(...)`; UtilitySystem scores and runs them.
+pub mod curve;
+pub mod reasoner;
+
+pub use self::reasoner::{Input, UtilityAction, UtilityOption, UtilityState};
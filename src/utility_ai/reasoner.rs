@@ -0,0 +1,250 @@
+use super::curve::Curve;
+use serde::{Deserialize, Serialize};
+
+// A running action needs to be beaten by this much before it's dropped
+pub const DEFAULT_HYSTERESIS: f32 = 0.1;
+
+// Something a consideration measures, as a number from 0 to 1. Distances are in
+// pixels, feet to feet.
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+pub enum Input {
+    // Distance to the target as a fraction of this many pixels; 1 at that distance
+    // or beyond, and when there's no target
+    TargetDistance(f32),
+    // 1 while the target is in sight, 0 otherwise
+    CanSeeTarget,
+    // Own health as a fraction of its max; 1 without a Health component
+    Health,
+    // Allies within `radius`, as a fraction of `max`
+    Allies { radius: f32, max: u32 },
+    // Lowest health fraction among allies within this many pixels; 1 if there are none
+    AllyHealth(f32),
+    // How far the action is through its cooldown; 1 once it's ready
+    CooldownReady,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct Consideration {
+    pub input: Input,
+    #[serde(default)]
+    pub curve: Curve,
+}
+
+// What an entity does once an option wins. Attack and Heal happen once; the rest
+// carry on every frame for as long as they keep winning.
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+pub enum UtilityAction {
+    Idle,
+    Attack,
+    Chase,      // After the target
+    Flee,       // Away from the target
+    Wander,
+    ReturnHome, // Back to where it spawned
+    // Heals the most hurt ally within `radius` pixels by `amount`
+    Heal { amount: u32, radius: f32 },
+}
+
+impl UtilityAction {
+    pub fn is_instant(&self) -> bool {
+        matches!(self, UtilityAction::Attack | UtilityAction::Heal { .. })
+    }
+}
+
+// One action an entity can pick, e.g.
+//
+//   (action: Flee, considerations: [(input: Health, curve: Inverse), (input: CanSeeTarget)])
+//
+// Its score is `weight` times its considerations' scores multiplied together, so
+// any consideration scoring 0 rules it out.
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct UtilityOption {
+    pub action: UtilityAction,
+    #[serde(default)]
+    pub considerations: Vec<Consideration>,
+    #[serde(default = "default_weight")]
+    pub weight: f32,
+    // Seconds before it can score on CooldownReady again. Starts when an instant
+    // action happens, or when any other action stops.
+    #[serde(default)]
+    pub cooldown: f32,
+}
+
+fn default_weight() -> f32 {
+    1.0
+}
+
+// One entity's options and what it's doing with them. Saved with the entity, so
+// the options are copied from its definition rather than shared.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct UtilityState {
+    pub options: Vec<UtilityOption>,
+    pub hysteresis: f32,
+    pub current: Option<usize>,  // Index into options
+    pub cooldowns: Vec<f32>,     // Seconds left, indexed like options
+    pub target: Option<usize>,   // Nearest hostile entity
+    pub home: (f32, f32),        // Where it spawned; a position, like patrol waypoints
+    pub time: f32,               // Seconds this entity has been thinking, for wandering
+}
+
+impl UtilityState {
+    pub fn new(options: Vec<UtilityOption>, hysteresis: f32, home: (f32, f32)) -> Self {
+        UtilityState {
+            cooldowns: vec![0.0; options.len()],
+            options,
+            hysteresis,
+            current: None,
+            target: None,
+            home,
+            time: 0.0,
+        }
+    }
+
+    pub fn tick(&mut self, delta_time: f32) {
+        self.time += delta_time;
+        for cooldown in &mut self.cooldowns {
+            *cooldown = (*cooldown - delta_time).max(0.0);
+        }
+    }
+
+    // `measure` reads the world; CooldownReady is answered here. Multiplying scores
+    // drags down options with many considerations, so each one is made up by a
+    // share of what it lost, the more considerations the bigger the share.
+    pub fn score(&self, index: usize, measure: &impl Fn(&Input) -> f32) -> f32 {
+        let option = &self.options[index];
+        let count = option.considerations.len() as f32;
+        let make_up = 1.0 - 1.0 / count.max(1.0);
+        let mut score = option.weight;
+        for consideration in &option.considerations {
+            let input = match consideration.input {
+                Input::CooldownReady if option.cooldown > 0.0 => 1.0 - self.cooldowns[index] / option.cooldown,
+                Input::CooldownReady => 1.0,
+                ref input => measure(input),
+            };
+            let value = consideration.curve.evaluate(input);
+            score *= value + (1.0 - value) * make_up * value;
+            if score <= 0.0 {
+                return 0.0;
+            }
+        }
+        score
+    }
+
+    // Switch to the best scoring option, if any scores above 0. The running one gets
+    // `hysteresis` added, so close scores don't flip back and forth every frame.
+    pub fn choose(&mut self, measure: &impl Fn(&Input) -> f32) -> Option<usize> {
+        let mut best: Option<(usize, f32)> = None;
+        for index in 0..self.options.len() {
+            let mut score = self.score(index, measure);
+            if score <= 0.0 {
+                continue;
+            }
+            if self.current == Some(index) {
+                score += self.hysteresis;
+            }
+            if best.is_none_or(|(_, best)| score > best) {
+                best = Some((index, score));
+            }
+        }
+
+        let chosen = best.map(|(index, _)| index);
+        if let Some(previous) = self.current.filter(|&previous| Some(previous) != chosen) {
+            self.cooldowns[previous] = self.options[previous].cooldown;
+        }
+        self.current = chosen;
+        chosen
+    }
+
+    // An instant action has happened: it's over, and its cooldown starts
+    pub fn finish(&mut self, index: usize) {
+        self.cooldowns[index] = self.options[index].cooldown;
+        self.current = None;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn option(action: UtilityAction, considerations: Vec<Consideration>, cooldown: f32) -> UtilityOption {
+        UtilityOption { action, considerations, weight: 1.0, cooldown }
+    }
+
+    fn consider(input: Input, curve: Curve) -> Consideration {
+        Consideration { input, curve }
+    }
+
+    #[test]
+    fn any_zero_consideration_rules_an_option_out() {
+        let state = UtilityState::new(vec![
+            option(UtilityAction::Attack, vec![consider(Input::CanSeeTarget, Curve::Linear), consider(Input::Health, Curve::Linear)], 0.0),
+        ], DEFAULT_HYSTERESIS, (0.0, 0.0));
+        let measure = |input: &Input| if *input == Input::CanSeeTarget { 0.0 } else { 1.0 };
+        assert_eq!(state.score(0, &measure), 0.0);
+    }
+
+    #[test]
+    fn more_considerations_are_made_up_for() {
+        let one = UtilityState::new(vec![
+            option(UtilityAction::Flee, vec![consider(Input::Health, Curve::Linear)], 0.0),
+        ], DEFAULT_HYSTERESIS, (0.0, 0.0));
+        let three = UtilityState::new(vec![
+            option(UtilityAction::Flee, vec![
+                consider(Input::Health, Curve::Linear),
+                consider(Input::Health, Curve::Linear),
+                consider(Input::Health, Curve::Linear),
+            ], 0.0),
+        ], DEFAULT_HYSTERESIS, (0.0, 0.0));
+        let measure = |_: &Input| 0.5;
+
+        assert_eq!(one.score(0, &measure), 0.5);
+        // Plain multiplication would give 0.125
+        let score = three.score(0, &measure);
+        assert!(score > 0.125 && score < 0.5, "{}", score);
+    }
+
+    #[test]
+    fn running_option_keeps_winning_close_calls() {
+        let mut state = UtilityState::new(vec![
+            option(UtilityAction::Chase, vec![consider(Input::TargetDistance(100.0), Curve::Inverse)], 0.0),
+            option(UtilityAction::Wander, vec![consider(Input::TargetDistance(100.0), Curve::Linear)], 0.0),
+        ], 0.1, (0.0, 0.0));
+
+        assert_eq!(state.choose(&|_: &Input| 0.4), Some(0));
+        // Wander now scores 0.55 against Chase's 0.45 + 0.1
+        assert_eq!(state.choose(&|_: &Input| 0.55), Some(0));
+        assert_eq!(state.choose(&|_: &Input| 0.6), Some(1));
+    }
+
+    #[test]
+    fn cooldowns_start_when_an_option_stops() {
+        let mut state = UtilityState::new(vec![
+            option(UtilityAction::Attack, vec![consider(Input::CooldownReady, Curve::Step(1.0))], 2.0),
+            option(UtilityAction::Idle, vec![consider(Input::Health, Curve::Linear)], 0.0),
+        ], 0.0, (0.0, 0.0));
+        let measure = |_: &Input| 0.5;
+
+        assert_eq!(state.choose(&measure), Some(0));
+        state.finish(0);
+        assert_eq!(state.current, None);
+        assert_eq!(state.choose(&measure), Some(1));
+
+        state.tick(1.0);
+        assert_eq!(state.score(0, &measure), 0.0);
+        state.tick(1.0);
+        assert_eq!(state.choose(&measure), Some(0));
+        // Idle stopping starts its own (zero) cooldown without touching Attack's
+        assert_eq!(state.cooldowns, vec![0.0, 0.0]);
+    }
+
+    #[test]
+    fn nothing_is_chosen_when_every_option_scores_0() {
+        let mut state = UtilityState::new(vec![
+            option(UtilityAction::Attack, vec![consider(Input::CanSeeTarget, Curve::Linear)], 0.0),
+        ], DEFAULT_HYSTERESIS, (0.0, 0.0));
+        state.current = Some(0);
+        assert_eq!(state.choose(&|_: &Input| 0.0), None);
+        assert_eq!(state.current, None);
+    }
+}
@@ -0,0 +1,391 @@
+// `validate [level.ron ...]` - checks entities.ron and levels (every level in
+// assets/levels by default) without opening a window, so bad paths, frame counts
+// and typos are caught before anyone runs the game. Unknown enum values and fields
+// are already rejected when entities.ron is parsed; this covers what parsing can't.
+use crate::assets::{ANIMATION_TYPES, SPRITE_FRAME_SIZE};
+use crate::component_registry::{ComponentRegistry, SpawnerDefinition};
+use crate::components::position::{FEET_OFFSET_X, FEET_OFFSET_Y};
+use crate::components::chunked_tilemap::CHUNK_SIZE;
+use crate::components::{Faction, RangedAttack};
+use crate::components::tilemap::{parse_tile_csv, TileId};
+use crate::entity_definitions::{AiDefinition, EntityDefinition, EntityDefinitions};
+use crate::entity_factory::ENTITY_DEFINITIONS_PATH;
+use crate::level_definitions::LevelDefinition;
+use crate::vfs;
+use std::collections::{BTreeMap, HashMap};
+
+const LEVELS_DIR: &str = "assets/levels";
+
+struct Problem {
+    path: String,
+    message: String,
+}
+
+// Width and height from a PNG or BMP header, without decoding the image
+fn image_dimensions(bytes: &[u8]) -> Option<(u32, u32)> {
+    const PNG_SIGNATURE: &[u8] = b"\x89PNG\r\n\x1a\n";
+    if bytes.starts_with(PNG_SIGNATURE) && bytes.get(12..16) == Some(b"IHDR") {
+        let width = u32::from_be_bytes(bytes.get(16..20)?.try_into().ok()?);
+        let height = u32::from_be_bytes(bytes.get(20..24)?.try_into().ok()?);
+        return Some((width, height));
+    }
+    if bytes.starts_with(b"BM") {
+        let width = i32::from_le_bytes(bytes.get(18..22)?.try_into().ok()?);
+        let height = i32::from_le_bytes(bytes.get(22..26)?.try_into().ok()?);
+        return Some((width.unsigned_abs(), height.unsigned_abs())); // Negative height = top-down rows
+    }
+    None
+}
+
+struct Validator {
+    problems: Vec<Problem>,
+    definitions: Option<EntityDefinitions>,
+    levels: HashMap<String, Option<LevelDefinition>>, // Parsed on first use; None if it failed
+    registry: ComponentRegistry,
+}
+
+impl Validator {
+    fn new() -> Self {
+        Validator {
+            problems: Vec::new(),
+            definitions: None,
+            levels: HashMap::new(),
+            registry: ComponentRegistry::with_defaults(),
+        }
+    }
+
+    fn problem(&mut self, path: &str, message: String) {
+        self.problems.push(Problem { path: path.to_string(), message });
+    }
+
+    // Image size, reporting why it couldn't be read
+    fn image(&mut self, owner: &str, what: &str, path: &str) -> Option<(u32, u32)> {
+        let bytes = match vfs::read(path) {
+            Ok(bytes) => bytes,
+            Err(e) => {
+                self.problem(owner, format!("{}: {}", what, e));
+                return None;
+            }
+        };
+        let dimensions = image_dimensions(&bytes);
+        if dimensions.is_none() {
+            self.problem(owner, format!("{}: {} is not a PNG or BMP image", what, path));
+        }
+        dimensions
+    }
+
+    fn validate_entities(&mut self) {
+        let path = vfs::normalize(ENTITY_DEFINITIONS_PATH);
+        let definitions = match vfs::read_to_string(&path) {
+            Ok(ron_str) => match EntityDefinitions::parse(&ron_str) {
+                Ok(definitions) => definitions,
+                Err(e) => return self.problem(&path, format!("Failed to parse: {}", e)),
+            },
+            Err(e) => return self.problem(&path, e.to_string()),
+        };
+
+        let sorted: BTreeMap<&String, &EntityDefinition> = definitions.entities.iter().collect();
+        for (name, definition) in sorted {
+            self.validate_entity(&path, name, definition);
+            self.validate_ranged_attack(&path, name, definition, &definitions);
+            self.validate_faction(&path, name, definition, &definitions);
+            self.validate_spawner(&path, name, definition, &definitions);
+        }
+        self.definitions = Some(definitions);
+    }
+
+    // The projectile a RangedAttack fires has to be an entity that is one
+    fn validate_ranged_attack(&mut self, path: &str, name: &str, definition: &EntityDefinition, definitions: &EntityDefinitions) {
+        // Bad RangedAttack data is already reported by validate_entity
+        let Some(Ok(ranged_attack)) = definition.components.get("RangedAttack").map(|value| value.clone().into_rust::<RangedAttack>()) else { return };
+        let owner = format!("{} [{}]", path, name);
+        match definitions.entities.get(&ranged_attack.projectile) {
+            None => self.problem(&owner, format!("RangedAttack fires unknown entity {}", ranged_attack.projectile)),
+            Some(projectile) if !projectile.components.contains_key("Projectile") => {
+                self.problem(&owner, format!("RangedAttack fires {}, which has no Projectile component", ranged_attack.projectile));
+            },
+            Some(_) => {},
+        }
+    }
+
+    // A Faction has to be in the faction table, or nothing is hostile to it
+    fn validate_faction(&mut self, path: &str, name: &str, definition: &EntityDefinition, definitions: &EntityDefinitions) {
+        let Some(Ok(faction)) = definition.components.get("Faction").map(|value| value.clone().into_rust::<Faction>()) else { return };
+        if !definitions.factions.contains(&faction.name) {
+            self.problem(&format!("{} [{}]", path, name), format!("Faction {} isn't in the factions table", faction.name));
+        }
+    }
+
+    // Everything a Spawner brings in has to be a known entity, and it has to have
+    // something to spawn
+    fn validate_spawner(&mut self, path: &str, name: &str, definition: &EntityDefinition, definitions: &EntityDefinitions) {
+        let Some(Ok(spawner)) = definition.components.get("Spawner").map(|value| value.clone().into_rust::<SpawnerDefinition>()) else { return };
+        let owner = format!("{} [{}]", path, name);
+        if spawner.waves.is_empty() && spawner.entity.is_empty() {
+            self.problem(&owner, "Spawner has neither an entity nor waves".to_string());
+        }
+        if !spawner.waves.is_empty() && !spawner.entity.is_empty() {
+            self.problem(&owner, format!("Spawner has waves, so its entity {} is never spawned", spawner.entity));
+        }
+        if spawner.max_alive == 0 {
+            self.problem(&owner, "Spawner max_alive is 0, so it never spawns anything".to_string());
+        }
+        let spawned = std::iter::once(&spawner.entity)
+            .filter(|entity| !entity.is_empty())
+            .chain(spawner.waves.iter().flat_map(|wave| &wave.enemies));
+        for entity in spawned {
+            if !definitions.entities.contains_key(entity) {
+                self.problem(&owner, format!("Spawner spawns unknown entity {}", entity));
+            }
+        }
+        for (i, wave) in spawner.waves.iter().enumerate() {
+            if wave.enemies.is_empty() {
+                self.problem(&owner, format!("Spawner wave {} has no enemies", i));
+            }
+        }
+    }
+
+    fn validate_entity(&mut self, path: &str, name: &str, definition: &EntityDefinition) {
+        let owner = format!("{} [{}]", path, name);
+
+        if let (Some(health), Some(max_health)) = (definition.health, definition.max_health)
+            && health > max_health {
+            self.problem(&owner, format!("health {} is above max_health {}", health, max_health));
+        }
+        if let AiDefinition::Chase { detection_range, attack_range, leash_range, .. } = definition.ai {
+            if attack_range > detection_range {
+                self.problem(&owner, format!(
+                    "Chase attack_range {} is larger than detection_range {}", attack_range, detection_range
+                ));
+            }
+            // It would give up on anything it noticed at the edge of its range straight away
+            if leash_range < detection_range {
+                self.problem(&owner, format!(
+                    "Chase leash_range {} is smaller than detection_range {}", leash_range, detection_range
+                ));
+            }
+        }
+        if let AiDefinition::Utility { options, .. } = &definition.ai {
+            if options.is_empty() {
+                self.problem(&owner, "Utility AI has no options, so it never does anything".to_string());
+            }
+            for option in options {
+                if option.weight <= 0.0 {
+                    self.problem(&owner, format!("Utility option {:?} has weight {}, so it can never win", option.action, option.weight));
+                }
+            }
+        }
+
+        // Unknown names and bad values would otherwise only show up when the entity spawns
+        for (component, value) in &definition.components {
+            if let Err(e) = self.registry.check(component, value) {
+                self.problem(&owner, format!("component {}: {}", component, e));
+            }
+        }
+
+        let Some(animations) = &definition.animations else { return };
+        for (anim_type, animation) in ANIMATION_TYPES.iter().zip(animations.in_order()) {
+            if animation.frames == 0 {
+                self.problem(&owner, format!("{} animation has 0 frames", anim_type));
+                continue;
+            }
+
+            let frames = animation.frames as u32;
+            let texture_path = vfs::normalize(&animation.texture);
+            let what = format!("{} texture", anim_type);
+            let Some((width, height)) = self.image(&owner, &what, &texture_path) else { continue };
+            if width != frames * SPRITE_FRAME_SIZE || height != SPRITE_FRAME_SIZE {
+                self.problem(&owner, format!(
+                    "{} is {}x{}, but {} frames of {}x{} need {}x{}",
+                    texture_path, width, height, frames, SPRITE_FRAME_SIZE, SPRITE_FRAME_SIZE,
+                    frames * SPRITE_FRAME_SIZE, SPRITE_FRAME_SIZE
+                ));
+            }
+        }
+    }
+
+    // Parse a level once, reporting a failure only the first time
+    fn level(&mut self, path: &str) -> Option<LevelDefinition> {
+        if let Some(level) = self.levels.get(path) {
+            return level.clone();
+        }
+        let level = match vfs::read_to_string(path) {
+            Ok(ron_str) => match ron::from_str::<LevelDefinition>(&ron_str) {
+                Ok(level) => Some(level),
+                Err(e) => {
+                    self.problem(path, format!("Failed to parse: {}", e));
+                    None
+                }
+            },
+            Err(e) => {
+                self.problem(path, e.to_string());
+                None
+            }
+        };
+        self.levels.insert(path.to_string(), level.clone());
+        level
+    }
+
+    // Parse a tile layer, reporting the failure against the level that uses it
+    fn tiles(&mut self, level_path: &str, tilemap_path: &str) -> Option<Vec<Vec<TileId>>> {
+        match vfs::read(tilemap_path) {
+            Ok(bytes) => match parse_tile_csv(bytes.as_slice()) {
+                Ok(tiles) => Some(tiles),
+                Err(e) => {
+                    self.problem(level_path, format!("Tilemap {}: {}", tilemap_path, e));
+                    None
+                }
+            },
+            Err(e) => {
+                self.problem(level_path, format!("Tilemap: {}", e));
+                None
+            }
+        }
+    }
+
+    fn check_tile_ids(&mut self, tilemap_path: &str, tiles: &[Vec<TileId>], tileset_path: &str, tile_count: Option<u32>) {
+        let Some(tile_count) = tile_count else { return };
+        // One line per bad id rather than per cell
+        let mut bad_tiles: BTreeMap<u32, (usize, (usize, usize))> = BTreeMap::new();
+        for (y, row) in tiles.iter().enumerate() {
+            for (x, TileId(id)) in row.iter().enumerate() {
+                if *id > tile_count {
+                    bad_tiles.entry(*id).or_insert((0, (x, y))).0 += 1;
+                }
+            }
+        }
+        for (id, (count, (x, y))) in bad_tiles {
+            self.problem(tilemap_path, format!(
+                "Tile id {} used in {} cell(s), first at ({}, {}), but {} only has {} tiles",
+                id, count, x, y, tileset_path, tile_count
+            ));
+        }
+    }
+
+    fn validate_level(&mut self, path: &str) {
+        let Some(level) = self.level(path) else { return };
+        if level.tile_size == 0 {
+            return self.problem(path, "tile_size is 0".to_string());
+        }
+
+        // Tileset size decides which tile ids are valid
+        let tileset_path = vfs::normalize(&level.tileset);
+        let tile_count = self.image(path, "tileset", &tileset_path).map(|(width, height)| {
+            if width % level.tile_size != 0 || height % level.tile_size != 0 {
+                self.problem(path, format!(
+                    "Tileset {} is {}x{}, not a multiple of tile_size {}", tileset_path, width, height, level.tile_size
+                ));
+            }
+            (width / level.tile_size) * (height / level.tile_size)
+        });
+
+        // A streamed world has no edges, so only its chunks' tiles are checked
+        let bounds = match &level.world {
+            Some(world_dir) => {
+                let chunks: Vec<String> = vfs::list(world_dir).into_iter()
+                    .filter(|chunk_path| chunk_path.ends_with(".csv"))
+                    .collect();
+                if chunks.is_empty() {
+                    self.problem(path, format!("World {} has no chunks", world_dir));
+                }
+                for chunk_path in chunks {
+                    let Some(tiles) = self.tiles(path, &chunk_path) else { continue };
+                    if tiles.len() != CHUNK_SIZE || tiles.iter().any(|row| row.len() != CHUNK_SIZE) {
+                        self.problem(&chunk_path, format!("Chunk must be {}x{} tiles", CHUNK_SIZE, CHUNK_SIZE));
+                    }
+                    self.check_tile_ids(&chunk_path, &tiles, &tileset_path, tile_count);
+                }
+                None
+            },
+            None => {
+                let tilemap_path = vfs::normalize(&level.tilemap);
+                let Some(tiles) = self.tiles(path, &tilemap_path) else { return };
+                self.check_tile_ids(&tilemap_path, &tiles, &tileset_path, tile_count);
+                let map_width = tiles.first().map_or(0, |row| row.len()) as f32 * level.tile_size as f32;
+                let map_height = tiles.len() as f32 * level.tile_size as f32;
+                Some((map_width, map_height))
+            },
+        };
+        let on_map = |x: f32, y: f32| {
+            let (feet_x, feet_y) = (x + FEET_OFFSET_X, y + FEET_OFFSET_Y);
+            bounds.is_none_or(|(map_width, map_height)| feet_x >= 0.0 && feet_y >= 0.0 && feet_x < map_width && feet_y < map_height)
+        };
+
+        for (index, spawn) in level.spawns.iter().enumerate() {
+            let known = self.definitions.as_ref()
+                .is_none_or(|definitions| definitions.entities.contains_key(&spawn.entity));
+            if !known {
+                self.problem(path, format!("Spawn {} uses unknown entity \"{}\"", index, spawn.entity));
+            }
+            if !on_map(spawn.x, spawn.y) {
+                let (map_width, map_height) = bounds.unwrap_or_default();
+                self.problem(path, format!(
+                    "Spawn {} ({}) at ({}, {}) stands outside the {}x{} map", index, spawn.entity, spawn.x, spawn.y, map_width, map_height
+                ));
+            }
+        }
+        if !level.spawns.iter().any(|spawn| spawn.entity == "player") {
+            self.problem(path, "No player spawn".to_string());
+        }
+
+        for (index, pickup) in level.pickups.iter().enumerate() {
+            if pickup.count == 0 {
+                self.problem(path, format!("Pickup {} ({}) has a count of 0", index, pickup.item));
+            }
+            // Pickups are placed by the point they lie on, on_map wants an entity position
+            if !on_map(pickup.x - FEET_OFFSET_X, pickup.y - FEET_OFFSET_Y) {
+                self.problem(path, format!("Pickup {} ({}) at ({}, {}) is outside the map", index, pickup.item, pickup.x, pickup.y));
+            }
+        }
+
+        let mut entries: Vec<(&String, &(f32, f32))> = level.entries.iter().collect();
+        entries.sort_by(|a, b| a.0.cmp(b.0));
+        for (name, (x, y)) in entries {
+            if !on_map(*x, *y) {
+                self.problem(path, format!("Entry \"{}\" at ({}, {}) is outside the map", name, x, y));
+            }
+        }
+
+        for (index, trigger) in level.triggers.iter().enumerate() {
+            if trigger.width <= 0.0 || trigger.height <= 0.0 {
+                self.problem(path, format!("Trigger {} has an empty area", index));
+            }
+            let target = vfs::normalize(&trigger.target_level);
+            if let Some(target_level) = self.level(&target) {
+                if !target_level.entries.contains_key(&trigger.entry) {
+                    self.problem(path, format!(
+                        "Trigger {} sends the player to entry \"{}\", which {} doesn't define", index, trigger.entry, target
+                    ));
+                }
+            } else {
+                self.problem(path, format!("Trigger {} targets level {}, which can't be loaded", index, target));
+            }
+        }
+    }
+}
+
+pub fn run_cli(args: &[String]) -> Result<(), String> {
+    let levels: Vec<String> = if args.is_empty() {
+        vfs::list(LEVELS_DIR).into_iter()
+            .filter(|path| path.ends_with(".ron"))
+            .collect()
+    } else {
+        args.iter().map(|path| vfs::normalize(path)).collect()
+    };
+
+    let mut validator = Validator::new();
+    validator.validate_entities();
+    for level in &levels {
+        validator.validate_level(level);
+    }
+
+    for problem in &validator.problems {
+        eprintln!("error: {}: {}", problem.path, problem.message);
+    }
+    if validator.problems.is_empty() {
+        println!("Checked entity definitions and {} level(s): no problems found", levels.len());
+        Ok(())
+    } else {
+        Err(format!("Validation failed with {} problem(s)", validator.problems.len()))
+    }
+}
@@ -0,0 +1,316 @@
+use super::lz;
+use crate::assets::AssetError;
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+
+// Packed archive layout (all integers little-endian):
+//   magic "SDLPAK01"
+//   u32 entry count
+//   per entry: u16 name length, name (UTF-8, '/' separated, relative to the mount point),
+//              u64 offset into the data section, u64 stored size, u64 original size,
+//              u8 compression
+//   data section: entry payloads back to back
+const MAGIC: &[u8; 8] = b"SDLPAK01";
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Compression {
+    None,
+    Lz,
+}
+
+impl Compression {
+    fn to_byte(self) -> u8 {
+        match self {
+            Compression::None => 0,
+            Compression::Lz => 1,
+        }
+    }
+
+    fn from_byte(byte: u8) -> Option<Self> {
+        match byte {
+            0 => Some(Compression::None),
+            1 => Some(Compression::Lz),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct ArchiveEntry {
+    pub offset: u64,
+    pub stored_size: u64,
+    pub size: u64,
+    pub compression: Compression,
+}
+
+// Read-only view of a packed archive. Only the index is kept in memory; entry data
+// is read from disk on demand, so this can be shared between loader threads.
+pub struct PackArchive {
+    path: PathBuf,
+    entries: HashMap<String, ArchiveEntry>,
+    data_start: u64,
+}
+
+fn read_bytes<R: Read>(reader: &mut R, count: usize) -> std::io::Result<Vec<u8>> {
+    let mut bytes = vec![0; count];
+    reader.read_exact(&mut bytes)?;
+    Ok(bytes)
+}
+
+fn read_u16<R: Read>(reader: &mut R) -> std::io::Result<u16> {
+    let mut bytes = [0; 2];
+    reader.read_exact(&mut bytes)?;
+    Ok(u16::from_le_bytes(bytes))
+}
+
+fn read_u32<R: Read>(reader: &mut R) -> std::io::Result<u32> {
+    let mut bytes = [0; 4];
+    reader.read_exact(&mut bytes)?;
+    Ok(u32::from_le_bytes(bytes))
+}
+
+fn read_u64<R: Read>(reader: &mut R) -> std::io::Result<u64> {
+    let mut bytes = [0; 8];
+    reader.read_exact(&mut bytes)?;
+    Ok(u64::from_le_bytes(bytes))
+}
+
+impl PackArchive {
+    pub fn open(path: &Path) -> Result<Self, AssetError> {
+        let display = path.display().to_string();
+        let io_error = |e: std::io::Error| AssetError::Io { path: display.clone(), message: e.to_string() };
+        let parse_error = |message: String| AssetError::Parse { path: display.clone(), message };
+
+        let mut file = File::open(path).map_err(io_error)?;
+        if read_bytes(&mut file, MAGIC.len()).map_err(io_error)? != MAGIC {
+            return Err(parse_error("Not a packed asset archive".to_string()));
+        }
+
+        let count = read_u32(&mut file).map_err(io_error)?;
+        let mut entries = HashMap::new();
+        for _ in 0..count {
+            let name_length = read_u16(&mut file).map_err(io_error)? as usize;
+            let name = String::from_utf8(read_bytes(&mut file, name_length).map_err(io_error)?)
+                .map_err(|e| parse_error(format!("Invalid entry name: {}", e)))?;
+            let offset = read_u64(&mut file).map_err(io_error)?;
+            let stored_size = read_u64(&mut file).map_err(io_error)?;
+            let size = read_u64(&mut file).map_err(io_error)?;
+            let compression = read_bytes(&mut file, 1).map_err(io_error)?[0];
+            let compression = Compression::from_byte(compression)
+                .ok_or_else(|| parse_error(format!("Unknown compression {} for {}", compression, name)))?;
+
+            entries.insert(name, ArchiveEntry { offset, stored_size, size, compression });
+        }
+
+        let data_start = file.stream_position().map_err(io_error)?;
+        Ok(PackArchive {
+            path: path.to_path_buf(),
+            entries,
+            data_start,
+        })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn contains(&self, name: &str) -> bool {
+        self.entries.contains_key(name)
+    }
+
+    // True if any entry lives under this directory
+    pub fn contains_dir(&self, name: &str) -> bool {
+        let prefix = format!("{}/", name.trim_end_matches('/'));
+        name.is_empty() || self.entries.keys().any(|entry| entry.starts_with(&prefix))
+    }
+
+    // Names of the files directly inside a directory
+    pub fn files_in(&self, dir: &str) -> Vec<String> {
+        let prefix = if dir.is_empty() { String::new() } else { format!("{}/", dir.trim_end_matches('/')) };
+        self.entries.keys()
+            .filter_map(|name| name.strip_prefix(prefix.as_str()))
+            .filter(|file| !file.contains('/'))
+            .map(str::to_string)
+            .collect()
+    }
+
+    pub fn read(&self, name: &str) -> Result<Vec<u8>, AssetError> {
+        let entry = self.entries.get(name)
+            .ok_or_else(|| AssetError::NotFound(name.to_string()))?;
+        let io_error = |e: std::io::Error| AssetError::Io { path: name.to_string(), message: e.to_string() };
+
+        let mut file = File::open(&self.path).map_err(io_error)?;
+        file.seek(SeekFrom::Start(self.data_start + entry.offset)).map_err(io_error)?;
+        let stored = read_bytes(&mut file, entry.stored_size as usize).map_err(io_error)?;
+
+        match entry.compression {
+            Compression::None => Ok(stored),
+            Compression::Lz => lz::decompress(&stored, entry.size as usize)
+                .map_err(|message| AssetError::Parse { path: name.to_string(), message }),
+        }
+    }
+}
+
+#[derive(Default, Debug)]
+pub struct PackStats {
+    pub files: usize,
+    pub compressed_files: usize,
+    pub original_bytes: u64,
+    pub stored_bytes: u64,
+}
+
+fn collect_files(dir: &Path, files: &mut Vec<PathBuf>) -> std::io::Result<()> {
+    for entry in std::fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_dir() {
+            collect_files(&path, files)?;
+        } else {
+            files.push(path);
+        }
+    }
+    Ok(())
+}
+
+// Pack every file under `source_dir` into one archive. Entries are compressed only
+// when that actually makes them smaller.
+pub fn pack(source_dir: &Path, output: &Path) -> Result<PackStats, AssetError> {
+    let source_display = source_dir.display().to_string();
+    let output_display = output.display().to_string();
+    let read_error = |e: std::io::Error| AssetError::Io { path: source_display.clone(), message: e.to_string() };
+    let write_error = |e: std::io::Error| AssetError::Io { path: output_display.clone(), message: e.to_string() };
+
+    let mut files = Vec::new();
+    collect_files(source_dir, &mut files).map_err(read_error)?;
+    // Sorted so the same input always produces the same archive
+    files.sort();
+
+    let output_canonical = output.canonicalize().ok();
+    let mut stats = PackStats::default();
+    let mut index = Vec::new();
+    let mut data = Vec::new();
+
+    for file in files {
+        if output_canonical.is_some() && file.canonicalize().ok() == output_canonical {
+            continue; // Don't pack a previous copy of the archive into itself
+        }
+
+        let name = file.strip_prefix(source_dir)
+            .map_err(|e| AssetError::Io { path: file.display().to_string(), message: e.to_string() })?
+            .components()
+            .map(|component| component.as_os_str().to_string_lossy().into_owned())
+            .collect::<Vec<_>>()
+            .join("/");
+        let bytes = std::fs::read(&file)
+            .map_err(|e| AssetError::Io { path: file.display().to_string(), message: e.to_string() })?;
+
+        let compressed = lz::compress(&bytes);
+        let (stored, compression) = if compressed.len() < bytes.len() {
+            stats.compressed_files += 1;
+            (compressed, Compression::Lz)
+        } else {
+            (bytes.clone(), Compression::None)
+        };
+
+        index.push((name, data.len() as u64, stored.len() as u64, bytes.len() as u64, compression));
+        stats.files += 1;
+        stats.original_bytes += bytes.len() as u64;
+        stats.stored_bytes += stored.len() as u64;
+        data.extend_from_slice(&stored);
+    }
+
+    let mut header = Vec::new();
+    header.extend_from_slice(MAGIC);
+    header.extend_from_slice(&(index.len() as u32).to_le_bytes());
+    for (name, offset, stored_size, size, compression) in &index {
+        let name_length = u16::try_from(name.len())
+            .map_err(|_| AssetError::Io { path: name.clone(), message: "Path too long for archive".to_string() })?;
+        header.extend_from_slice(&name_length.to_le_bytes());
+        header.extend_from_slice(name.as_bytes());
+        header.extend_from_slice(&offset.to_le_bytes());
+        header.extend_from_slice(&stored_size.to_le_bytes());
+        header.extend_from_slice(&size.to_le_bytes());
+        header.push(compression.to_byte());
+    }
+
+    let mut file = File::create(output).map_err(write_error)?;
+    file.write_all(&header).map_err(write_error)?;
+    file.write_all(&data).map_err(write_error)?;
+    Ok(stats)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // A fresh directory under the system temp dir, unique to this test
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("sdlpak_{}_{}", std::process::id(), name));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn reads_back_packed_files() {
+        let dir = temp_dir("round_trip");
+        let source = dir.join("assets");
+        std::fs::create_dir_all(source.join("levels")).unwrap();
+        let repetitive = "0,0,0,1,".repeat(200);
+        std::fs::write(source.join("levels/level1.csv"), &repetitive).unwrap();
+        std::fs::write(source.join("tiny.txt"), "ab").unwrap();
+
+        let output = dir.join("assets.pak");
+        let stats = pack(&source, &output).unwrap();
+        assert_eq!(stats.files, 2);
+        assert_eq!(stats.compressed_files, 1);
+        assert!(stats.stored_bytes < stats.original_bytes);
+
+        let archive = PackArchive::open(&output).unwrap();
+        assert_eq!(archive.len(), 2);
+        assert_eq!(archive.read("levels/level1.csv").unwrap(), repetitive.as_bytes());
+        assert_eq!(archive.read("tiny.txt").unwrap(), b"ab");
+        assert!(matches!(archive.read("missing.txt"), Err(AssetError::NotFound(_))));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn lists_directories() {
+        let dir = temp_dir("listing");
+        let source = dir.join("assets");
+        std::fs::create_dir_all(source.join("levels/extra")).unwrap();
+        std::fs::write(source.join("levels/a.ron"), "()").unwrap();
+        std::fs::write(source.join("levels/b.ron"), "()").unwrap();
+        std::fs::write(source.join("levels/extra/c.ron"), "()").unwrap();
+
+        let output = dir.join("assets.pak");
+        pack(&source, &output).unwrap();
+        let archive = PackArchive::open(&output).unwrap();
+
+        let mut files = archive.files_in("levels");
+        files.sort();
+        assert_eq!(files, vec!["a.ron", "b.ron"]);
+        assert!(archive.contains("levels/extra/c.ron"));
+        assert!(archive.contains_dir("levels/extra"));
+        assert!(!archive.contains_dir("level"));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn rejects_other_files() {
+        let dir = temp_dir("bad_magic");
+        let path = dir.join("not_a.pak");
+        std::fs::write(&path, b"PK\x03\x04 definitely a zip").unwrap();
+
+        assert!(matches!(PackArchive::open(&path), Err(AssetError::Parse { .. })));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,108 @@
+// Small LZ77-style compressor used for packed archive entries. Text assets (CSV
+// tilemaps, RON definitions) shrink a lot; PNGs are already compressed and are
+// stored as-is by the packer when this doesn't help.
+//
+// Stream layout: a flag byte followed by up to 8 items. Bit n of the flag byte set
+// means item n is a back-reference (u16 little-endian distance, u8 length - 3),
+// clear means a single literal byte.
+
+const MIN_MATCH: usize = 3;
+const MAX_MATCH: usize = 255 + MIN_MATCH;
+const MAX_DISTANCE: usize = u16::MAX as usize;
+const HASH_BITS: u32 = 15;
+
+fn hash(bytes: &[u8]) -> usize {
+    let value = (bytes[0] as u32) << 16 | (bytes[1] as u32) << 8 | bytes[2] as u32;
+    (value.wrapping_mul(2_654_435_761) >> (32 - HASH_BITS)) as usize
+}
+
+pub fn compress(input: &[u8]) -> Vec<u8> {
+    let mut output = Vec::with_capacity(input.len() / 2);
+    // Last position (+1, so 0 means empty) each 3-byte prefix was seen at
+    let mut table = vec![0usize; 1 << HASH_BITS];
+    let mut i = 0;
+
+    while i < input.len() {
+        let flag_index = output.len();
+        output.push(0u8);
+
+        for bit in 0..8 {
+            if i >= input.len() {
+                break;
+            }
+
+            let mut match_length = 0;
+            let mut match_distance = 0;
+            if i + MIN_MATCH <= input.len() {
+                let slot = hash(&input[i..]);
+                let candidate = table[slot];
+                table[slot] = i + 1;
+
+                if candidate > 0 {
+                    let start = candidate - 1;
+                    let distance = i - start;
+                    if distance <= MAX_DISTANCE {
+                        let mut length = 0;
+                        while length < MAX_MATCH && i + length < input.len() && input[start + length] == input[i + length] {
+                            length += 1;
+                        }
+                        if length >= MIN_MATCH {
+                            match_length = length;
+                            match_distance = distance;
+                        }
+                    }
+                }
+            }
+
+            if match_length > 0 {
+                output[flag_index] |= 1 << bit;
+                output.extend_from_slice(&(match_distance as u16).to_le_bytes());
+                output.push((match_length - MIN_MATCH) as u8);
+                i += match_length;
+            } else {
+                output.push(input[i]);
+                i += 1;
+            }
+        }
+    }
+    output
+}
+
+fn take<'i>(input: &'i [u8], pos: &mut usize, count: usize) -> Result<&'i [u8], String> {
+    let bytes = input.get(*pos..*pos + count).ok_or("Compressed data is truncated")?;
+    *pos += count;
+    Ok(bytes)
+}
+
+pub fn decompress(input: &[u8], size: usize) -> Result<Vec<u8>, String> {
+    let mut output = Vec::with_capacity(size);
+    let mut pos = 0;
+
+    while output.len() < size {
+        let flags = take(input, &mut pos, 1)?[0];
+        for bit in 0..8 {
+            if output.len() >= size {
+                break;
+            }
+
+            if flags & (1 << bit) != 0 {
+                let item = take(input, &mut pos, 3)?;
+                let distance = u16::from_le_bytes([item[0], item[1]]) as usize;
+                let length = item[2] as usize + MIN_MATCH;
+                if distance == 0 || distance > output.len() {
+                    return Err(format!("Invalid back-reference distance {}", distance));
+                }
+                // Byte by byte: a match may overlap the bytes it's producing
+                let start = output.len() - distance;
+                for k in 0..length {
+                    output.push(output[start + k]);
+                }
+            } else {
+                output.push(take(input, &mut pos, 1)?[0]);
+            }
+        }
+    }
+
+    output.truncate(size);
+    Ok(output)
+}
@@ -0,0 +1,301 @@
+// Virtual filesystem every asset read goes through. Game code uses virtual paths
+// like "assets/levels/level1.ron"; mounts decide where the bytes actually come from,
+// a loose directory during development or a packed archive in a release build.
+pub mod archive;
+pub mod lz;
+
+use self::archive::PackArchive;
+use crate::assets::AssetError;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+use std::time::SystemTime;
+
+// Root every virtual asset path lives under
+pub const ASSET_ROOT: &str = "assets";
+pub const DEFAULT_ARCHIVE: &str = "assets.pak";
+
+pub enum MountSource {
+    Directory(PathBuf),
+    Archive(PackArchive),
+}
+
+pub struct Mount {
+    pub point: String, // Virtual directory this source appears under
+    pub source: MountSource,
+}
+
+impl Mount {
+    // Path of `path` relative to this mount, if the mount covers it
+    fn relative<'p>(&self, path: &'p str) -> Option<&'p str> {
+        if path == self.point {
+            return Some("");
+        }
+        path.strip_prefix(self.point.as_str())?.strip_prefix('/')
+    }
+
+    pub fn describe(&self) -> String {
+        match &self.source {
+            MountSource::Directory(dir) => format!("{} -> {}", self.point, dir.display()),
+            MountSource::Archive(archive) => format!(
+                "{} -> {} ({} files)",
+                self.point,
+                archive.path().display(),
+                archive.len()
+            ),
+        }
+    }
+}
+
+pub struct Vfs {
+    mounts: Vec<Mount>, // Searched last to first, so later mounts override earlier ones
+}
+
+impl Vfs {
+    pub fn new() -> Self {
+        Vfs { mounts: Vec::new() }
+    }
+
+    pub fn mount_directory(&mut self, point: &str, dir: &Path) {
+        self.mounts.push(Mount {
+            point: point.trim_end_matches('/').to_string(),
+            source: MountSource::Directory(dir.to_path_buf()),
+        });
+    }
+
+    pub fn mount_archive(&mut self, point: &str, archive_path: &Path) -> Result<(), AssetError> {
+        let archive = PackArchive::open(archive_path)?;
+        self.mounts.push(Mount {
+            point: point.trim_end_matches('/').to_string(),
+            source: MountSource::Archive(archive),
+        });
+        Ok(())
+    }
+
+    // Mounts for the current run: a packed archive if one ships with the game, with
+    // the loose assets directory on top so edited files win over packed ones.
+    pub fn from_environment() -> Self {
+        let mut vfs = Vfs::new();
+        let exe_dir = std::env::current_exe().ok()
+            .and_then(|exe| exe.parent().map(Path::to_path_buf));
+
+        let mut archive_candidates = vec![PathBuf::from(DEFAULT_ARCHIVE)];
+        if let Some(exe_dir) = &exe_dir {
+            archive_candidates.push(exe_dir.join(DEFAULT_ARCHIVE));
+        }
+        if let Some(archive) = archive_candidates.iter().find(|path| path.is_file())
+            && let Err(e) = vfs.mount_archive(ASSET_ROOT, archive) {
+            eprintln!("Ignoring asset archive: {}", e);
+        }
+
+        let mut dir_candidates = vec![PathBuf::from(ASSET_ROOT)];
+        if let Some(exe_dir) = &exe_dir {
+            dir_candidates.push(exe_dir.join(ASSET_ROOT));
+            dir_candidates.push(exe_dir.join("../..").join(ASSET_ROOT)); // target/<profile>/
+        }
+        dir_candidates.push(Path::new(env!("CARGO_MANIFEST_DIR")).join(ASSET_ROOT));
+        if let Some(dir) = dir_candidates.iter().find(|path| path.is_dir()) {
+            vfs.mount_directory(ASSET_ROOT, dir);
+        } else if vfs.mounts.is_empty() {
+            // Nothing found; keep the old relative behaviour so errors name a sensible path
+            vfs.mount_directory(ASSET_ROOT, Path::new(ASSET_ROOT));
+        }
+
+        vfs
+    }
+
+    pub fn mounts(&self) -> &[Mount] {
+        &self.mounts
+    }
+
+    // `path` must already be normalized
+    pub fn read(&self, path: &str) -> Result<Vec<u8>, AssetError> {
+        if Path::new(path).is_absolute() {
+            return std::fs::read(path).map_err(|e| io_error(path, e));
+        }
+
+        for mount in self.mounts.iter().rev() {
+            let Some(relative) = mount.relative(path) else { continue };
+            match &mount.source {
+                MountSource::Directory(dir) => {
+                    let host_path = dir.join(relative);
+                    if host_path.is_file() {
+                        return std::fs::read(&host_path).map_err(|e| io_error(path, e));
+                    }
+                },
+                MountSource::Archive(archive) => {
+                    if archive.contains(relative) {
+                        return archive.read(relative).map_err(|e| match e {
+                            AssetError::Parse { message, .. } => AssetError::Parse { path: path.to_string(), message },
+                            other => other,
+                        });
+                    }
+                },
+            }
+        }
+        Err(AssetError::NotFound(path.to_string()))
+    }
+
+    pub fn read_to_string(&self, path: &str) -> Result<String, AssetError> {
+        let bytes = self.read(path)?;
+        String::from_utf8(bytes)
+            .map_err(|e| AssetError::Parse { path: path.to_string(), message: e.to_string() })
+    }
+
+    // True for files and for directories containing at least one file
+    pub fn exists(&self, path: &str) -> bool {
+        if Path::new(path).is_absolute() {
+            return Path::new(path).exists();
+        }
+
+        self.mounts.iter().any(|mount| {
+            let Some(relative) = mount.relative(path) else { return false };
+            match &mount.source {
+                MountSource::Directory(dir) => dir.join(relative).exists(),
+                MountSource::Archive(archive) => archive.contains(relative) || archive.contains_dir(relative),
+            }
+        })
+    }
+
+    // Virtual paths of the files directly inside a directory, across every mount
+    pub fn list(&self, dir: &str) -> Vec<String> {
+        let mut files = Vec::new();
+        for mount in &self.mounts {
+            let Some(relative) = mount.relative(dir) else { continue };
+            let names = match &mount.source {
+                MountSource::Directory(host_dir) => std::fs::read_dir(host_dir.join(relative))
+                    .map(|entries| entries
+                        .filter_map(|entry| entry.ok())
+                        .filter(|entry| entry.path().is_file())
+                        .map(|entry| entry.file_name().to_string_lossy().into_owned())
+                        .collect())
+                    .unwrap_or_default(),
+                MountSource::Archive(archive) => archive.files_in(relative),
+            };
+            files.extend(names.into_iter().map(|name| format!("{}/{}", dir, name)));
+        }
+        files.sort();
+        files.dedup();
+        files
+    }
+
+    // Modification time of a loose file. Archive contents never change while the game
+    // runs, so they have none and hot reload ignores them.
+    pub fn modified(&self, path: &str) -> Option<SystemTime> {
+        if Path::new(path).is_absolute() {
+            return std::fs::metadata(path).and_then(|metadata| metadata.modified()).ok();
+        }
+
+        for mount in self.mounts.iter().rev() {
+            let Some(relative) = mount.relative(path) else { continue };
+            match &mount.source {
+                MountSource::Directory(dir) => {
+                    if let Ok(metadata) = std::fs::metadata(dir.join(relative)) {
+                        return metadata.modified().ok();
+                    }
+                },
+                MountSource::Archive(archive) => {
+                    if archive.contains(relative) {
+                        return None;
+                    }
+                },
+            }
+        }
+        None
+    }
+
+    // Writes go to the highest priority directory mount covering the path (archives
+    // are read-only), creating parent directories as needed.
+    pub fn write(&self, path: &str, contents: &[u8]) -> Result<(), AssetError> {
+        let host_path = if Path::new(path).is_absolute() {
+            PathBuf::from(path)
+        } else {
+            self.mounts.iter().rev()
+                .find_map(|mount| match (&mount.source, mount.relative(path)) {
+                    (MountSource::Directory(dir), Some(relative)) => Some(dir.join(relative)),
+                    _ => None,
+                })
+                .unwrap_or_else(|| PathBuf::from(path))
+        };
+
+        if let Some(parent) = host_path.parent().filter(|parent| !parent.as_os_str().is_empty()) {
+            std::fs::create_dir_all(parent).map_err(|e| io_error(path, e))?;
+        }
+        std::fs::write(&host_path, contents).map_err(|e| io_error(path, e))
+    }
+}
+
+fn io_error(path: &str, error: std::io::Error) -> AssetError {
+    AssetError::Io { path: path.to_string(), message: error.to_string() }
+}
+
+static VFS: OnceLock<Vfs> = OnceLock::new();
+
+// Install the mounts for this run. Only the first call has any effect.
+pub fn init(vfs: Vfs) {
+    let _ = VFS.set(vfs);
+}
+
+pub fn get() -> &'static Vfs {
+    VFS.get_or_init(Vfs::from_environment)
+}
+
+// Canonical virtual path: forward slashes, no "." or ".." segments, and rooted
+// under assets/. Absolute host paths are passed through untouched.
+pub fn normalize(path: &str) -> String {
+    if Path::new(path).is_absolute() {
+        return path.to_string();
+    }
+
+    let mut segments: Vec<&str> = Vec::new();
+    for segment in path.split(['/', '\\']) {
+        match segment {
+            "" | "." => {},
+            ".." => {
+                segments.pop();
+            },
+            segment => segments.push(segment),
+        }
+    }
+
+    if segments.first() != Some(&ASSET_ROOT) {
+        segments.insert(0, ASSET_ROOT);
+    }
+    segments.join("/")
+}
+
+pub fn read(path: &str) -> Result<Vec<u8>, AssetError> {
+    get().read(&normalize(path))
+}
+
+pub fn read_to_string(path: &str) -> Result<String, AssetError> {
+    get().read_to_string(&normalize(path))
+}
+
+pub fn exists(path: &str) -> bool {
+    get().exists(&normalize(path))
+}
+
+pub fn list(dir: &str) -> Vec<String> {
+    get().list(&normalize(dir))
+}
+
+pub fn modified(path: &str) -> Option<SystemTime> {
+    get().modified(&normalize(path))
+}
+
+pub fn write(path: &str, contents: &[u8]) -> Result<(), AssetError> {
+    get().write(&normalize(path), contents)
+}
+
+// `pack <output.pak> [source_dir]` - bundle the asset directory into one archive
+pub fn run_pack_cli(args: &[String]) -> Result<(), String> {
+    let output = args.first().map(String::as_str).unwrap_or(DEFAULT_ARCHIVE);
+    let source = args.get(1).map(String::as_str).unwrap_or(ASSET_ROOT);
+
+    let stats = archive::pack(Path::new(source), Path::new(output)).map_err(|e| e.to_string())?;
+    println!(
+        "Packed {} files ({} compressed) from {} into {}: {} -> {} bytes",
+        stats.files, stats.compressed_files, source, output, stats.original_bytes, stats.stored_bytes
+    );
+    Ok(())
+}